@@ -0,0 +1,357 @@
+/// This module implements the Ex addresses and ranges that can prefix
+/// a prompt command (eg: ``:.,$d``, ``:'a,'b>``, ``:/foo/+1``).
+///
+/// Parsing and resolution are split in two steps: an ``AddressRange`` is
+/// first parsed out of the command string, without any knowledge of the
+/// document, and is then resolved into a ``LineRange`` of line numbers,
+/// with respect to the current line, the marks and the document content.
+use crate::{Document, LineNumber, RowIndex};
+use std::cmp;
+use std::convert::TryFrom;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AddressBase {
+    /// ``.``, or an address only made of offsets, such as ``+3``
+    CurrentLine,
+    /// ``$``
+    LastLine,
+    /// ``42``
+    Number(usize),
+    /// ``'a``
+    Mark(char),
+    /// ``/pattern/``
+    ForwardSearch(String),
+    /// ``?pattern?``
+    BackwardSearch(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Address {
+    pub base: AddressBase,
+    pub offset: isize,
+}
+
+impl Address {
+    #[must_use]
+    pub fn new(base: AddressBase, offset: isize) -> Self {
+        Self { base, offset }
+    }
+
+    /// Resolve the address into a line number. Line 0 is a valid result, as it
+    /// can be used as a destination by commands such as ``:m`` or ``:t``.
+    ///
+    /// # Errors
+    /// Returns an error if a mark is not set, if a pattern can't be found, or if
+    /// the offset takes the address before the start of the document.
+    pub fn resolve(
+        &self,
+        document: &Document,
+        current_line_number: LineNumber,
+    ) -> Result<LineNumber, String> {
+        let base = match &self.base {
+            AddressBase::CurrentLine => current_line_number,
+            AddressBase::LastLine => document.last_line_number(),
+            AddressBase::Number(n) => LineNumber::new(*n),
+            AddressBase::Mark(mark) => *document
+                .marks()
+                .get(mark)
+                .ok_or_else(|| format!("Mark '{mark}' not set"))?,
+            AddressBase::ForwardSearch(pattern) => {
+                Self::search(document, current_line_number, pattern, true)?
+            }
+            AddressBase::BackwardSearch(pattern) => {
+                Self::search(document, current_line_number, pattern, false)?
+            }
+        };
+        let line_number = if self.offset >= 0 {
+            base.add(self.offset.unsigned_abs())
+        } else if base.value >= self.offset.unsigned_abs() {
+            base.sub(self.offset.unsigned_abs())
+        } else {
+            return Err(String::from("Invalid range"));
+        };
+        Ok(line_number)
+    }
+
+    /// Return the number of the first line containing the pattern, starting the
+    /// search after (or before) the current line and wrapping around the document.
+    fn search(
+        document: &Document,
+        current_line_number: LineNumber,
+        pattern: &str,
+        forward: bool,
+    ) -> Result<LineNumber, String> {
+        let num_rows = document.num_rows();
+        if pattern.is_empty() || num_rows == 0 {
+            return Err(String::from("Empty search pattern"));
+        }
+        let current_row_index = RowIndex::from(current_line_number).value;
+        for delta in 1..=num_rows {
+            let row_index = if forward {
+                (current_row_index + delta) % num_rows
+            } else {
+                (current_row_index + num_rows - (delta % num_rows)) % num_rows
+            };
+            if let Some(row) = document.get_row(RowIndex::new(row_index)) {
+                if row.contains(pattern) {
+                    return Ok(LineNumber::from(RowIndex::new(row_index)));
+                }
+            }
+        }
+        Err(format!("Pattern not found: {pattern}"))
+    }
+}
+
+/// An unresolved range, made of one or two addresses.
+///
+/// When the addresses are separated by ``;`` instead of ``,``, the cursor is
+/// virtually moved to the first address before the second one gets resolved.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AddressRange {
+    pub start: Address,
+    pub end: Option<Address>,
+    pub start_is_current_line: bool,
+}
+
+impl AddressRange {
+    /// Resolve both addresses into a ``LineRange``. When only one address was
+    /// provided, the range spans that single line.
+    ///
+    /// # Errors
+    /// Returns an error if any of the range addresses can't be resolved.
+    pub fn resolve(
+        &self,
+        document: &Document,
+        current_line_number: LineNumber,
+    ) -> Result<LineRange, String> {
+        let start = self.start.resolve(document, current_line_number)?;
+        let end = match &self.end {
+            None => start,
+            Some(end) => {
+                let relative_to = if self.start_is_current_line {
+                    start
+                } else {
+                    current_line_number
+                };
+                end.resolve(document, relative_to)?
+            }
+        };
+        Ok(LineRange::new(start, end))
+    }
+}
+
+/// A resolved range of lines, both ends included.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LineRange {
+    pub start: LineNumber,
+    pub end: LineNumber,
+}
+
+impl LineRange {
+    /// Create a new range, swapping its boundaries if they are backwards.
+    /// Line 0 is considered as being line 1, as Vim does.
+    #[must_use]
+    pub fn new(start: LineNumber, end: LineNumber) -> Self {
+        let start = cmp::max(start, LineNumber::new(1));
+        let end = cmp::max(end, LineNumber::new(1));
+        if start <= end {
+            Self { start, end }
+        } else {
+            Self {
+                start: end,
+                end: start,
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn single(line_number: LineNumber) -> Self {
+        Self::new(line_number, line_number)
+    }
+
+    /// Return the number of lines spanned by the range
+    #[must_use]
+    pub fn num_lines(&self) -> usize {
+        self.end
+            .value
+            .saturating_sub(self.start.value)
+            .saturating_add(1)
+    }
+
+    /// Return whether the range only targets existing lines of the document
+    #[must_use]
+    pub fn is_within(&self, document: &Document) -> bool {
+        self.end <= document.last_line_number()
+    }
+
+    /// Return the ``RowIndex`` of the first line in the range
+    #[must_use]
+    pub fn start_row_index(&self) -> RowIndex {
+        RowIndex::from(self.start)
+    }
+
+    /// Return the ``RowIndex`` of the last line in the range
+    #[must_use]
+    pub fn end_row_index(&self) -> RowIndex {
+        RowIndex::from(self.end)
+    }
+}
+
+/// Parse the optional range at the start of a prompt command and return it
+/// along with the rest of the command.
+///
+/// # Errors
+/// Returns an error if the range is malformed (eg: ``'`` not followed by a mark name).
+pub fn parse_range(input: &str) -> Result<(Option<AddressRange>, &str), String> {
+    let mut chars = input.char_indices().peekable();
+    skip_whitespace(&mut chars);
+    if let Some((_, '%')) = chars.peek() {
+        chars.next();
+        let range = AddressRange {
+            start: Address::new(AddressBase::Number(1), 0),
+            end: Some(Address::new(AddressBase::LastLine, 0)),
+            start_is_current_line: false,
+        };
+        return Ok((Some(range), rest(input, &mut chars)));
+    }
+    let start = parse_address(&mut chars)?;
+    skip_whitespace(&mut chars);
+    let separator = match chars.peek() {
+        Some(&(_, c)) if c == ',' || c == ';' => {
+            chars.next();
+            Some(c)
+        }
+        _ => None,
+    };
+    let range = match (start, separator) {
+        (None, None) => None,
+        (Some(start), None) => Some(AddressRange {
+            start,
+            end: None,
+            start_is_current_line: false,
+        }),
+        (start, Some(separator)) => {
+            skip_whitespace(&mut chars);
+            let end = parse_address(&mut chars)?;
+            Some(AddressRange {
+                start: start.unwrap_or_else(|| Address::new(AddressBase::CurrentLine, 0)),
+                end: Some(end.unwrap_or_else(|| Address::new(AddressBase::CurrentLine, 0))),
+                start_is_current_line: separator == ';',
+            })
+        }
+    };
+    Ok((range, rest(input, &mut chars)))
+}
+
+/// Return the part of the input that hasn't been consumed by the iterator yet
+fn rest<'a>(input: &'a str, chars: &mut Peekable<CharIndices>) -> &'a str {
+    match chars.peek() {
+        Some(&(i, _)) => &input[i..],
+        None => "",
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<CharIndices>) {
+    while let Some((_, ' ')) = chars.peek() {
+        chars.next();
+    }
+}
+
+fn parse_number(chars: &mut Peekable<CharIndices>) -> Result<Option<usize>, String> {
+    let mut digits = String::new();
+    while let Some(&(_, c)) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    if digits.is_empty() {
+        return Ok(None);
+    }
+    digits
+        .parse::<usize>()
+        .map(Some)
+        .map_err(|_| format!("Invalid address '{digits}'"))
+}
+
+/// Parse a search pattern, up to its closing delimiter (or the end of the input)
+fn parse_pattern(chars: &mut Peekable<CharIndices>, delimiter: char) -> String {
+    let mut pattern = String::new();
+    while let Some((_, c)) = chars.next() {
+        if c == delimiter {
+            break;
+        }
+        if c == '\\' {
+            if let Some(&(_, escaped)) = chars.peek() {
+                if escaped == delimiter {
+                    chars.next();
+                    pattern.push(escaped);
+                    continue;
+                }
+            }
+        }
+        pattern.push(c);
+    }
+    pattern
+}
+
+/// Parse a single address, made of an optional base and any number of offsets
+fn parse_address(chars: &mut Peekable<CharIndices>) -> Result<Option<Address>, String> {
+    let base = match chars.peek() {
+        Some(&(_, '.')) => {
+            chars.next();
+            Some(AddressBase::CurrentLine)
+        }
+        Some(&(_, '$')) => {
+            chars.next();
+            Some(AddressBase::LastLine)
+        }
+        Some(&(_, '\'')) => {
+            chars.next();
+            match chars.next() {
+                Some((_, mark)) if mark.is_ascii_alphabetic() => Some(AddressBase::Mark(mark)),
+                // the selection marks are set by visual mode, which bo doesn't have yet
+                Some((_, '<' | '>')) => return Err(String::from("No visual selection")),
+                _ => return Err(String::from("Invalid mark name")),
+            }
+        }
+        Some(&(_, '/')) => {
+            chars.next();
+            Some(AddressBase::ForwardSearch(parse_pattern(chars, '/')))
+        }
+        Some(&(_, '?')) => {
+            chars.next();
+            Some(AddressBase::BackwardSearch(parse_pattern(chars, '?')))
+        }
+        Some(&(_, c)) if c.is_ascii_digit() => parse_number(chars)?.map(AddressBase::Number),
+        _ => None,
+    };
+
+    let mut offset: isize = 0;
+    let mut has_offset = false;
+    while let Some(&(_, c)) = chars.peek() {
+        let sign: isize = match c {
+            '+' => 1,
+            '-' => -1,
+            _ => break,
+        };
+        chars.next();
+        has_offset = true;
+        let n = parse_number(chars)?.unwrap_or(1);
+        let n = isize::try_from(n).map_err(|_| String::from("Invalid range"))?;
+        offset = offset.saturating_add(sign.saturating_mul(n));
+    }
+
+    match base {
+        Some(base) => Ok(Some(Address::new(base, offset))),
+        None if has_offset => Ok(Some(Address::new(AddressBase::CurrentLine, offset))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+#[path = "./address_test.rs"]
+mod address_test;
@@ -0,0 +1,194 @@
+use crate::address::{parse_range, Address, AddressBase, AddressRange, LineRange};
+use crate::{Document, LineNumber, Row};
+use std::path::PathBuf;
+
+fn test_document() -> Document {
+    Document::new(
+        vec![
+            Row::from("first"),
+            Row::from("second"),
+            Row::from("third"),
+            Row::from("fourth"),
+            Row::from("fifth"),
+        ],
+        PathBuf::from("test.txt"),
+    )
+}
+
+fn resolve(input: &str, current_line: usize) -> Result<LineRange, String> {
+    let mut document = test_document();
+    document.set_mark('a', LineNumber::new(2));
+    let (range, _) = parse_range(input)?;
+    range
+        .unwrap()
+        .resolve(&document, LineNumber::new(current_line))
+}
+
+#[test]
+fn test_parse_range_without_range() {
+    assert_eq!(parse_range("q"), Ok((None, "q")));
+    assert_eq!(parse_range(""), Ok((None, "")));
+}
+
+#[test]
+fn test_parse_range_single_address() {
+    assert_eq!(
+        parse_range("42"),
+        Ok((
+            Some(AddressRange {
+                start: Address::new(AddressBase::Number(42), 0),
+                end: None,
+                start_is_current_line: false,
+            }),
+            ""
+        ))
+    );
+    assert_eq!(
+        parse_range(".+3d"),
+        Ok((
+            Some(AddressRange {
+                start: Address::new(AddressBase::CurrentLine, 3),
+                end: None,
+                start_is_current_line: false,
+            }),
+            "d"
+        ))
+    );
+    assert_eq!(
+        parse_range("$-2-1y"),
+        Ok((
+            Some(AddressRange {
+                start: Address::new(AddressBase::LastLine, -3),
+                end: None,
+                start_is_current_line: false,
+            }),
+            "y"
+        ))
+    );
+    assert_eq!(
+        parse_range("/wor\\/ld/+j"),
+        Ok((
+            Some(AddressRange {
+                start: Address::new(AddressBase::ForwardSearch(String::from("wor/ld")), 1),
+                end: None,
+                start_is_current_line: false,
+            }),
+            "j"
+        ))
+    );
+}
+
+#[test]
+fn test_parse_range_two_addresses() {
+    assert_eq!(
+        parse_range("'a,'bnormal Ax"),
+        Ok((
+            Some(AddressRange {
+                start: Address::new(AddressBase::Mark('a'), 0),
+                end: Some(Address::new(AddressBase::Mark('b'), 0)),
+                start_is_current_line: false,
+            }),
+            "normal Ax"
+        ))
+    );
+    assert_eq!(
+        parse_range("%d"),
+        Ok((
+            Some(AddressRange {
+                start: Address::new(AddressBase::Number(1), 0),
+                end: Some(Address::new(AddressBase::LastLine, 0)),
+                start_is_current_line: false,
+            }),
+            "d"
+        ))
+    );
+    assert_eq!(
+        parse_range(",+2"),
+        Ok((
+            Some(AddressRange {
+                start: Address::new(AddressBase::CurrentLine, 0),
+                end: Some(Address::new(AddressBase::CurrentLine, 2)),
+                start_is_current_line: false,
+            }),
+            ""
+        ))
+    );
+}
+
+#[test]
+fn test_parse_range_invalid_mark() {
+    assert!(parse_range("'").is_err());
+    assert!(parse_range("'!d").is_err());
+}
+
+#[test]
+fn test_resolve_range() {
+    assert_eq!(resolve("2", 1), Ok(LineRange::single(LineNumber::new(2))));
+    assert_eq!(
+        resolve(".,$", 3),
+        Ok(LineRange::new(LineNumber::new(3), LineNumber::new(5)))
+    );
+    assert_eq!(
+        resolve("%", 3),
+        Ok(LineRange::new(LineNumber::new(1), LineNumber::new(5)))
+    );
+    assert_eq!(
+        resolve("'a,.+1", 3),
+        Ok(LineRange::new(LineNumber::new(2), LineNumber::new(4)))
+    );
+    // backwards ranges are swapped
+    assert_eq!(
+        resolve("4,2", 1),
+        Ok(LineRange::new(LineNumber::new(2), LineNumber::new(4)))
+    );
+    // ';' moves the current line to the first address
+    assert_eq!(
+        resolve("2;+2", 5),
+        Ok(LineRange::new(LineNumber::new(2), LineNumber::new(4)))
+    );
+    assert_eq!(
+        resolve("2,+2", 1),
+        Ok(LineRange::new(LineNumber::new(2), LineNumber::new(3)))
+    );
+}
+
+#[test]
+fn test_resolve_search_address() {
+    // the search starts after the current line and wraps around
+    assert_eq!(
+        resolve("/fi/", 1),
+        Ok(LineRange::single(LineNumber::new(5)))
+    );
+    assert_eq!(
+        resolve("/fi/", 5),
+        Ok(LineRange::single(LineNumber::new(1)))
+    );
+    assert_eq!(
+        resolve("?th?", 5),
+        Ok(LineRange::single(LineNumber::new(4)))
+    );
+    assert_eq!(
+        resolve("/nope/", 1),
+        Err(String::from("Pattern not found: nope"))
+    );
+}
+
+#[test]
+fn test_resolve_errors() {
+    assert_eq!(resolve("'b", 1), Err(String::from("Mark 'b' not set")));
+    assert_eq!(resolve(".-3", 2), Err(String::from("Invalid range")));
+    assert_eq!(
+        parse_range("'<,'>d"),
+        Err(String::from("No visual selection"))
+    );
+    assert_eq!(parse_range("'1d"), Err(String::from("Invalid mark name")));
+}
+
+#[test]
+fn test_line_range() {
+    let range = LineRange::new(LineNumber::new(0), LineNumber::new(3));
+    assert_eq!(range.start, LineNumber::new(1));
+    assert_eq!(range.num_lines(), 3);
+    assert!(range.is_within(&test_document()));
+    assert!(!LineRange::single(LineNumber::new(6)).is_within(&test_document()));
+}
@@ -1,25 +1,565 @@
-pub const QUIT: &str = "q";
-pub const FORCE_QUIT: &str = "q!";
-pub const LINE_NUMBERS: &str = "ln";
-pub const STATS: &str = "stats";
-pub const HELP: &str = "help";
-pub const OPEN: &str = "open";
-pub const OPEN_SHORT: &str = "o";
-pub const NEW: &str = "new";
-pub const SAVE: &str = "w";
-pub const SAVE_AND_QUIT: &str = "wq";
-pub const DEBUG: &str = "debug";
-
-pub const ALL_COMMANDS: [&str; 11] = [
-    QUIT,
-    FORCE_QUIT,
-    LINE_NUMBERS,
-    STATS,
-    HELP,
-    OPEN,
-    OPEN_SHORT,
-    NEW,
-    SAVE,
-    SAVE_AND_QUIT,
-    DEBUG,
+/// This module defines the registry of all prompt commands. Each command is
+/// described once, along with the arguments, range and bang it accepts, the
+/// ``Editor`` method handling it and its description. Command parsing and
+/// validation, autocompletion and the help screen are all derived from it.
+use crate::address::{self, AddressRange, LineRange};
+use crate::editor::Editor;
+use std::fmt;
+
+/// The kind of value a command argument holds, which drives its autocompletion
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArgumentKind {
+    Filename,
+    Register,
+    Address,
+    Keys,
+    Mark,
+    Mapping,
+    Option,
+    Number,
+    Highlight,
+    Suggestion,
+}
+
+impl fmt::Display for ArgumentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArgumentKind::Filename => write!(f, "filename"),
+            ArgumentKind::Register | ArgumentKind::Mark => write!(f, "x"),
+            ArgumentKind::Address => write!(f, "address"),
+            ArgumentKind::Keys => write!(f, "keys"),
+            ArgumentKind::Mapping => write!(f, "lhs rhs"),
+            ArgumentKind::Option => write!(f, "option[=value]"),
+            ArgumentKind::Number => write!(f, "n"),
+            ArgumentKind::Highlight => write!(f, "group color [bold]"),
+            ArgumentKind::Suggestion => write!(f, "word"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArgumentSpec {
+    None,
+    Optional(ArgumentKind),
+    Required(ArgumentKind),
+}
+
+impl ArgumentSpec {
+    #[must_use]
+    pub fn kind(self) -> Option<ArgumentKind> {
+        match self {
+            ArgumentSpec::None => None,
+            ArgumentSpec::Optional(kind) | ArgumentSpec::Required(kind) => Some(kind),
+        }
+    }
+}
+
+/// Whether a command accepts a range, and which lines it operates on when none is given
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RangeSpec {
+    /// The command does not accept any range
+    None,
+    /// The command accepts a range, but does not default to one
+    Optional,
+    /// The command operates on the current line by default
+    CurrentLine,
+    /// The command operates on the current and next lines by default
+    CurrentAndNextLine,
+}
+
+/// The arguments a command handler gets called with, once its range has been resolved
+#[derive(Debug, PartialEq)]
+pub struct Invocation {
+    pub range: Option<LineRange>,
+    pub bang: bool,
+    /// Number of times the command name was repeated (eg: 2 for ``:>>``)
+    pub repeat: usize,
+    pub arguments: String,
+}
+
+pub type Handler = fn(&mut Editor, &Invocation);
+
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub argument: ArgumentSpec,
+    pub range: RangeSpec,
+    pub bang: bool,
+    /// Whether the name can be repeated to repeat the command (eg: ``:>>``)
+    pub repeatable: bool,
+    pub handler: Handler,
+    pub description: &'static str,
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Command {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Command {
+    /// Return whether the command can be invoked with the provided name
+    #[must_use]
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+
+    /// Return the command usage, as displayed in the help screen (eg: ``[range]d [x]``)
+    #[must_use]
+    pub fn synopsis(&self) -> String {
+        let range = if self.range == RangeSpec::None {
+            ""
+        } else {
+            "[range]"
+        };
+        let mut names = vec![self.name];
+        names.extend(self.aliases);
+        let bang = if self.bang { "[!]" } else { "" };
+        let argument = match self.argument {
+            ArgumentSpec::None => String::new(),
+            ArgumentSpec::Optional(kind) => format!(" [{kind}]"),
+            ArgumentSpec::Required(kind) => format!(" <{kind}>"),
+        };
+        format!("{range}{}{bang}{argument}", names.join("/"))
+    }
+}
+
+/// A command parsed out of the prompt, which range hasn't been resolved yet
+#[derive(Debug, PartialEq)]
+pub struct ParsedCommand<'a> {
+    pub command: &'static Command,
+    pub range: Option<AddressRange>,
+    pub bang: bool,
+    pub repeat: usize,
+    pub arguments: &'a str,
+}
+
+/// What the user typed in the command prompt
+#[derive(Debug, PartialEq)]
+pub enum ParsedPrompt<'a> {
+    /// Nothing but the prompt prefix
+    Empty,
+    /// A range without any command (eg: ``:42``), moving the cursor to its last line
+    Range(AddressRange),
+    Command(ParsedCommand<'a>),
+}
+
+pub const COMMANDS: [Command; 31] = [
+    Command {
+        name: "q",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: true,
+        repeatable: false,
+        handler: Editor::quit_command,
+        description: "quit bo (q! discards unsaved changes)",
+    },
+    Command {
+        name: "ln",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::line_numbers_command,
+        description: "toggle line numbers",
+    },
+    Command {
+        name: "stats",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::stats_command,
+        description: "toggle line/word stats",
+    },
+    Command {
+        name: "focus",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::focus_command,
+        description: "toggle the distraction-free focus mode",
+    },
+    Command {
+        name: "lint",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::lint_command,
+        description: "list the style findings of the prose linter",
+    },
+    Command {
+        name: "help",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::help_command,
+        description: "display this help screen",
+    },
+    Command {
+        name: "open",
+        aliases: &["o", "e", "edit"],
+        argument: ArgumentSpec::Required(ArgumentKind::Filename),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::open_command,
+        description: "open a file, optionally with an encoding (ex: e ++enc=latin1 notes.txt)",
+    },
+    Command {
+        name: "new",
+        aliases: &[],
+        argument: ArgumentSpec::Required(ArgumentKind::Filename),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::new_command,
+        description: "open a new file",
+    },
+    Command {
+        name: "w",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Filename),
+        range: RangeSpec::Optional,
+        bang: false,
+        repeatable: false,
+        handler: Editor::save_command,
+        description: "save (under a new name), or write the lines in range to a file",
+    },
+    Command {
+        name: "wq",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::save_and_quit_command,
+        description: "save and quit",
+    },
+    Command {
+        name: "r",
+        aliases: &["read"],
+        argument: ArgumentSpec::Required(ArgumentKind::Filename),
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::read_command,
+        description: "insert the lines of a file below the last line in range",
+    },
+    Command {
+        name: "debug",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::debug_command,
+        description: "dump the editor state as JSON to bo.log",
+    },
+    Command {
+        name: "d",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Register),
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::delete_command,
+        description: "delete lines (into register x)",
+    },
+    Command {
+        name: "y",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Register),
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::yank_command,
+        description: "yank lines (into register x)",
+    },
+    Command {
+        name: "m",
+        aliases: &[],
+        argument: ArgumentSpec::Required(ArgumentKind::Address),
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::move_command,
+        description: "move lines below address",
+    },
+    Command {
+        name: "co",
+        aliases: &["t"],
+        argument: ArgumentSpec::Required(ArgumentKind::Address),
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::copy_command,
+        description: "copy lines below address",
+    },
+    Command {
+        name: ">",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: true,
+        handler: Editor::shift_right_command,
+        description: "indent lines (>> indents twice)",
+    },
+    Command {
+        name: "<",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: true,
+        handler: Editor::shift_left_command,
+        description: "dedent lines (<< dedents twice)",
+    },
+    Command {
+        name: "retab",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Number),
+        range: RangeSpec::Optional,
+        bang: true,
+        repeatable: false,
+        handler: Editor::retab_command,
+        description:
+            "convert the whitespace containing tabs to the new tab stop (all of it with !)",
+    },
+    Command {
+        name: "j",
+        aliases: &[],
+        argument: ArgumentSpec::None,
+        range: RangeSpec::CurrentAndNextLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::join_command,
+        description: "join lines",
+    },
+    Command {
+        name: "normal",
+        aliases: &[],
+        argument: ArgumentSpec::Required(ArgumentKind::Keys),
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::normal_command,
+        description: "execute normal commands on each line",
+    },
+    Command {
+        name: "mark",
+        aliases: &["k"],
+        argument: ArgumentSpec::Required(ArgumentKind::Mark),
+        range: RangeSpec::CurrentLine,
+        bang: false,
+        repeatable: false,
+        handler: Editor::mark_command,
+        description: "set mark x on the last line in range",
+    },
+    Command {
+        name: "map",
+        aliases: &["nmap"],
+        argument: ArgumentSpec::Optional(ArgumentKind::Mapping),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::map_command,
+        description: "map keys in normal mode (ex: map Q :q<CR>), or list mappings",
+    },
+    Command {
+        name: "imap",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Mapping),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::imap_command,
+        description: "map keys in insert mode (ex: imap jk <Esc>), or list mappings",
+    },
+    Command {
+        name: "cmap",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Mapping),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::cmap_command,
+        description: "map keys in the command prompt, or list mappings",
+    },
+    Command {
+        name: "noremap",
+        aliases: &["nnoremap"],
+        argument: ArgumentSpec::Optional(ArgumentKind::Mapping),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::noremap_command,
+        description: "map keys in normal mode, without remapping the mapped keys",
+    },
+    Command {
+        name: "inoremap",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Mapping),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::inoremap_command,
+        description: "map keys in insert mode, without remapping the mapped keys",
+    },
+    Command {
+        name: "cnoremap",
+        aliases: &[],
+        argument: ArgumentSpec::Optional(ArgumentKind::Mapping),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::cnoremap_command,
+        description: "map keys in the command prompt, without remapping the mapped keys",
+    },
+    Command {
+        name: "highlight",
+        aliases: &["hi"],
+        argument: ArgumentSpec::Optional(ArgumentKind::Highlight),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::highlight_command,
+        description:
+            "set the color of a highlight group (ex: hi Keyword blue bold), or display them",
+    },
+    Command {
+        name: "spellreplace",
+        aliases: &[],
+        argument: ArgumentSpec::Required(ArgumentKind::Suggestion),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::spellreplace_command,
+        description: "replace the word under the cursor, suggesting spelling corrections",
+    },
+    Command {
+        name: "set",
+        aliases: &[],
+        argument: ArgumentSpec::Required(ArgumentKind::Option),
+        range: RangeSpec::None,
+        bang: false,
+        repeatable: false,
+        handler: Editor::set_command,
+        description: "set an option (ex: set fileformat=dos), or display its value",
+    },
 ];
+
+/// Return every name a command can be invoked with, in registry order, used
+/// as command autocompletion suggestions
+#[must_use]
+pub fn all_command_names() -> Vec<String> {
+    let mut names = vec![];
+    for command in &COMMANDS {
+        names.push(command.name.to_string());
+        if command.bang {
+            names.push(format!("{}!", command.name));
+        }
+        names.extend(command.aliases.iter().map(|alias| (*alias).to_string()));
+    }
+    names
+}
+
+/// Return the command that can be invoked with the provided name, if any
+#[must_use]
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|command| command.is_named(name))
+}
+
+/// Split a prompt command (stripped from its range) into its name, bang and arguments.
+///
+/// The name is either made of letters or of a repeated shift symbol (eg: ``>>``), in
+/// which case the arguments can directly follow the name without any whitespace
+/// (eg: ``m0`` or ``t.``).
+#[must_use]
+pub fn split_command(command: &str) -> (&str, bool, &str) {
+    let command = command.trim_start();
+    let name_len = match command.chars().next() {
+        Some(c) if c == '>' || c == '<' => command.chars().take_while(|&ch| ch == c).count(),
+        Some(c) if c.is_ascii_alphabetic() => command
+            .chars()
+            .take_while(char::is_ascii_alphabetic)
+            .count(),
+        Some(c) => c.len_utf8(),
+        None => 0,
+    };
+    let (name, rest) = command.split_at(name_len);
+    match rest.strip_prefix('!') {
+        Some(arguments) => (name, true, arguments.trim()),
+        None => (name, false, rest.trim()),
+    }
+}
+
+/// Parse and validate a prompt command against the registry.
+///
+/// # Errors
+/// Returns an error if the command is unknown, or if it was provided a range, a bang
+/// or arguments that it does not accept.
+pub fn parse(input: &str) -> Result<ParsedPrompt<'_>, String> {
+    let (range, rest) = address::parse_range(input)?;
+    let (name, bang, arguments) = split_command(rest);
+    if name.is_empty() {
+        return Ok(match range {
+            Some(range) => ParsedPrompt::Range(range),
+            None => ParsedPrompt::Empty,
+        });
+    }
+    let first_char = name.chars().next().unwrap_or_default();
+    let (command, repeat) = match find(name) {
+        Some(command) => (command, 1),
+        None => match find(&first_char.to_string()) {
+            Some(command) if command.repeatable && name.chars().all(|c| c == first_char) => {
+                (command, name.chars().count())
+            }
+            _ => return Err(format!("Unknown command '{name}'")),
+        },
+    };
+    if range.is_some() && command.range == RangeSpec::None {
+        return Err(String::from("No range allowed"));
+    }
+    if bang && !command.bang {
+        return Err(String::from("No ! allowed"));
+    }
+    match command.argument {
+        ArgumentSpec::None if !arguments.is_empty() => {
+            return Err(String::from("Trailing characters"));
+        }
+        ArgumentSpec::Required(kind) if arguments.is_empty() => {
+            return Err(format!("Argument required: <{kind}>"));
+        }
+        _ => (),
+    }
+    Ok(ParsedPrompt::Command(ParsedCommand {
+        command,
+        range,
+        bang,
+        repeat,
+        arguments,
+    }))
+}
+
+#[cfg(test)]
+#[path = "./commands_test.rs"]
+mod commands_test;
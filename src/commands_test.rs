@@ -0,0 +1,68 @@
+use crate::commands::{all_command_names, find, parse, split_command, ParsedPrompt};
+
+#[test]
+fn test_split_command() {
+    assert_eq!(split_command("q"), ("q", false, ""));
+    assert_eq!(split_command("q!"), ("q", true, ""));
+    assert_eq!(split_command("w new.txt"), ("w", false, "new.txt"));
+    assert_eq!(split_command("  open a b"), ("open", false, "a b"));
+    assert_eq!(split_command("m0"), ("m", false, "0"));
+    assert_eq!(split_command("t."), ("t", false, "."));
+    assert_eq!(split_command(">>"), (">>", false, ""));
+    assert_eq!(split_command("<"), ("<", false, ""));
+    assert_eq!(split_command("normal Ahey"), ("normal", false, "Ahey"));
+    assert_eq!(split_command(""), ("", false, ""));
+}
+
+#[test]
+fn test_find_command_by_alias() {
+    assert_eq!(find("o"), find("open"));
+    assert_eq!(find("t").unwrap().name, "co");
+    assert!(find("derp").is_none());
+}
+
+#[test]
+fn test_synopsis() {
+    assert_eq!(find("d").unwrap().synopsis(), "[range]d [x]");
+    assert_eq!(find("open").unwrap().synopsis(), "open/o/e/edit <filename>");
+    assert_eq!(find("q").unwrap().synopsis(), "q[!]");
+}
+
+#[test]
+fn test_all_command_names() {
+    let names = all_command_names();
+    assert!(names.contains(&String::from("q!")));
+    assert!(names.contains(&String::from("o")));
+    assert!(!names.contains(&String::from("w!")));
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(parse(""), Ok(ParsedPrompt::Empty));
+    assert!(matches!(parse("42"), Ok(ParsedPrompt::Range(_))));
+    match parse("2,3>>>") {
+        Ok(ParsedPrompt::Command(parsed)) => {
+            assert_eq!(parsed.command.name, ">");
+            assert_eq!(parsed.repeat, 3);
+            assert!(parsed.range.is_some());
+        }
+        other => panic!("unexpected parse result {:?}", other),
+    }
+    match parse("q!") {
+        Ok(ParsedPrompt::Command(parsed)) => assert!(parsed.bang),
+        other => panic!("unexpected parse result {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse("derp"), Err(String::from("Unknown command 'derp'")));
+    assert_eq!(parse("2q"), Err(String::from("No range allowed")));
+    assert_eq!(parse("wq!"), Err(String::from("No ! allowed")));
+    assert_eq!(parse("ln foo"), Err(String::from("Trailing characters")));
+    assert_eq!(
+        parse("normal"),
+        Err(String::from("Argument required: <keys>"))
+    );
+    assert_eq!(parse("dd"), Err(String::from("Unknown command 'dd'")));
+}
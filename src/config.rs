@@ -1,7 +1,102 @@
-#[derive(Default, Debug)]
+use crate::highlighting::Theme;
+use crate::lint::{Linter, DEFAULT_SENTENCE_LENGTH};
+use crate::spell::Dictionary;
+use crate::utils;
+use std::path::PathBuf;
+
+/// Location of the config file, made of prompt commands executed at startup
+const CONFIG_FILE: &str = "~/.config/bo/config";
+
+/// Names of the options that can be changed with ``:set``
+pub const OPTION_NAMES: [&str; 18] = [
+    "expandtab",
+    "fileencoding",
+    "fileformat",
+    "filetype",
+    "focuswidth",
+    "lint",
+    "noexpandtab",
+    "nolint",
+    "nosentencecount",
+    "nospell",
+    "scrolloff",
+    "sentencecount",
+    "sentencelength",
+    "shiftwidth",
+    "spell",
+    "spellfile",
+    "spelllang",
+    "tabstop",
+];
+/// Default number of columns of a tab stop and of an indentation level
+const DEFAULT_TABSTOP: usize = 4;
+/// Default number of columns of the text column displayed in focus mode
+const DEFAULT_FOCUS_WIDTH: usize = 80;
+/// Default language of the spell checking dictionary
+const DEFAULT_SPELL_LANGUAGE: &str = "en_US";
+/// Directory of the personal dictionaries, named after their language
+const PERSONAL_DICTIONARY_DIRECTORY: &str = "~/.config/bo/spell";
+
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub display_line_numbers: bool,
     pub display_stats: bool,
+    /// Number of columns between two tab stops
+    pub tabstop: usize,
+    /// Number of columns of an indentation level
+    pub shiftwidth: usize,
+    /// Whether to indent with spaces instead of tabs
+    pub expandtab: bool,
+    /// Whether to display the number of sentences in the status bar
+    pub sentencecount: bool,
+    /// Minimum number of rows kept displayed above and below the cursor
+    pub scrolloff: usize,
+    /// Whether the distraction-free focus mode is enabled
+    pub focus: bool,
+    /// Number of columns of the text column displayed in focus mode
+    pub focuswidth: usize,
+    /// Styles of the syntax highlighting groups
+    pub theme: Theme,
+    /// Whether misspelled words are underlined
+    pub spell: bool,
+    /// Language of the spell checking dictionary (ex: ``en_US``)
+    pub spelllang: String,
+    /// File the words added with ``zg`` are written to, or an empty string to
+    /// use the personal dictionary of the language
+    pub spellfile: String,
+    /// Directories the spell checking dictionaries are looked up in
+    pub spell_directories: Vec<PathBuf>,
+    /// Whether the style findings of the prose linter are highlighted
+    pub lint: bool,
+    /// Number of words above which the linter flags a sentence as too long
+    pub sentencelength: usize,
+    /// Directory the user-defined rule sets of the linter are looked up in
+    pub lint_directory: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            display_line_numbers: false,
+            display_stats: false,
+            tabstop: DEFAULT_TABSTOP,
+            shiftwidth: DEFAULT_TABSTOP,
+            expandtab: true,
+            sentencecount: false,
+            scrolloff: 0,
+            focus: false,
+            focuswidth: DEFAULT_FOCUS_WIDTH,
+            theme: Theme::default(),
+            spell: false,
+            spelllang: DEFAULT_SPELL_LANGUAGE.to_string(),
+            spellfile: String::new(),
+            spell_directories: Dictionary::directories(),
+            lint: false,
+            sentencelength: DEFAULT_SENTENCE_LENGTH,
+            lint_directory: Linter::directory(),
+        }
+    }
 }
 
 impl Config {
@@ -9,6 +104,22 @@ impl Config {
     pub fn toggle(config: bool) -> bool {
         !config
     }
+
+    #[must_use]
+    pub fn file_path() -> PathBuf {
+        PathBuf::from(utils::expand_tilde(CONFIG_FILE))
+    }
+
+    /// Return the path of the personal dictionary the words added with ``zg`` are written to
+    #[must_use]
+    pub fn spellfile_path(&self) -> PathBuf {
+        if self.spellfile.is_empty() {
+            PathBuf::from(utils::expand_tilde(PERSONAL_DICTIONARY_DIRECTORY))
+                .join(format!("{}.add", self.spelllang))
+        } else {
+            PathBuf::from(utils::expand_tilde(&self.spellfile))
+        }
+    }
 }
 
 #[cfg(test)]
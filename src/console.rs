@@ -1,7 +1,9 @@
-use crate::{LineNumber, Position};
+use crate::frame::Style;
+use crate::{AnsiPosition, LineNumber, ScreenPosition};
+use std::cmp;
 use std::fmt::Debug;
 use std::io::Error;
-use termion::color;
+use std::time::Duration;
 use termion::event::{Event, MouseEvent};
 
 #[derive(Debug)]
@@ -48,9 +50,14 @@ pub trait Console: Debug {
     /// Will return an error if an event can't be read from the console input.
     fn read_event(&mut self) -> Result<Event, Error>;
 
-    fn clear_screen(&self);
+    /// Read the next event from the console input, or return ``None`` if no event
+    /// was received before the timeout expired.
+    ///
+    /// # Errors
+    /// Will return an error if an event can't be read from the console input.
+    fn read_event_timeout(&mut self, timeout: Duration) -> Result<Option<Event>, Error>;
 
-    fn clear_current_line(&self);
+    fn clear_screen(&self);
 
     /// # Errors
     /// Will return an error if the terminal can't be flushed
@@ -60,33 +67,88 @@ pub trait Console: Debug {
 
     fn show_cursor(&self);
 
-    fn set_bg_color(&self, color: color::Rgb);
-
-    fn reset_bg_color(&self);
-
-    fn set_fg_color(&self, color: color::Rgb);
+    fn clear_all(&self);
 
-    fn reset_fg_color(&self);
+    /// Move the cursor to the provided cell, with 0-based coordinates
+    fn move_cursor_to(&self, x: u16, y: u16);
 
-    fn to_alternate_screen(&self);
+    /// Write the text at the cursor position, with the current style
+    fn write(&self, text: &str);
 
-    fn to_main_screen(&self);
+    /// Set the style of the text written from now on
+    fn set_style(&self, style: Style);
 
-    fn clear_all(&self);
+    /// Scroll the lines located between the top and bottom ones (0-based, both
+    /// included) by the provided number of lines, upwards if positive and
+    /// downwards otherwise, leaving the rest of the screen untouched.
+    fn scroll_region(&self, top: u16, bottom: u16, lines: i32);
 
     fn size(&self) -> ConsoleSize;
 
-    fn text_area_size(&self) -> ConsoleSize;
+    fn text_area_size(&self) -> ConsoleSize {
+        self.size().restrict_to_text_area()
+    }
 
-    fn middle_of_screen_line_number(&self) -> LineNumber;
+    fn middle_of_screen_line_number(&self) -> LineNumber {
+        LineNumber::new(self.text_area_size().height as usize / 2)
+    }
 
-    fn bottom_of_screen_line_number(&self) -> LineNumber;
+    fn bottom_of_screen_line_number(&self) -> LineNumber {
+        LineNumber::new(self.text_area_size().height as usize)
+    }
 
-    fn get_cursor_index_from_mouse_event(&self, mouse_event: MouseEvent, x_offset: u8) -> Position;
+    fn get_cursor_index_from_mouse_event(
+        &self,
+        mouse_event: MouseEvent,
+        row_prefix_length: u8,
+    ) -> ScreenPosition {
+        if let MouseEvent::Press(_, x, y) = mouse_event {
+            let offset_adjustment: u8 = if row_prefix_length > 0 {
+                row_prefix_length.saturating_add(1)
+            } else {
+                0
+            };
+            let ansi_position = AnsiPosition {
+                x: x.saturating_sub(u16::from(offset_adjustment)),
+                y,
+            };
+            ScreenPosition::from(ansi_position)
+        } else {
+            ScreenPosition::top_left()
+        }
+    }
 
-    fn set_cursor_position_in_text_area(&self, position: &Position, row_prefix_length: u8);
+    fn set_cursor_position_in_text_area(
+        &self,
+        position: &ScreenPosition,
+        mut row_prefix_length: u8,
+    ) {
+        let ansi_position = AnsiPosition::from(*position);
+        // hiding the fact that the terminal position is 1-based, while preventing an overflow
+        row_prefix_length += u8::from(row_prefix_length > 0);
+        let text_area_size = self.size().restrict_to_text_area();
+        self.move_cursor_to(
+            cmp::min(
+                ansi_position.x.saturating_add(row_prefix_length.into()),
+                text_area_size.width,
+            )
+            .saturating_sub(1),
+            cmp::min(ansi_position.y, text_area_size.height).saturating_sub(1),
+        );
+    }
 
-    fn set_cursor_position_anywhere(&self, position: &Position);
+    fn set_cursor_position_anywhere(&self, position: &ScreenPosition) {
+        let ansi_position = AnsiPosition::from(*position);
+        let console_size = self.size();
+        self.move_cursor_to(
+            cmp::min(ansi_position.x, console_size.width).saturating_sub(1),
+            cmp::min(
+                ansi_position.y.saturating_add(2), // delta_y = 2 to account for the last 2 lines (status + message bars)
+                console_size.height,
+            )
+            .saturating_sub(1),
+        );
+    }
 
     fn set_cursor_as_steady_bar(&self);
 
@@ -0,0 +1,61 @@
+/// This module computes the number of terminal columns taken by text, as
+/// terminals display East Asian wide characters and most emoji over two
+/// columns, combining characters or joiners over none, and tabs up to the
+/// next tab stop.
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Return the number of columns taken by a grapheme cluster, between 0 and 2.
+/// Emoji presentation and ZWJ sequences (ex: a family emoji) are displayed as
+/// a single wide emoji.
+#[must_use]
+pub fn grapheme_width(grapheme: &str) -> usize {
+    if grapheme.len() == 1 {
+        // ASCII fast path, control characters included
+        return 1;
+    }
+    grapheme.width().min(2)
+}
+
+/// Return the number of columns taken by a grapheme displayed at the provided
+/// column, a tab extending up to the next multiple of ``tabstop``
+#[must_use]
+pub fn grapheme_width_at(grapheme: &str, column: usize, tabstop: usize) -> usize {
+    if grapheme == "\t" {
+        let tabstop = tabstop.max(1);
+        tabstop - column % tabstop
+    } else {
+        grapheme_width(grapheme)
+    }
+}
+
+/// Return the whitespace spanning from the start column to the end one, made
+/// of tabs reaching as many tab stops as possible followed by spaces, or of
+/// spaces only if ``expandtab`` is set
+#[must_use]
+pub fn whitespace(start: usize, end: usize, tabstop: usize, expandtab: bool) -> String {
+    let mut whitespace = String::new();
+    let mut column = start;
+    if !expandtab {
+        let tabstop = tabstop.max(1);
+        while (column / tabstop + 1) * tabstop <= end {
+            whitespace.push('\t');
+            column = (column / tabstop + 1) * tabstop;
+        }
+    }
+    whitespace.push_str(&" ".repeat(end.saturating_sub(column)));
+    whitespace
+}
+
+/// Return the number of columns taken by the text, tabs excluded
+#[must_use]
+pub fn str_width(s: &str) -> usize {
+    if s.is_ascii() {
+        return s.len();
+    }
+    s.graphemes(true).map(grapheme_width).sum()
+}
+
+#[cfg(test)]
+#[path = "./display_width_test.rs"]
+mod display_width_test;
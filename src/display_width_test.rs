@@ -0,0 +1,22 @@
+use crate::display_width::{grapheme_width, str_width};
+
+#[test]
+fn test_grapheme_width() {
+    assert_eq!(grapheme_width("a"), 1);
+    assert_eq!(grapheme_width("e\u{301}"), 1); // e + combining acute accent
+    assert_eq!(grapheme_width("ｱ"), 1); // halfwidth katakana
+    assert_eq!(grapheme_width("中"), 2);
+    assert_eq!(grapheme_width("Ａ"), 2); // fullwidth latin
+    assert_eq!(grapheme_width("😀"), 2);
+    assert_eq!(grapheme_width("❤\u{fe0f}"), 2); // emoji presentation selector
+    assert_eq!(grapheme_width("👨\u{200d}👩\u{200d}👧"), 2); // ZWJ sequence
+    assert_eq!(grapheme_width("\u{200b}"), 0); // zero width space
+}
+
+#[test]
+fn test_str_width() {
+    assert_eq!(str_width("Hello"), 5);
+    assert_eq!(str_width("Hellö"), 5);
+    assert_eq!(str_width("日本語 text"), 11);
+    assert_eq!(str_width("a👨\u{200d}👩\u{200d}👧b"), 4);
+}
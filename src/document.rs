@@ -1,60 +1,97 @@
-use crate::{LineNumber, Row, RowIndex};
+use crate::filetype::{Filetype, MODELINE_LINES};
+use crate::highlighting::{Highlighter, Span};
+use crate::mapped_file::{self, MappedFile, MAPPED_FILE_MIN_SIZE};
+use crate::rope::{self, Piece, Rope};
+use crate::{Encoding, FileFormat, GraphemeIndex, LineNumber, Row, RowIndex};
 use serde::Serialize;
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
 use std::hash::{Hash, Hasher};
-use std::io::{Error, Write};
+use std::io::{BufWriter, Error, ErrorKind, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::slice::{Iter, IterMut};
 use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Serialize)]
 pub struct Document {
-    rows: Vec<Row>,
+    rows: Rope,
     pub filename: Option<PathBuf>,
+    pub format: FileFormat,
+    /// Type of the file, detected when it's opened or renamed
+    #[serde(skip)]
+    pub filetype: Option<Filetype>,
+    /// File mapped in memory, whose lines are still being indexed
+    #[serde(skip)]
+    mapped_file: Option<MappedFile>,
+    /// Syntax highlighting of the rows, invalidated from the first modified one
+    #[serde(skip)]
+    highlighter: Highlighter,
+    /// Lines marked with ``:mark``, following the lines as rows are inserted and removed
+    #[serde(skip)]
+    marks: HashMap<char, LineNumber>,
 }
 
 impl fmt::Debug for Document {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct(self.filename.as_ref().unwrap().to_str().unwrap_or_default())
-            .finish()
+            .finish_non_exhaustive()
     }
 }
 
 impl Default for Document {
     fn default() -> Self {
         Self {
-            rows: vec![Row::from("")],
+            rows: Rope::from(vec![Row::from("")]),
             filename: None,
+            format: FileFormat::default(),
+            mapped_file: None,
+            filetype: None,
+            highlighter: Highlighter::default(),
+            marks: HashMap::new(),
         }
     }
 }
 
 impl Hash for Document {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        for row in self.iter() {
-            row.hash(state);
-        }
+        self.rows.len().hash(state);
+        self.rows.hashed().hash(state);
+        self.format.hash(state);
     }
 }
 
 impl Document {
     #[must_use]
     pub fn new(rows: Vec<Row>, filename: PathBuf) -> Self {
-        Self {
-            rows,
+        let mut document = Self {
+            rows: Rope::from(rows),
             filename: Some(filename),
-        }
+            format: FileFormat::default(),
+            mapped_file: None,
+            filetype: None,
+            highlighter: Highlighter::default(),
+            marks: HashMap::new(),
+        };
+        document.detect_filetype();
+        document
     }
 
     #[must_use]
     pub fn new_empty(filename: PathBuf) -> Self {
-        Self {
-            rows: vec![Row::from("")],
+        let mut document = Self {
+            rows: Rope::from(vec![Row::from("")]),
             filename: Some(filename),
-        }
+            format: FileFormat::default(),
+            mapped_file: None,
+            filetype: None,
+            highlighter: Highlighter::default(),
+            marks: HashMap::new(),
+        };
+        document.detect_filetype();
+        document
     }
 
     /// # Panics
@@ -70,47 +107,235 @@ impl Document {
         PathBuf::from(out)
     }
 
+    /// Return the name of the file the document is written to, before replacing
+    /// the target file
+    fn temporary_filename(filename: &Path) -> PathBuf {
+        let mut temporary_filename = Self::swap_filename(filename);
+        temporary_filename.set_extension("tmp");
+        temporary_filename
+    }
+
     /// # Errors
     /// # Panics
     /// Returns an error if a file bearing the provided filename
     /// cannot be open.
     pub fn open(filename: PathBuf) -> Result<Self, Error> {
+        Self::open_with_encoding(filename, None)
+    }
+
+    /// Open the file, decoding it with the provided encoding, or with the detected
+    /// one if none is provided.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, or if its content is invalid
+    /// in the encoding.
+    pub fn open_with_encoding(
+        filename: PathBuf,
+        encoding: Option<Encoding>,
+    ) -> Result<Self, Error> {
         if !filename.is_file() {
-            return Ok(Self::new_empty(filename));
+            let mut document = Self::new_empty(filename);
+            document.format.encoding = encoding.unwrap_or(Encoding::Utf8);
+            return Ok(document);
         }
-        let file_contents = if (&Self::swap_filename(&filename)).is_file() {
-            fs::read_to_string(Self::swap_filename(&filename))?
+        let bytes = if Self::swap_filename(&filename).is_file() {
+            fs::read(Self::swap_filename(&filename))?
+        } else if encoding.is_none()
+            && fs::metadata(&filename)?.len() >= MAPPED_FILE_MIN_SIZE
+            && mapped_file::looks_like_utf8(&filename)?
+        {
+            // files in other encodings are decoded up front
+            return Self::open_mapped(filename);
         } else {
-            fs::read_to_string(&filename)?
+            fs::read(&filename)?
+        };
+        let encoding = encoding.unwrap_or_else(|| Encoding::detect(&bytes));
+        let file_contents = encoding
+            .decode(&bytes)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+        let (mut format, lines) = FileFormat::detect(&file_contents);
+        format.encoding = encoding;
+        let rows: Vec<Row> = lines.into_iter().map(Row::from).collect();
+        let mut document = Self {
+            rows: Rope::from(rows),
+            filename: Some(filename),
+            format,
+            mapped_file: None,
+            filetype: None,
+            highlighter: Highlighter::default(),
+            marks: HashMap::new(),
         };
+        document.detect_filetype();
+        Ok(document)
+    }
 
-        let mut rows = Vec::new();
-        for line in file_contents.lines() {
-            rows.push(Row::from(line));
+    /// Read the lines of the file, decoded with its detected encoding
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, or if its content is invalid
+    /// in the detected encoding.
+    pub fn read_lines(filename: &Path) -> Result<Vec<String>, Error> {
+        let bytes = fs::read(filename)?;
+        let file_contents = Encoding::detect(&bytes)
+            .decode(&bytes)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+        let (_, lines) = FileFormat::detect(&file_contents);
+        Ok(lines.into_iter().map(String::from).collect())
+    }
+
+    /// Open the UTF-8 encoded file by mapping it in memory. Its lines are indexed
+    /// in the background, and their rows are only decoded when accessed.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened or mapped.
+    pub fn open_mapped(filename: PathBuf) -> Result<Self, Error> {
+        let mapped_file = MappedFile::open(&filename)?;
+        let mut rows = Rope::default();
+        match mapped_file.next_block() {
+            Some(block) => rows.push_mapped(block),
+            None => rows.push(Row::default()),
         }
-        Ok(Self {
+        let mut document = Self {
             rows,
             filename: Some(filename),
-        })
+            format: mapped_file.format,
+            mapped_file: Some(mapped_file),
+            filetype: None,
+            highlighter: Highlighter::default(),
+            marks: HashMap::new(),
+        };
+        document.detect_filetype();
+        Ok(document)
+    }
+
+    /// Append the rows of the mapped file indexed since the last call, and
+    /// return how many there were
+    pub fn index_mapped_rows(&mut self) -> usize {
+        let (blocks, done) = match &self.mapped_file {
+            Some(mapped_file) => mapped_file.indexed_blocks(),
+            None => return 0,
+        };
+        if done {
+            self.mapped_file = None;
+        }
+        let num_rows = self.num_rows();
+        for block in blocks {
+            self.rows.push_mapped(block);
+        }
+        self.num_rows() - num_rows
+    }
+
+    /// Wait for the whole mapped file to be indexed
+    pub fn finish_indexing(&mut self) {
+        if let Some(mapped_file) = self.mapped_file.take() {
+            while let Some(block) = mapped_file.next_block() {
+                self.rows.push_mapped(block);
+            }
+        }
+    }
+
+    /// Return the percentage of the mapped file indexed so far, if still being indexed
+    #[must_use]
+    pub fn indexing_progress(&self) -> Option<usize> {
+        self.mapped_file.as_ref().map(MappedFile::progress)
+    }
+
+    /// Return whether some rows are read from a file mapped in memory
+    #[must_use]
+    pub fn is_mapped(&self) -> bool {
+        self.rows.is_mapped()
+    }
+
+    /// Free the memory used by the unmodified rows of a mapped file that are
+    /// located outside of the provided row index range
+    pub fn unload_rows_outside(&mut self, range: &Range<usize>) {
+        self.rows.unload_rows_outside(range);
+    }
+
+    /// Write the rows located between the start and end row indices (both included)
+    /// to the writer, following the document format.
+    fn write_to<W: Write>(
+        &self,
+        mut writer: W,
+        start: RowIndex,
+        end: RowIndex,
+    ) -> Result<(), Error> {
+        let encode = |text: &str| {
+            self.format
+                .encoding
+                .encode(text)
+                .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+        };
+        if start.value == 0 {
+            writer.write_all(&encode(self.format.header())?)?;
+        }
+        // unmodified rows of a mapped file can be copied as they are, if written
+        // with the format they were read with
+        let raw_line_ending =
+            (self.format.encoding == Encoding::Utf8).then_some(self.format.line_ending);
+        let last_row_index = self.num_rows().saturating_sub(1);
+        let mut row_index = start.value;
+        for piece in self
+            .rows
+            .pieces(&(start.value..end.value.saturating_add(1)), raw_line_ending)
+        {
+            match piece {
+                Piece::Rows(rows) => {
+                    for row in rows {
+                        writer.write_all(&encode(&row.string)?)?;
+                        writer.write_all(&encode(
+                            self.format.line_terminator(row_index == last_row_index),
+                        )?)?;
+                        row_index += 1;
+                    }
+                }
+                Piece::Bytes(bytes, num_rows) => {
+                    writer.write_all(bytes)?;
+                    row_index += num_rows;
+                    // the last line of the mapped file is now followed by other rows
+                    if !bytes.ends_with(b"\n") && row_index <= last_row_index {
+                        writer.write_all(self.format.line_ending.as_str().as_bytes())?;
+                    }
+                }
+            }
+        }
+        writer.flush()
+    }
+
+    /// Return the index of the last row, used to write the whole document
+    fn last_row_index(&self) -> RowIndex {
+        RowIndex::new(self.num_rows().saturating_sub(1))
     }
 
     /// # Errors
     /// # Panics
     /// Can return an error if the file can't be created or written to.
     pub fn save_to_swap_file(&self) -> Result<(), Error> {
-        if self.filename.is_some() {
-            let mut file = fs::File::create(Self::swap_filename(self.filename.as_ref().unwrap()))?;
-            for row in &self.rows {
-                file.write_all(row.as_bytes())?;
-                file.write_all(b"\n")?;
-            }
+        if self.is_mapped() {
+            // the whole file would have to be copied
+            return Ok(());
+        }
+        if let Some(filename) = &self.filename {
+            let file = fs::File::create(Self::swap_filename(filename))?;
+            self.write_to(
+                BufWriter::new(file),
+                RowIndex::new(0),
+                self.last_row_index(),
+            )?;
         }
         Ok(())
     }
 
+    /// Trim the trailing spaces of the rows. The unmodified rows of a mapped
+    /// file are left untouched.
     pub fn trim_trailing_spaces(&mut self) {
-        for row in self.iter_mut() {
+        for (index, row) in self.rows.iter_loaded_mut() {
+            let len = row.string.len();
             row.trim_end_inplace();
+            if row.string.len() != len {
+                self.highlighter.invalidate(index);
+            }
         }
     }
 
@@ -118,14 +343,11 @@ impl Document {
     /// # Panics
     /// Can return an error if the file can't be created or written to.
     pub fn save(&self) -> Result<(), Error> {
-        if self.filename.is_some() {
-            let filename = &self.filename.as_ref().unwrap();
-            let mut file = fs::File::create(filename)?;
-
-            for row in &self.rows {
-                file.write_all(row.as_bytes())?;
-                file.write_all(b"\n")?;
-            }
+        if self.mapped_file.is_some() {
+            return Err(Error::other("the file is still being indexed"));
+        }
+        if let Some(filename) = &self.filename {
+            self.write_rows(filename, RowIndex::new(0), self.last_row_index())?;
             if fs::remove_file(Self::swap_filename(filename)).is_ok() {
                 // pass
             }
@@ -137,30 +359,109 @@ impl Document {
     /// # Panics
     /// Can return an error if the file can't be created or written to.
     pub fn save_as(&mut self, new_name: &str) -> Result<(), Error> {
-        if self.filename.is_some() && !new_name.is_empty() {
-            fs::rename(self.filename.as_ref().unwrap(), new_name)?;
+        if let Some(filename) = &self.filename {
+            if !new_name.is_empty() {
+                fs::rename(filename, new_name)?;
+            }
         }
         self.filename = Some(PathBuf::from(new_name));
+        self.detect_filetype();
         self.save()
     }
 
+    /// Write the rows located between the start and end row indices (both included)
+    /// to the provided file, without changing the document filename.
+    ///
+    /// # Errors
+    /// Can return an error if the file can't be created or written to.
+    pub fn write_rows(&self, filename: &Path, start: RowIndex, end: RowIndex) -> Result<(), Error> {
+        if !self.is_mapped() {
+            // all rows are encoded before the file is truncated, so that it's left
+            // untouched if a row can't be encoded
+            let mut content = vec![];
+            self.write_to(&mut content, start, end)?;
+            return fs::write(filename, content);
+        }
+        // The mapped file can't be truncated while its content is copied, so the
+        // rows are written to a temporary file, replacing the target file once
+        // complete. The target of a symbolic link is replaced, rather than the link.
+        let filename = fs::canonicalize(filename).unwrap_or_else(|_| filename.to_path_buf());
+        let temporary_filename = Self::temporary_filename(&filename);
+        let written = fs::File::create(&temporary_filename)
+            .and_then(|file| self.write_to(BufWriter::new(file), start, end));
+        if let Err(error) = written {
+            if fs::remove_file(&temporary_filename).is_ok() {
+                // pass
+            }
+            return Err(error);
+        }
+        if let Ok(metadata) = fs::metadata(&filename) {
+            fs::set_permissions(&temporary_filename, metadata.permissions())?;
+        }
+        fs::rename(temporary_filename, filename)
+    }
+
     #[must_use]
     pub fn get_row(&self, index: RowIndex) -> Option<&Row> {
         self.rows.get(index.value)
     }
 
+    /// Replace the content of the row located at the provided index
+    pub fn replace_row(&mut self, index: RowIndex, string: String) {
+        self.highlighter.invalidate(index.value);
+        if let Some(row) = self.rows.get_mut(index.value) {
+            *row = Row::from(string);
+        }
+    }
+
+    /// Remove the row at the provided index, along with its mark, if any
     pub fn remove_row(&mut self, index: RowIndex) -> Row {
+        self.highlighter.remove(index.value);
+        let line_number = LineNumber::from(index);
+        self.marks.retain(|_, marked| *marked != line_number);
+        for marked in self.marks.values_mut() {
+            if *marked > line_number {
+                *marked = marked.previous();
+            }
+        }
         self.rows.remove(index.value)
     }
 
+    /// Insert the row at the provided index, shifting all rows after it down.
+    /// If the index is past the end of the document, the row is appended.
+    pub fn insert_row(&mut self, index: RowIndex, row: Row) {
+        self.highlighter.insert(index.value);
+        let line_number = LineNumber::from(index);
+        for marked in self.marks.values_mut() {
+            if *marked >= line_number {
+                *marked = marked.next();
+            }
+        }
+        if index.value >= self.num_rows() {
+            self.rows.push(row);
+        } else {
+            self.rows.insert(index.value, row);
+        }
+    }
+
+    /// Return the marks set in the document, along with the lines they're set to
+    #[must_use]
+    pub fn marks(&self) -> &HashMap<char, LineNumber> {
+        &self.marks
+    }
+
+    pub fn set_mark(&mut self, mark: char, line_number: LineNumber) {
+        self.marks.insert(mark, line_number);
+    }
+
     #[must_use]
     pub fn row_lengths(&self) -> Vec<usize> {
-        self.rows.iter().map(Row::len).collect()
+        self.iter().map(Row::len).collect()
     }
 
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.rows.len() == 0
+        self.rows.is_empty()
     }
 
     #[must_use]
@@ -185,56 +486,109 @@ impl Document {
         LineNumber::new(self.num_rows())
     }
 
-    pub fn iter(&self) -> Iter<Row> {
+    #[must_use]
+    pub fn iter(&self) -> rope::Iter<'_> {
         self.rows.iter()
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<Row> {
+    pub fn iter_mut(&mut self) -> rope::IterMut<'_> {
+        self.highlighter.invalidate_from(0);
         self.rows.iter_mut()
     }
 
-    pub fn insert_string(&mut self, text: &str, x: usize, y: RowIndex) {
-        let mut delta_x: usize = 0;
-        let mut delta_y: usize = 0;
-        for c in text.graphemes(true) {
-            if c == "\n" {
-                self.insert_newline(x + delta_x, RowIndex::new(y.value + delta_y));
-                delta_x = 0;
-                delta_y += 1;
-            } else if let Some(current_row) = self.rows.get_mut(y.value + delta_y) {
-                current_row.append_str(c);
-                delta_x += 1;
+    /// Insert the text at the provided position, splitting the row on newlines
+    pub fn insert_string(&mut self, text: &str, x: GraphemeIndex, y: RowIndex) {
+        self.highlighter.invalidate(y.value);
+        let (mut x, mut y) = (x, y);
+        for grapheme in text.graphemes(true) {
+            if grapheme == "\n" {
+                if let Some(current_row) = self.rows.get_mut(y.value) {
+                    let split_row = current_row.split(x);
+                    self.insert_row(y.next(), split_row);
+                }
+                x = GraphemeIndex::new(0);
+                y = y.next();
+            } else if let Some(current_row) = self.rows.get_mut(y.value) {
+                current_row.insert_str(x, grapheme);
+                x = x.next();
             }
         }
     }
 
-    pub fn insert_newline(&mut self, x: usize, y: RowIndex) {
+    /// Return the text located between the provided positions, the end one
+    /// being excluded. Rows are separated by newlines.
+    #[must_use]
+    pub fn text_between(
+        &self,
+        start: (RowIndex, GraphemeIndex),
+        end: (RowIndex, GraphemeIndex),
+    ) -> String {
+        let mut lines = vec![];
+        for y in start.0.value..=end.0.value {
+            let Some(row) = self.get_row(RowIndex::new(y)) else {
+                break;
+            };
+            let from = if y == start.0.value {
+                start.1
+            } else {
+                GraphemeIndex::new(0)
+            };
+            let to = if y == end.0.value {
+                end.1
+            } else {
+                GraphemeIndex::new(row.len())
+            };
+            lines.push(&row.string[row.byte_index(from).value..row.byte_index(to).value]);
+        }
+        lines.join("\n")
+    }
+
+    /// Delete the text located between the provided positions, the end one
+    /// being excluded, joining the first and last rows
+    pub fn delete_between(
+        &mut self,
+        start: (RowIndex, GraphemeIndex),
+        end: (RowIndex, GraphemeIndex),
+    ) {
+        self.highlighter.invalidate(start.0.value);
+        let last_row_index = cmp::min(end.0.value, self.num_rows().saturating_sub(1));
+        let suffix = match self.rows.get_mut(last_row_index) {
+            Some(row) => row.split(end.1),
+            None => return,
+        };
+        for _ in start.0.value..last_row_index {
+            self.remove_row(start.0.next());
+        }
+        if let Some(row) = self.rows.get_mut(start.0.value) {
+            let _deleted = row.split(start.1);
+            row.append(&suffix);
+        }
+    }
+
+    pub fn insert_newline(&mut self, x: GraphemeIndex, y: RowIndex) {
+        self.highlighter.invalidate(y.value);
         if y.value > self.num_rows() {
             return;
         }
         let current_row = self.rows.get_mut(y.value);
         if let Some(current_row) = current_row {
-            if x < current_row.len().saturating_sub(1) {
+            if x.value < current_row.len().saturating_sub(1) {
                 let split_row = current_row.split(x);
-                self.rows.insert(y.next().value, split_row);
+                self.insert_row(y.next(), split_row);
                 // newline inserted in the middle of the row
             } else {
-                let new_row = Row::default();
-                if y.value == self.num_rows() || y.next().value == self.num_rows() {
-                    self.rows.push(new_row);
-                } else {
-                    self.rows.insert(y.next().value, new_row);
-                }
+                self.insert_row(y.next(), Row::default());
             }
         }
     }
 
-    pub fn insert(&mut self, c: char, x: usize, y: RowIndex) {
+    pub fn insert(&mut self, c: char, x: GraphemeIndex, y: RowIndex) {
+        self.highlighter.invalidate(y.value);
         match y.value.cmp(&self.num_rows()) {
             Ordering::Equal | Ordering::Greater => {
                 let mut row = Row::default();
-                row.insert(0, c);
-                self.rows.push(row);
+                row.insert(GraphemeIndex::new(0), c);
+                self.insert_row(y, row);
             }
             Ordering::Less => {
                 if let Some(row) = self.rows.get_mut(y.value) {
@@ -244,22 +598,26 @@ impl Document {
         }
     }
 
-    pub fn delete_string(&mut self, text: &str, x: usize, y: RowIndex) {
+    pub fn delete_string(&mut self, text: &str, x: GraphemeIndex, y: RowIndex) {
         let mut x = x;
         let mut delta_x: usize = 0;
         let mut delta_y: usize = 0;
-        for c in text.chars() {
-            if c == '\n' {
-                self.join_row_with_previous_one(0, RowIndex::new(y.value - delta_y), None);
+        for c in text.graphemes(true) {
+            if c == "\n" {
+                self.join_row_with_previous_one(
+                    GraphemeIndex::new(0),
+                    RowIndex::new(y.value - delta_y),
+                    None,
+                );
                 delta_y += 1;
                 delta_x = 0;
                 if let Some(prev_row) = self.rows.get(y.value - delta_y) {
-                    x = prev_row.len().saturating_sub(1);
-                };
+                    x = GraphemeIndex::new(prev_row.len().saturating_sub(1));
+                }
             } else {
                 self.delete(
-                    x - delta_x,
-                    x - delta_x + 1,
+                    x.sub(delta_x),
+                    x.sub(delta_x).next(),
                     RowIndex::new(y.value - delta_y),
                 );
                 delta_x += 1;
@@ -267,13 +625,14 @@ impl Document {
         }
     }
 
-    pub fn delete(&mut self, x: usize, from_x: usize, y: RowIndex) {
+    pub fn delete(&mut self, x: GraphemeIndex, from_x: GraphemeIndex, y: RowIndex) {
+        self.highlighter.invalidate(y.value);
         if y.value >= self.num_rows() {
             return;
         }
         if let Some(row) = self.rows.get_mut(y.value) {
             // Deletion at the very start of a line means we append the current line to the previous one
-            if x == 0 && from_x == 0 && y.value > 0 {
+            if x.value == 0 && from_x.value == 0 && y.value > 0 {
                 self.join_row_with_previous_one(x, y, None);
             } else {
                 row.delete(x);
@@ -282,26 +641,89 @@ impl Document {
     }
 
     pub fn delete_row(&mut self, index: RowIndex) {
+        self.highlighter.invalidate(index.value);
         if index.value > self.num_rows() {
         } else if self.num_rows() == 1 {
             if let Some(row) = self.rows.get_mut(0) {
-                row.string = "".to_string();
+                *row = Row::default();
             }
         } else if self.get_row(index).is_some() {
             self.remove_row(index);
         }
     }
 
-    pub fn join_row_with_previous_one(&mut self, x: usize, y: RowIndex, join_with: Option<char>) {
+    pub fn join_row_with_previous_one(
+        &mut self,
+        x: GraphemeIndex,
+        y: RowIndex,
+        join_with: Option<char>,
+    ) {
+        self.highlighter.invalidate(y.value.saturating_sub(1));
         let current_row = self.remove_row(y);
         if let Some(previous_row) = self.rows.get_mut(y.value.saturating_sub(1)) {
             if let Some(join_char) = join_with {
-                previous_row.insert(x.saturating_add(1), join_char);
+                previous_row.insert(x.next(), join_char);
             }
             previous_row.append(&current_row);
         }
     }
 
+    /// Detect the type of the file from its name and from its first and last
+    /// rows, which can contain a shebang or a modeline
+    pub fn detect_filetype(&mut self) {
+        let num_rows = self.num_rows();
+        let head: Vec<&str> = (0..cmp::min(MODELINE_LINES, num_rows))
+            .filter_map(|index| self.rows.get(index))
+            .map(|row| row.string.as_str())
+            .collect();
+        let tail: Vec<&str> = (cmp::max(MODELINE_LINES, num_rows.saturating_sub(MODELINE_LINES))
+            ..num_rows)
+            .filter_map(|index| self.rows.get(index))
+            .map(|row| row.string.as_str())
+            .collect();
+        self.filetype = Filetype::detect(self.filename.as_deref(), &head, &tail);
+    }
+
+    /// Highlight the rows that weren't highlighted since they were last modified,
+    /// along with the rows following them that now start in a different state,
+    /// up to the provided row index (excluded). The syntax is the one of the
+    /// filetype, and mapped files aren't highlighted, as it would require
+    /// decoding them entirely.
+    pub fn highlight_rows(&mut self, end: usize) {
+        let syntax = if self.is_mapped() {
+            None
+        } else {
+            self.filetype.and_then(Filetype::syntax)
+        };
+        self.highlighter.set_syntax(syntax);
+        if syntax.is_none() {
+            return;
+        }
+        let end = cmp::min(end, self.num_rows());
+        let mut index = self.highlighter.next_row_to_highlight(0);
+        while index < end {
+            let Some(row) = self.rows.get(index) else {
+                break;
+            };
+            index = if self.highlighter.highlight(index, row) {
+                // the following row starts in a different state
+                self.highlighter.invalidate(index + 1);
+                index + 1
+            } else {
+                self.highlighter.next_row_to_highlight(index + 1)
+            };
+        }
+    }
+
+    /// Return the highlighted spans of the row, empty if the row wasn't highlighted
+    #[must_use]
+    pub fn highlights(&self, index: RowIndex) -> &[Span] {
+        self.highlighter.spans(index.value)
+    }
+
+    /// Return a hash of the document content and format. Only the rows modified
+    /// since the last call are rehashed, which makes it cheap enough to be called
+    /// on every redraw to find out whether the document was modified.
     #[must_use]
     pub fn hashed(&self) -> u64 {
         let mut s = DefaultHasher::new();
@@ -310,6 +732,24 @@ impl Document {
     }
 }
 
+impl<'a> IntoIterator for &'a Document {
+    type Item = &'a Row;
+    type IntoIter = rope::Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Document {
+    type Item = &'a mut Row;
+    type IntoIter = rope::IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 #[cfg(test)]
 #[path = "./document_test.rs"]
 mod document_test;
@@ -1,5 +1,9 @@
-use crate::{Document, LineNumber, Row, RowIndex};
+use crate::highlighting::{HighlightGroup, Span};
+use crate::{Document, Encoding, GraphemeIndex, LineEnding, LineNumber, Row, RowIndex};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tempfile::NamedTempFile;
 
 #[test]
 fn test_document_get_row() {
@@ -85,10 +89,10 @@ fn test_document_insert() {
         vec![Row::from("Hello"), Row::from("world!")],
         PathBuf::from("test.rs"),
     );
-    doc.insert(' ', 6, RowIndex::new(1));
+    doc.insert(' ', GraphemeIndex::new(6), RowIndex::new(1));
     assert_eq!(doc.rows.get(0).unwrap().string, "Hello");
     assert_eq!(doc.rows.get(1).unwrap().string, "world! ");
-    doc.insert('W', 0, RowIndex::new(2));
+    doc.insert('W', GraphemeIndex::new(0), RowIndex::new(2));
     assert_eq!(doc.rows.get(2).unwrap().string, "W");
 }
 
@@ -99,7 +103,7 @@ fn test_document_insert_newline_at_the_end() {
         PathBuf::from("test.rs"),
     );
     assert_eq!(doc.num_rows(), 2);
-    doc.insert_newline(6, RowIndex::new(1));
+    doc.insert_newline(GraphemeIndex::new(6), RowIndex::new(1));
     assert_eq!(doc.num_rows(), 3);
 }
 
@@ -109,10 +113,18 @@ fn test_document_delete() {
         vec![Row::from("Hello"), Row::from("world!")],
         PathBuf::from("test.rs"),
     );
-    doc.delete(5, 6, RowIndex::new(1));
+    doc.delete(
+        GraphemeIndex::new(5),
+        GraphemeIndex::new(6),
+        RowIndex::new(1),
+    );
     assert_eq!(doc.rows.get(0).unwrap().string, "Hello");
     assert_eq!(doc.rows.get(1).unwrap().string, "world");
-    doc.delete(2, 6, RowIndex::new(1));
+    doc.delete(
+        GraphemeIndex::new(2),
+        GraphemeIndex::new(6),
+        RowIndex::new(1),
+    );
     assert_eq!(doc.rows.get(1).unwrap().string, "wold");
 }
 
@@ -122,7 +134,11 @@ fn test_document_delete_at_start_of_line() {
         vec![Row::from("Hello"), Row::from("world!")],
         PathBuf::from("test.rs"),
     );
-    doc.delete(0, 0, RowIndex::new(1));
+    doc.delete(
+        GraphemeIndex::new(0),
+        GraphemeIndex::new(0),
+        RowIndex::new(1),
+    );
     assert_eq!(doc.rows.get(0).unwrap().string, "Helloworld!");
     assert!(doc.rows.get(1).is_none());
 }
@@ -144,12 +160,12 @@ fn test_insert_newline() {
         vec![Row::from("Hello"), Row::from("world!")],
         PathBuf::from("test.rs"),
     );
-    doc.insert_newline(0, RowIndex::new(0));
+    doc.insert_newline(GraphemeIndex::new(0), RowIndex::new(0));
     assert_eq!(doc.rows.get(0).unwrap().string, "");
     assert_eq!(doc.rows.get(1).unwrap().string, "Hello");
     assert_eq!(doc.rows.get(2).unwrap().string, "world!");
 
-    doc.insert_newline(0, RowIndex::new(2));
+    doc.insert_newline(GraphemeIndex::new(0), RowIndex::new(2));
     assert_eq!(doc.rows.get(0).unwrap().string, "");
     assert_eq!(doc.rows.get(1).unwrap().string, "Hello");
     assert_eq!(doc.rows.get(2).unwrap().string, "");
@@ -159,7 +175,7 @@ fn test_insert_newline() {
 #[test]
 fn test_insert_newline_row_split() {
     let mut doc = Document::new(vec![Row::from("Hello world!")], PathBuf::from("test.rs"));
-    doc.insert_newline(5, RowIndex::new(0));
+    doc.insert_newline(GraphemeIndex::new(5), RowIndex::new(0));
     assert_eq!(doc.rows.get(0).unwrap().string, "Hello");
     assert_eq!(doc.rows.get(1).unwrap().string, " world!");
 }
@@ -192,7 +208,7 @@ fn test_document_join_row_with_previous_one() {
         vec![Row::from("Hello"), Row::from("world!")],
         PathBuf::from("test.rs"),
     );
-    doc.join_row_with_previous_one(4, RowIndex::new(1), Some(' '));
+    doc.join_row_with_previous_one(GraphemeIndex::new(4), RowIndex::new(1), Some(' '));
     assert_eq!(doc.rows.get(0).unwrap().string, "Hello world!");
     assert_eq!(doc.num_rows(), 1);
 }
@@ -203,7 +219,7 @@ fn test_document_insert_string() {
         vec![Row::from("abcd"), Row::from("ef")],
         PathBuf::from("test.rs"),
     );
-    doc.insert_string("Ä\ngh", 3, RowIndex::new(1));
+    doc.insert_string("Ä\ngh", GraphemeIndex::new(3), RowIndex::new(1));
     assert_eq!(doc.num_rows(), 3);
     assert_eq!(doc.rows.get(1).unwrap().string, "efÄ");
     assert_eq!(doc.rows.get(2).unwrap().string, "gh");
@@ -215,8 +231,241 @@ fn test_document_delete_string() {
         vec![Row::from("abcd"), Row::from("efÄ"), Row::from("gh")],
         PathBuf::from("test.rs"),
     );
-    doc.delete_string("hg\nÄ", 1, RowIndex::new(2));
+    doc.delete_string("hg\nÄ", GraphemeIndex::new(1), RowIndex::new(2));
     assert_eq!(doc.num_rows(), 2);
     assert_eq!(doc.rows.get(0).unwrap().string, "abcd");
     assert_eq!(doc.rows.get(1).unwrap().string, "ef");
 }
+
+fn round_trip(content: &str) -> String {
+    let f = NamedTempFile::new().unwrap();
+    fs::write(f.path(), content).unwrap();
+    let doc = Document::open(f.path().to_path_buf()).unwrap();
+    doc.save().unwrap();
+    fs::read_to_string(f.path()).unwrap()
+}
+
+#[test]
+fn test_document_preserves_file_format() {
+    assert_eq!(round_trip("a\r\nb\r\n"), "a\r\nb\r\n");
+    assert_eq!(round_trip("a\nb"), "a\nb");
+    assert_eq!(round_trip("\u{feff}a\n"), "\u{feff}a\n");
+    assert_eq!(round_trip("a\r\nb\n"), "a\r\nb\n");
+}
+
+#[test]
+fn test_document_open_strips_bom() {
+    let f = NamedTempFile::new().unwrap();
+    fs::write(f.path(), "\u{feff}hello\r\n").unwrap();
+    let doc = Document::open(f.path().to_path_buf()).unwrap();
+    assert_eq!(doc.get_row(RowIndex::new(0)).unwrap().string, "hello");
+    assert!(doc.format.bom);
+    assert_eq!(doc.format.line_ending, LineEnding::Dos);
+}
+
+#[test]
+fn test_document_round_trips_utf16() {
+    let f = NamedTempFile::new().unwrap();
+    fs::write(f.path(), b"\xff\xfeh\x00i\x00\n\x00").unwrap();
+    let doc = Document::open(f.path().to_path_buf()).unwrap();
+    assert_eq!(doc.get_row(RowIndex::new(0)).unwrap().string, "hi");
+    assert_eq!(doc.format.encoding, Encoding::Utf16Le);
+    assert!(doc.format.bom);
+    doc.save().unwrap();
+    assert_eq!(fs::read(f.path()).unwrap(), b"\xff\xfeh\x00i\x00\n\x00");
+}
+
+#[test]
+fn test_document_open_with_encoding() {
+    let f = NamedTempFile::new().unwrap();
+    fs::write(f.path(), "é\n").unwrap();
+    let doc = Document::open_with_encoding(f.path().to_path_buf(), Some(Encoding::Latin1)).unwrap();
+    assert_eq!(doc.get_row(RowIndex::new(0)).unwrap().string, "Ã©");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_document_save_through_symlink() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("target.txt");
+    let link = dir.path().join("link.txt");
+    fs::write(&target, "old\n").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    let mut doc = Document::open(link.clone()).unwrap();
+    doc.insert_row(RowIndex::new(1), Row::from("new"));
+    doc.save().unwrap();
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "old\nnew\n");
+}
+
+#[test]
+fn test_document_mapped_file_edits_are_saved() {
+    let f = NamedTempFile::new().unwrap();
+    let content = (0..3000)
+        .map(|i| format!("line {i}\n"))
+        .collect::<Vec<_>>()
+        .concat();
+    let content = content.replace("line 5\n", "line 5  \n");
+    fs::write(f.path(), &content).unwrap();
+    let mut doc = Document::open_mapped(f.path().to_path_buf()).unwrap();
+    assert_eq!(doc.num_rows(), 1024); // only the first block was waited for
+    doc.finish_indexing();
+    assert_eq!(doc.indexing_progress(), None);
+    assert_eq!(doc.num_rows(), 3000);
+    assert_eq!(
+        doc.get_row(RowIndex::new(2999)).unwrap().string,
+        "line 2999"
+    );
+
+    doc.insert('!', GraphemeIndex::new(0), RowIndex::new(1500));
+    doc.insert_row(RowIndex::new(3000), Row::from("the end"));
+    doc.unload_rows_outside(&(0..10));
+    assert!(doc.is_mapped());
+    // the rows of unmodified mapped blocks keep their trailing spaces
+    doc.trim_trailing_spaces();
+    doc.save().unwrap();
+
+    let mut expected: Vec<String> = content.lines().map(String::from).collect();
+    expected[1500] = String::from("!line 1500");
+    expected.push(String::from("the end"));
+    assert_eq!(
+        fs::read_to_string(f.path()).unwrap(),
+        expected.join("\n") + "\n"
+    );
+}
+
+#[test]
+fn test_document_mapped_file_hash_matches_loaded_file() {
+    let f = NamedTempFile::new().unwrap();
+    let content = (0..2000)
+        .map(|i| format!("line {i}\n"))
+        .collect::<Vec<_>>()
+        .concat();
+    fs::write(f.path(), &content).unwrap();
+    let mut mapped = Document::open_mapped(f.path().to_path_buf()).unwrap();
+    assert!(mapped.save().is_err()); // still being indexed
+    while mapped.indexing_progress().is_some() {
+        mapped.index_mapped_rows();
+    }
+    let loaded = Document::open(f.path().to_path_buf()).unwrap();
+    assert_eq!(mapped.hashed(), loaded.hashed());
+    mapped
+        .write_rows(f.path(), RowIndex::new(1000), RowIndex::new(1099))
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(f.path()).unwrap(),
+        content
+            .lines()
+            .skip(1000)
+            .take(100)
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n"
+    );
+}
+
+/// Benchmark the time taken to type in the middle of documents of increasing sizes,
+/// which should stay the same whatever the document size. Run with ``make bench``.
+#[test]
+#[ignore = "benchmark"]
+fn bench_typing_latency_by_document_size() {
+    let keystrokes: u32 = 10_000;
+    let mut latencies = Vec::new();
+    for &num_rows in &[1_000, 10_000, 100_000, 200_000] {
+        let rows = (0..num_rows)
+            .map(|i| Row::from(format!("Lorem ipsum dolor sit amet, line {i}").as_str()))
+            .collect();
+        let mut doc = Document::new(rows, PathBuf::from("bench.txt"));
+        let mut y = RowIndex::new(num_rows / 2);
+        let start = Instant::now();
+        for i in 0..keystrokes {
+            if i % 50 == 49 {
+                doc.insert_newline(GraphemeIndex::new(0), y);
+                y = y.next();
+            } else {
+                doc.insert('a', GraphemeIndex::new((i % 50) as usize), y);
+            }
+            // done on every redraw, to display whether the document was modified
+            let _ = doc.hashed();
+        }
+        let latency = start.elapsed() / keystrokes;
+        println!("{num_rows:>7} rows: {latency:?} per keystroke");
+        latencies.push(latency);
+    }
+    let fastest = latencies.iter().min().unwrap();
+    let slowest = latencies.iter().max().unwrap();
+    assert!(
+        *slowest < *fastest * 4,
+        "latency grows with the document size"
+    );
+}
+
+#[test]
+fn test_document_highlighting_is_invalidated_by_edits() {
+    let mut document = Document::new(
+        vec![
+            Row::from("text"),
+            Row::from("```"),
+            Row::from("*code*"),
+            Row::from("```"),
+        ],
+        PathBuf::from("post.md"),
+    );
+    document.highlight_rows(3);
+    assert_eq!(document.highlights(RowIndex::new(2)).len(), 1);
+    assert!(document.highlights(RowIndex::new(3)).is_empty());
+
+    // removing the opening fence turns the code into emphasis
+    document.delete_row(RowIndex::new(1));
+    document.highlight_rows(10);
+    assert_eq!(
+        document.highlights(RowIndex::new(1)),
+        &[Span::new(0..6, HighlightGroup::Emphasis)]
+    );
+    // the last row now opens a fence
+    assert_eq!(
+        document.highlights(RowIndex::new(2)),
+        &[Span::new(0..3, HighlightGroup::Code)]
+    );
+
+    // trimming trailing spaces only invalidates the trimmed rows
+    document.insert_row(RowIndex::new(0), Row::from("# title  "));
+    document.highlight_rows(10);
+    document.trim_trailing_spaces();
+    assert_eq!(
+        document.highlights(RowIndex::new(0)),
+        &[Span::new(0..9, HighlightGroup::Heading)]
+    );
+    document.highlight_rows(10);
+    assert_eq!(
+        document.highlights(RowIndex::new(0)),
+        &[Span::new(0..7, HighlightGroup::Heading)]
+    );
+
+    // only Markdown files are highlighted
+    let mut document = Document::new(vec![Row::from("*text*")], PathBuf::from("notes.txt"));
+    document.highlight_rows(1);
+    assert!(document.highlights(RowIndex::new(0)).is_empty());
+}
+
+#[test]
+fn test_document_marks_follow_row_edits() {
+    let mut doc = Document::new(
+        vec![Row::from("one"), Row::from("two"), Row::from("three")],
+        PathBuf::from("test.txt"),
+    );
+    doc.set_mark('a', LineNumber::new(2));
+    doc.set_mark('b', LineNumber::new(3));
+    doc.insert_newline(GraphemeIndex::new(3), RowIndex::new(0));
+    assert_eq!(doc.marks().get(&'a'), Some(&LineNumber::new(3)));
+    assert_eq!(doc.marks().get(&'b'), Some(&LineNumber::new(4)));
+    // the mark of a removed row is dropped
+    doc.remove_row(RowIndex::new(2));
+    assert_eq!(doc.marks().get(&'a'), None);
+    assert_eq!(doc.marks().get(&'b'), Some(&LineNumber::new(3)));
+    doc.delete_between(
+        (RowIndex::new(0), GraphemeIndex::new(1)),
+        (RowIndex::new(1), GraphemeIndex::new(0)),
+    );
+    assert_eq!(doc.marks().get(&'b'), Some(&LineNumber::new(2)));
+}
@@ -1,48 +1,99 @@
-use crate::commands::ALL_COMMANDS;
+use crate::address::{self, LineRange};
+use crate::commands::{ArgumentKind, Invocation, ParsedPrompt, RangeSpec};
+use crate::display_width::str_width;
+use crate::filetype::{Filetype, Indentation};
+use crate::frame::{Color, Frame, Style};
+use crate::highlighting::HighlightGroup;
+use crate::keymap::{self, Keymap, MapMode, Mapping, Resolution};
+use crate::lint::Linter;
+use crate::spell::Dictionary;
 use crate::{
-    commands, utils, AnsiPosition, Boundary, Config, Console, Document, Help, History, LineNumber,
-    Mode, Navigator, OperationType, Row, RowIndex,
+    commands, config::OPTION_NAMES, utils, AnsiPosition, Boundary, ByteIndex, CharSearch, Column,
+    Config, Console, Document, DocumentPosition, Encoding, GraphemeIndex, Help, History,
+    LineEnding, LineNumber, Mode, Navigator, OperationType, Row, RowIndex, WordKind,
 };
 use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
+use std::cell::Cell;
 use std::cmp;
-use std::io;
-use std::path::PathBuf;
-use termion::color;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use termion::event::{Event, Key, MouseButton, MouseEvent};
 use unicode_segmentation::UnicodeSegmentation;
 
-const STATUS_FG_COLOR: color::Rgb = color::Rgb(63, 63, 63);
-const STATUS_BG_COLOR: color::Rgb = color::Rgb(239, 239, 239);
+const STATUS_STYLE: Style = Style {
+    fg: Color::Rgb(63, 63, 63),
+    bg: Color::Rgb(239, 239, 239),
+    bold: false,
+    underline: false,
+};
+/// Style of the rows outside of the current paragraph, in focus mode
+const DIMMED_STYLE: Style = Style {
+    fg: Color::Ansi(8),
+    bg: Color::Default,
+    bold: false,
+    underline: false,
+};
 const PKG: &str = "bo";
 const COMMAND_PREFIX: char = ':';
 const SEARCH_PREFIX: char = '/';
 const AUTOCOMPLETION_SUGGESTIONS_SEPARATOR: char = '|';
 const LINE_NUMBER_OFFSET: u8 = 4; // number of chars
-const START_X: u8 = LINE_NUMBER_OFFSET as u8; // index, so that's actually an offset of 5 chars
-const SPACES_PER_TAB: usize = 4;
+const START_X: u8 = LINE_NUMBER_OFFSET; // index, so that's actually an offset of 5 chars
 const SWAP_SAVE_EVERY: u8 = 100; // save to a swap file every 100 unsaved edits
-
+const UNNAMED_REGISTER: char = '"';
+const KEY_SEQUENCE_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_MAPPING_DEPTH: usize = 100;
+/// Number of columns at the right of the message bar in which pending normal mode keys are displayed
+const PENDING_KEYS_COLUMNS: usize = 11;
+/// Interval at which the screen is refreshed while a mapped file is being indexed
+const INDEXING_REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+/// Number of rows searched between two progress updates, in a mapped file
+const SEARCH_PROGRESS_INTERVAL: usize = 100_000;
+
+/// Position of the cursor or of an edit, made of the index of a grapheme in its
+/// row, and of a row index
 #[derive(Debug, Default, PartialEq, Clone, Copy, Serialize)]
 pub struct Position {
-    pub x: usize,
+    pub x: GraphemeIndex,
     pub y: usize,
 }
 
 impl Position {
     pub fn reset_x(&mut self) {
-        self.x = 0;
+        self.x = GraphemeIndex::default();
+    }
+    #[must_use]
+    pub fn top_left() -> Self {
+        Self::default()
     }
+}
+
+/// Position of a cell on the screen, made of a display column and of a screen line,
+/// both starting at 0
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct ScreenPosition {
+    pub x: Column,
+    pub y: usize,
+}
+
+impl ScreenPosition {
     #[must_use]
     pub fn top_left() -> Self {
         Self::default()
     }
 }
 
-impl From<AnsiPosition> for Position {
+impl From<AnsiPosition> for ScreenPosition {
     fn from(p: AnsiPosition) -> Self {
         Self {
-            x: p.x.saturating_sub(1) as usize,
+            x: Column::new(p.x.saturating_sub(1) as usize),
             y: p.y.saturating_sub(1) as usize,
         }
     }
@@ -54,6 +105,13 @@ pub struct ViewportOffset {
     pub columns: usize,
 }
 
+/// Line of the screen at which ``zt``, ``zz`` and ``zb`` display the current row
+enum ScreenLine {
+    Top,
+    Middle,
+    Bottom,
+}
+
 #[derive(Debug)]
 enum Direction {
     Up,
@@ -63,6 +121,7 @@ enum Direction {
 }
 
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Editor {
     should_quit: bool,
     cursor_position: Position,
@@ -73,22 +132,67 @@ pub struct Editor {
     command_buffer: String,
     command_suggestions: Vec<String>,
     current_autocompletion_index: usize,
+    /// Part of the command buffer preceding the word being autocompleted
+    autocompletion_prefix: String,
+    /// Number of characters between the prompt cursor and the end of the command buffer
+    prompt_cursor_offset: usize,
+    /// Whether the next prompt keystroke names the register to insert (after ``Ctrl-r``)
+    inserting_register: bool,
     config: Config,
     normal_command_buffer: Vec<String>,
-    mouse_event_buffer: Vec<Position>,
+    /// Leading keys of a multi-key normal command (ex: the ``g`` of ``ge``, or the
+    /// ``di`` of ``dis``), waiting for the following ones
+    normal_command_prefix: String,
+    /// Last in-line grapheme search, repeated by ``;`` and ``,``
+    last_char_search: Option<CharSearch>,
+    mouse_event_buffer: Vec<ScreenPosition>,
     search_matches: Vec<(Position, Position)>,
     current_search_match_index: usize,
     alternate_screen: bool,
+    /// Last frame drawn on the terminal, against which the next one is diffed
+    last_frame: Option<Frame>,
+    /// Index of the first document row displayed in the last frame, if it displayed the document
+    last_frame_first_row: Option<usize>,
     last_saved_hash: u64,
+    /// Number of sentences of the document, along with the hash of the document
+    /// they were counted in, so that they're only counted again after an edit
+    sentence_count: Cell<Option<(u64, usize)>>,
     terminal: Box<dyn Console>,
     unsaved_edits: u8,
     row_prefix_length: u8,
-    help_message: String,
+    alternate_screen_text: String,
     history: History,
+    registers: HashMap<char, String>,
+    keymap: Keymap,
+    /// Keys typed so far that are the start of a mapping
+    pending_keys: Vec<Key>,
+    mapping_depth: usize,
+    mapping_aborted: bool,
+    /// Whether the status bar is hidden, in focus mode, until the next keystroke
+    status_bar_hidden: bool,
+    /// Spell checking dictionary, loaded when spell checking gets enabled
+    dictionary: Option<Dictionary>,
+    /// Prose linter, loaded when it's first used
+    linter: Option<Linter>,
+    /// Style findings of the paragraphs displayed on screen, highlighted with the lint option
+    visible_lint_findings: Vec<(Range<DocumentPosition>, String)>,
 }
 
-fn die(e: &io::Error) {
-    print!("{}", termion::clear::All);
+/// Parse the value of an option holding a number of columns
+fn parse_width(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(width) if width > 0 => Ok(width),
+        _ => Err(format!("Invalid {name} '{value}'")),
+    }
+}
+
+/// Return the register name provided as a command argument, defaulting to the unnamed register
+fn register_name(arguments: &str) -> char {
+    arguments.chars().next().unwrap_or(UNNAMED_REGISTER)
+}
+
+fn die(terminal: &dyn Console, e: &io::Error) {
+    terminal.clear_all();
     panic!("{}", e);
 }
 
@@ -123,47 +227,71 @@ impl Serialize for Editor {
 
 impl Editor {
     pub fn new(filename: Option<String>, terminal: Box<dyn Console>) -> Self {
-        let document: Document = match filename {
-            None => Document::default(),
-            // Some(path) => Document::open(utils::expand_tilde(&path).as_str()).unwrap_or_default(),
-            Some(path) => Document::open(std::path::PathBuf::from(utils::expand_tilde(&path)))
-                .unwrap_or_default(),
+        let (document, message) = match filename {
+            None => (Document::default(), String::new()),
+            Some(path) => match Document::open(PathBuf::from(utils::expand_tilde(&path))) {
+                Ok(document) => (document, String::new()),
+                Err(error) => (
+                    Document::default(),
+                    utils::red(&format!("Error opening {path}: {error}")),
+                ),
+            },
         };
         let last_saved_hash = document.hashed();
-        let help_message = Help::default().format();
-        Self {
+        let alternate_screen_text = Help::default().format();
+        let mut editor = Self {
             should_quit: false,
             cursor_position: Position::top_left(),
             document,
             offset: ViewportOffset::default(),
-            message: "".to_string(),
+            message,
             mode: Mode::Normal,
-            command_buffer: "".to_string(),
+            command_buffer: String::new(),
             command_suggestions: vec![],
             current_autocompletion_index: 0,
+            autocompletion_prefix: String::new(),
+            prompt_cursor_offset: 0,
+            inserting_register: false,
             config: Config::default(),
             normal_command_buffer: vec![],
+            normal_command_prefix: String::new(),
+            last_char_search: None,
             mouse_event_buffer: vec![],
             search_matches: vec![],
             current_search_match_index: 0,
             alternate_screen: false,
+            last_frame: None,
+            last_frame_first_row: None,
             terminal,
             unsaved_edits: 0,
             last_saved_hash,
+            sentence_count: Cell::new(None),
             row_prefix_length: 0,
-            help_message,
+            alternate_screen_text,
             history: History::default(),
-        }
+            registers: HashMap::new(),
+            keymap: Keymap::default(),
+            pending_keys: vec![],
+            mapping_depth: 0,
+            mapping_aborted: false,
+            status_bar_hidden: false,
+            dictionary: None,
+            linter: None,
+            visible_lint_findings: vec![],
+        };
+        editor.apply_filetype_defaults();
+        editor
     }
 
     /// Main screen rendering loop
     pub fn run(&mut self) {
         loop {
+            self.index_mapped_rows();
             if let Err(error) = self.refresh_screen() {
-                die(&error);
+                die(self.terminal.as_ref(), &error);
             }
             if let Err(error) = self.process_event() {
-                die(&error);
+                die(self.terminal.as_ref(), &error);
             }
             if self.should_quit {
                 self.terminal.clear_screen();
@@ -173,60 +301,160 @@ impl Editor {
     }
 
     /// Main event processing method. An event can be either be a keystroke or a mouse click
+    ///
+    /// When the keys typed so far are the start of a mapping, the next event is only
+    /// waited for until the key sequence times out, after which the keys get processed.
     fn process_event(&mut self) -> Result<(), std::io::Error> {
-        let event = self.terminal.read_event()?;
+        let event = if !self.pending_keys.is_empty() {
+            if let Some(event) = self.terminal.read_event_timeout(KEY_SEQUENCE_TIMEOUT)? {
+                event
+            } else {
+                self.flush_pending_keys();
+                return Ok(());
+            }
+        } else if self.document.indexing_progress().is_some() {
+            // come back regularly to display the indexing progress
+            match self
+                .terminal
+                .read_event_timeout(INDEXING_REFRESH_INTERVAL)?
+            {
+                Some(event) => event,
+                None => return Ok(()),
+            }
+        } else {
+            self.terminal.read_event()?
+        };
         match event {
             Event::Key(pressed_key) => self.process_keystroke(pressed_key),
-            Event::Mouse(mouse_event) => self.process_mouse_event(mouse_event),
+            Event::Mouse(mouse_event) => {
+                self.flush_pending_keys();
+                self.process_mouse_event(mouse_event);
+            }
             Event::Unsupported(_) => (),
         }
         Ok(())
     }
 
+    /// Return the mapping table applying to the current editor state
+    fn map_mode(&self) -> MapMode {
+        if self.is_receiving_command() {
+            return MapMode::Prompt;
+        }
+        match self.mode {
+            Mode::Normal => MapMode::Normal,
+            Mode::Insert => MapMode::Insert,
+        }
+    }
+
+    /// React to a keystroke, after having translated it through the user mappings.
+    ///
+    /// The keystroke is kept pending as long as the keys typed so far are the
+    /// start of a longer mapping.
+    fn process_keystroke(&mut self, pressed_key: Key) {
+        let mode = self.map_mode();
+        self.pending_keys.push(pressed_key);
+        match self.keymap.resolve(mode, &self.pending_keys) {
+            Resolution::Pending => (),
+            Resolution::Mapped(mapping) => {
+                let mapping = mapping.clone();
+                self.pending_keys.clear();
+                self.execute_mapping(&mapping);
+            }
+            Resolution::Unmapped => self.flush_pending_keys(),
+        }
+    }
+
+    /// Process the pending keys, which can't be the start of a longer mapping anymore
+    /// (or have timed out). The longest mapped sequence of leading keys gets executed,
+    /// and the remaining keys are processed again.
+    fn flush_pending_keys(&mut self) {
+        let keys = mem::take(&mut self.pending_keys);
+        let mode = self.map_mode();
+        let (mapped_len, mapping) = (1..=keys.len())
+            .rev()
+            .find_map(|len| {
+                self.keymap
+                    .get(mode, &keys[..len])
+                    .map(|mapping| (len, mapping.clone()))
+            })
+            .map_or((0, None), |(len, mapping)| (len, Some(mapping)));
+        let remaining_keys = match mapping {
+            Some(mapping) => {
+                self.execute_mapping(&mapping);
+                &keys[mapped_len..]
+            }
+            None => match keys.split_first() {
+                Some((first_key, remaining_keys)) => {
+                    self.process_unmapped_keystroke(*first_key);
+                    remaining_keys
+                }
+                None => return,
+            },
+        };
+        for key in remaining_keys {
+            self.process_keystroke(*key);
+        }
+    }
+
+    /// Process the keys a mapping maps to. Recursive mappings are aborted when
+    /// nested too deeply, to avoid looping forever (eg: ``:map a b`` and ``:map b a``).
+    fn execute_mapping(&mut self, mapping: &Mapping) {
+        if self.mapping_depth == 0 {
+            self.mapping_aborted = false;
+        }
+        if self.mapping_depth >= MAX_MAPPING_DEPTH {
+            self.mapping_aborted = true;
+            self.display_message(utils::red("Recursive mapping"));
+            return;
+        }
+        self.mapping_depth += 1;
+        for key in &mapping.keys {
+            if self.mapping_aborted {
+                break;
+            }
+            if mapping.recursive {
+                self.process_keystroke(*key);
+            } else {
+                self.process_unmapped_keystroke(*key);
+            }
+        }
+        self.mapping_depth -= 1;
+    }
+
     /// React to a keystroke. The reaction itself depends on the editor
     /// mode (insert, command, normal) or whether the editor is currently
     /// receiving a user input command (eg: ":q", etc).
-    fn process_keystroke(&mut self, pressed_key: Key) {
+    fn process_unmapped_keystroke(&mut self, pressed_key: Key) {
         if self.is_receiving_command() {
             if self.is_autocompleting_command() {
                 match pressed_key {
                     Key::Char('\t') => self.cycle_through_command_suggestions(),
                     Key::Char('\n') => {
                         self.command_buffer = format!(
-                            "{}{}",
+                            "{}{}{}",
                             COMMAND_PREFIX,
-                            self.command_suggestions[self.current_autocompletion_index].clone()
+                            self.autocompletion_prefix,
+                            self.command_suggestions[self.current_autocompletion_index]
                         );
                         self.reset_autocompletions();
-                        self.process_keystroke(pressed_key);
+                        self.process_unmapped_keystroke(pressed_key);
                     }
                     _ => {
                         self.reset_autocompletions();
-                        self.process_keystroke(pressed_key);
+                        self.process_unmapped_keystroke(pressed_key);
                     }
                 }
             } else {
-                // accumulate the command in the command buffer
-                match pressed_key {
-                    Key::Esc => self.stop_receiving_command(),
-                    Key::Char('\n') => {
-                        // Enter
-                        self.process_received_command();
-                        self.stop_receiving_command();
-                    }
-                    Key::Char('\t') => self.autocomplete_command(),
-                    Key::Char(c) => self.command_buffer.push(c), // accumulate keystrokes into the buffer
-                    Key::Backspace => self
-                        .command_buffer
-                        .truncate(self.command_buffer.len().saturating_sub(1)),
-                    _ => (),
-                }
+                self.process_prompt_keystroke(pressed_key);
             }
         } else {
             match self.mode {
                 Mode::Normal => self.process_normal_command(pressed_key),
                 Mode::Insert => self.process_insert_command(pressed_key),
             }
+            self.scroll_cursor_into_view();
+            // in focus mode, the status bar is hidden while typing
+            self.status_bar_hidden = self.config.focus && self.mode == Mode::Insert;
         }
     }
 
@@ -238,17 +466,23 @@ impl Editor {
                 self.terminal
                     .get_cursor_index_from_mouse_event(mouse_event, self.row_prefix_length),
             ),
-            MouseEvent::Release(_, _) => {
-                if !self.mouse_event_buffer.is_empty() {
-                    // Make sure that we're moving to an x/y location in which we already
-                    // have text, to avoid breaking out of the document bounds.
-                    let cursor_position = self.mouse_event_buffer.pop().unwrap();
-                    if cursor_position.y.saturating_add(1) <= self.document.num_rows() {
-                        if let Some(target_row) = self.get_row(RowIndex::new(cursor_position.y)) {
-                            if cursor_position.x <= target_row.len() {
-                                self.cursor_position = cursor_position;
-                            }
-                        }
+            MouseEvent::Release(_, _) if !self.mouse_event_buffer.is_empty() => {
+                // Make sure that we're moving to an x/y location in which we already
+                // have text, to avoid breaking out of the document bounds.
+                let cursor_position = self.mouse_event_buffer.pop().unwrap();
+                let scroll_column = self.scroll_column();
+                let row_index = RowIndex::new(self.offset.rows.saturating_add(cursor_position.y));
+                if let Some(target_row) = self.get_row(row_index) {
+                    // the clicked column is converted to the grapheme displayed over it
+                    let x = target_row.index_at_column(
+                        scroll_column.add(cursor_position.x.sub(self.text_area_margin()).value),
+                        self.config.tabstop,
+                    );
+                    if x.value >= self.offset.columns {
+                        self.cursor_position = Position {
+                            x: x.sub(self.offset.columns),
+                            y: cursor_position.y,
+                        };
                     }
                 }
             }
@@ -280,7 +514,140 @@ impl Editor {
 
     /// Stop receiving a command
     fn stop_receiving_command(&mut self) {
-        self.command_buffer = "".to_string();
+        self.command_buffer = String::new();
+        self.prompt_cursor_offset = 0;
+        self.inserting_register = false;
+    }
+
+    /// Edit the command buffer according to the keystroke, or execute the command
+    /// it contains when Enter is pressed.
+    fn process_prompt_keystroke(&mut self, pressed_key: Key) {
+        if self.inserting_register {
+            self.inserting_register = false;
+            match pressed_key {
+                Key::Ctrl('w') => {
+                    let word = self.current_word();
+                    self.insert_in_prompt(&word);
+                }
+                Key::Char(name) => {
+                    if let Some(content) = self.registers.get(&name) {
+                        // multi-line contents are inserted on the single prompt line
+                        let content = content.trim_end_matches('\n').replace('\n', " ");
+                        self.insert_in_prompt(&content);
+                    }
+                }
+                _ => (),
+            }
+            return;
+        }
+        let cursor_position = self.prompt_cursor_position();
+        match pressed_key {
+            Key::Esc => self.stop_receiving_command(),
+            Key::Char('\n') => {
+                // Enter
+                self.process_received_command();
+                self.stop_receiving_command();
+            }
+            Key::Char('\t') => self.autocomplete_command(),
+            Key::Char(c) => self.insert_in_prompt(&c.to_string()),
+            Key::Backspace => {
+                if self.command_buffer.chars().count() == 1 {
+                    // deleting the prompt prefix itself closes the prompt
+                    self.stop_receiving_command();
+                } else {
+                    self.delete_in_prompt(cursor_position.saturating_sub(1));
+                }
+            }
+            Key::Left => self.move_prompt_cursor_to(cursor_position.saturating_sub(1)),
+            Key::Right => self.move_prompt_cursor_to(cursor_position.saturating_add(1)),
+            Key::Home => self.move_prompt_cursor_to(1),
+            Key::End => self.prompt_cursor_offset = 0,
+            Key::Ctrl('w') => {
+                let chars: Vec<char> = self.command_buffer.chars().take(cursor_position).collect();
+                let mut start = cursor_position;
+                while start > 1 && chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+                let deleting_word = start > 1 && is_word_char(chars[start - 1]);
+                while start > 1
+                    && !chars[start - 1].is_whitespace()
+                    && is_word_char(chars[start - 1]) == deleting_word
+                {
+                    start -= 1;
+                }
+                self.delete_in_prompt(start);
+            }
+            Key::Ctrl('u') => self.delete_in_prompt(1),
+            Key::Ctrl('r') => self.inserting_register = true,
+            _ => (),
+        }
+    }
+
+    /// Return the position of the prompt cursor, in characters, including the prompt prefix
+    fn prompt_cursor_position(&self) -> usize {
+        self.command_buffer
+            .chars()
+            .count()
+            .saturating_sub(self.prompt_cursor_offset)
+    }
+
+    /// Move the prompt cursor to the provided character position, without moving
+    /// it before the prompt prefix or after the end of the command buffer
+    fn move_prompt_cursor_to(&mut self, position: usize) {
+        let num_chars = self.command_buffer.chars().count();
+        let position = cmp::min(cmp::max(position, 1), num_chars);
+        self.prompt_cursor_offset = num_chars - position;
+    }
+
+    /// Return the byte index in the command buffer of the provided character position
+    fn prompt_byte_index(&self, position: usize) -> usize {
+        self.command_buffer
+            .char_indices()
+            .nth(position)
+            .map_or(self.command_buffer.len(), |(i, _)| i)
+    }
+
+    /// Insert the text in the command buffer, at the prompt cursor position
+    fn insert_in_prompt(&mut self, text: &str) {
+        let index = self.prompt_byte_index(self.prompt_cursor_position());
+        self.command_buffer.insert_str(index, text);
+    }
+
+    /// Delete the characters located between the provided position and the prompt cursor
+    fn delete_in_prompt(&mut self, from_position: usize) {
+        let from_position = cmp::max(from_position, 1);
+        let to_position = self.prompt_cursor_position();
+        if from_position >= to_position {
+            return;
+        }
+        let range = self.prompt_byte_index(from_position)..self.prompt_byte_index(to_position);
+        self.command_buffer.replace_range(range, "");
+    }
+
+    /// Return the word located under the cursor, in the document
+    fn current_word(&self) -> String {
+        let is_word_char = |c: &char| c.is_alphanumeric() || *c == '_';
+        // graphemes are compared by their first character, to keep the combining
+        // accents of a word
+        let graphemes: Vec<&str> = self.current_row().graphemes().collect();
+        let chars: Vec<char> = graphemes
+            .iter()
+            .map(|grapheme| grapheme.chars().next().unwrap_or_default())
+            .collect();
+        let x = self.current_x_position().value;
+        if !chars.get(x).is_some_and(is_word_char) {
+            return String::new();
+        }
+        let start = chars[..x]
+            .iter()
+            .rposition(|c| !is_word_char(c))
+            .map_or(0, |i| i + 1);
+        let len = chars[start..]
+            .iter()
+            .take_while(|c| is_word_char(c))
+            .count();
+        graphemes[start..start + len].concat()
     }
 
     /// Return whether the Editor is currently receiving a command
@@ -297,6 +664,7 @@ impl Editor {
     fn reset_autocompletions(&mut self) {
         self.command_suggestions = vec![];
         self.current_autocompletion_index = 0;
+        self.autocompletion_prefix = String::new();
     }
 
     fn pop_normal_command_repetitions(&mut self) -> usize {
@@ -321,111 +689,1032 @@ impl Editor {
                 self.process_search_command(command.strip_prefix(SEARCH_PREFIX).unwrap());
             }
             COMMAND_PREFIX => {
-                let command = command.strip_prefix(COMMAND_PREFIX).unwrap_or_default();
-                if command.is_empty() {
-                } else if command.chars().all(char::is_numeric) {
-                    // :n will get you to line n
-                    let line_number = command.parse::<usize>().unwrap();
-                    self.goto_line(LineNumber::new(line_number), 0);
-                } else if command.split(' ').count() > 1 {
-                    let cmd_tokens: Vec<&str> = command.split(' ').collect();
-                    match *cmd_tokens.get(0).unwrap_or(&"") {
-                        commands::OPEN | commands::OPEN_SHORT => {
-                            let filename = PathBuf::from(cmd_tokens[1]);
-                            if self.document.filename == Some(filename.clone()) {
-                                self.display_message(format!(
-                                    "{} is already opened",
-                                    cmd_tokens[1]
-                                ));
-                            } else if let Ok(document) = Document::open(filename) {
-                                self.document = document;
-                                self.last_saved_hash = self.document.hashed();
-                                self.reset_message();
-                                self.cursor_position = Position::default();
-                                self.history = History::default();
-                            } else {
-                                self.display_message(utils::red(&format!(
-                                    "{} not found",
-                                    cmd_tokens[1]
-                                )));
-                            }
-                        }
-                        commands::NEW => {
-                            self.document =
-                                Document::new_empty(PathBuf::from(cmd_tokens[1].to_string()));
-                            self.enter_insert_mode();
-                        }
-                        commands::SAVE => {
-                            let new_name = cmd_tokens[1..].join(" ");
-                            self.save(new_name.trim());
-                        }
-                        _ => self.display_message(utils::red(&format!(
-                            "Unknown command '{}'",
-                            cmd_tokens[0]
-                        ))),
+                self.process_prompt_command(
+                    command.strip_prefix(COMMAND_PREFIX).unwrap_or_default(),
+                );
+            }
+            _ => (),
+        }
+    }
+
+    /// Parse the prompt command and its optional range, and call the command handler
+    /// with the lines the range resolves to. A range that isn't followed by any command
+    /// moves the cursor to the last line of the range (eg: ``:42``).
+    fn process_prompt_command(&mut self, command: &str) {
+        let parsed_command = match commands::parse(command) {
+            Ok(ParsedPrompt::Empty) => return,
+            Ok(ParsedPrompt::Range(address_range)) => {
+                match address_range.resolve(&self.document, self.current_line_number()) {
+                    Ok(range) => {
+                        self.goto_line(
+                            cmp::min(range.end, self.document.last_line_number()),
+                            GraphemeIndex::new(0),
+                        );
                     }
-                } else {
-                    match command {
-                        commands::FORCE_QUIT => self.quit(true),
-                        commands::QUIT => self.quit(false),
-                        commands::LINE_NUMBERS => {
-                            self.config.display_line_numbers =
-                                Config::toggle(self.config.display_line_numbers);
-                            self.row_prefix_length = if self.config.display_line_numbers {
-                                START_X
-                            } else {
-                                0
-                            };
-                        }
-                        commands::STATS => {
-                            self.config.display_stats = Config::toggle(self.config.display_stats);
-                        }
-                        commands::HELP => {
-                            self.alternate_screen = true;
-                        }
-                        commands::SAVE => self.save(""),
-                        commands::SAVE_AND_QUIT => {
-                            self.save("");
-                            self.quit(false);
-                        }
-                        commands::DEBUG => {
-                            if let Ok(state) = serde_json::to_string_pretty(&self) {
-                                utils::log(state.as_str());
-                            }
-                        }
-                        _ => self
-                            .display_message(utils::red(&format!("Unknown command '{}'", command))),
+                    Err(error) => self.display_message(utils::red(&error)),
+                }
+                return;
+            }
+            Ok(ParsedPrompt::Command(parsed_command)) => parsed_command,
+            Err(error) => {
+                self.display_message(utils::red(&error));
+                return;
+            }
+        };
+        let current_line_number = self.current_line_number();
+        let range = match parsed_command.range {
+            Some(address_range) => {
+                let single_address = address_range.end.is_none();
+                match address_range.resolve(&self.document, current_line_number) {
+                    // as in vim, ``:5j`` joins line 5 with the next one
+                    Ok(range)
+                        if single_address
+                            && parsed_command.command.range == RangeSpec::CurrentAndNextLine
+                            && range.is_within(&self.document) =>
+                    {
+                        Some(LineRange::new(
+                            range.start,
+                            cmp::min(range.start.next(), self.document.last_line_number()),
+                        ))
+                    }
+                    Ok(range) if range.is_within(&self.document) => Some(range),
+                    Ok(_) => {
+                        self.display_message(utils::red("Invalid range"));
+                        return;
+                    }
+                    Err(error) => {
+                        self.display_message(utils::red(&error));
+                        return;
                     }
                 }
             }
-            _ => (),
+            None => match parsed_command.command.range {
+                RangeSpec::None | RangeSpec::Optional => None,
+                RangeSpec::CurrentLine => Some(LineRange::single(current_line_number)),
+                RangeSpec::CurrentAndNextLine => Some(LineRange::new(
+                    current_line_number,
+                    cmp::min(current_line_number.next(), self.document.last_line_number()),
+                )),
+            },
+        };
+        let invocation = Invocation {
+            range,
+            bang: parsed_command.bang,
+            repeat: parsed_command.repeat,
+            arguments: parsed_command.arguments.to_string(),
+        };
+        (parsed_command.command.handler)(self, &invocation);
+    }
+
+    /// Return the range the command was invoked with, defaulting to the current line
+    fn invocation_range(&self, invocation: &Invocation) -> LineRange {
+        invocation
+            .range
+            .unwrap_or_else(|| LineRange::single(self.current_line_number()))
+    }
+
+    pub(crate) fn quit_command(&mut self, invocation: &Invocation) {
+        self.quit(invocation.bang);
+    }
+
+    pub(crate) fn line_numbers_command(&mut self, _invocation: &Invocation) {
+        self.config.display_line_numbers = Config::toggle(self.config.display_line_numbers);
+        self.update_row_prefix_length();
+    }
+
+    pub(crate) fn focus_command(&mut self, _invocation: &Invocation) {
+        self.config.focus = Config::toggle(self.config.focus);
+        self.status_bar_hidden = self.config.focus;
+        self.update_row_prefix_length();
+        self.scroll_cursor_into_view();
+    }
+
+    /// Display the line numbers before the rows if they are enabled, except
+    /// in focus mode
+    fn update_row_prefix_length(&mut self) {
+        self.row_prefix_length = if self.config.display_line_numbers && !self.config.focus {
+            START_X
+        } else {
+            0
+        };
+    }
+
+    pub(crate) fn stats_command(&mut self, _invocation: &Invocation) {
+        self.config.display_stats = Config::toggle(self.config.display_stats);
+    }
+
+    pub(crate) fn help_command(&mut self, _invocation: &Invocation) {
+        self.alternate_screen_text = Help::with_mappings(&self.keymap).format();
+        self.alternate_screen = true;
+    }
+
+    /// Open the file provided as argument, optionally preceded by an encoding
+    /// override (eg: ``++enc=latin1 notes.txt``). Without any filename, the current
+    /// file is reopened, which allows reading it again with another encoding.
+    pub(crate) fn open_command(&mut self, invocation: &Invocation) {
+        let mut encoding = None;
+        let mut filename = invocation.arguments.as_str();
+        if let Some(rest) = filename.strip_prefix("++enc=") {
+            let (name, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            match name.parse::<Encoding>() {
+                Ok(parsed) => encoding = Some(parsed),
+                Err(error) => {
+                    self.display_message(utils::red(&error));
+                    return;
+                }
+            }
+            filename = rest.trim_start();
+        }
+        if !filename.is_empty() {
+            self.open(filename, encoding);
+        } else if let Some(current_filename) = self.document.filename.clone() {
+            if encoding.is_some() {
+                self.reopen(&current_filename, encoding);
+            } else {
+                self.display_message(format!("{} is already opened", current_filename.display()));
+            }
+        } else {
+            self.display_message(utils::red("No file name"));
+        }
+    }
+
+    pub(crate) fn new_command(&mut self, invocation: &Invocation) {
+        self.document = Document::new_empty(PathBuf::from(&invocation.arguments));
+        self.apply_filetype_defaults();
+        self.enter_insert_mode();
+    }
+
+    pub(crate) fn save_command(&mut self, invocation: &Invocation) {
+        match invocation.range {
+            Some(range) => self.write_lines(range, &invocation.arguments),
+            None => self.save(&invocation.arguments),
+        }
+    }
+
+    pub(crate) fn read_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.read_lines(range.end, &invocation.arguments);
+    }
+
+    pub(crate) fn save_and_quit_command(&mut self, _invocation: &Invocation) {
+        self.save("");
+        self.quit(false);
+    }
+
+    pub(crate) fn debug_command(&mut self, _invocation: &Invocation) {
+        if let Ok(state) = serde_json::to_string_pretty(&self) {
+            utils::log(state.as_str());
         }
     }
 
-    /// Determine which commands could be autocompleted into based on the current
-    /// state of the user provided command.
+    pub(crate) fn delete_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.delete_lines(range, register_name(&invocation.arguments));
+    }
+
+    pub(crate) fn yank_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.yank_lines(range, register_name(&invocation.arguments));
+    }
+
+    pub(crate) fn move_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.move_lines(range, &invocation.arguments);
+    }
+
+    pub(crate) fn copy_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.copy_lines(range, &invocation.arguments);
+    }
+
+    pub(crate) fn shift_right_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.shift_lines(range, invocation.repeat, &Boundary::End);
+    }
+
+    pub(crate) fn shift_left_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.shift_lines(range, invocation.repeat, &Boundary::Start);
+    }
+
+    pub(crate) fn retab_command(&mut self, invocation: &Invocation) {
+        let range = invocation.range.unwrap_or_else(|| {
+            LineRange::new(LineNumber::new(1), self.document.last_line_number())
+        });
+        let new_tabstop = if invocation.arguments.is_empty() {
+            self.config.tabstop
+        } else {
+            match parse_width("tabstop", &invocation.arguments) {
+                Ok(tabstop) => tabstop,
+                Err(error) => {
+                    self.display_message(utils::red(&error));
+                    return;
+                }
+            }
+        };
+        self.retab_lines(range, new_tabstop, invocation.bang);
+    }
+
+    pub(crate) fn join_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.join_lines(range);
+    }
+
+    pub(crate) fn normal_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.execute_normal_keys(range, &invocation.arguments);
+    }
+
+    pub(crate) fn mark_command(&mut self, invocation: &Invocation) {
+        let range = self.invocation_range(invocation);
+        self.set_mark(range.end, &invocation.arguments);
+    }
+
+    pub(crate) fn set_command(&mut self, invocation: &Invocation) {
+        for option in invocation.arguments.split_whitespace() {
+            if let Err(error) = self.set_option(option) {
+                self.display_message(utils::red(&error));
+                return;
+            }
+        }
+    }
+
+    /// Set the option to the value provided after ``=`` (eg: ``fileformat=dos``),
+    /// or display its current value if no value was provided. Boolean options are
+    /// set by their name (eg: ``expandtab``), unset by their name prefixed by ``no``
+    /// and displayed by their name followed by ``?``.
+    fn set_option(&mut self, option: &str) -> Result<(), String> {
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (option.trim_end_matches('?'), None),
+        };
+        let query = value.is_none() && option.ends_with('?');
+        match (name, value) {
+            ("fileformat" | "ff", None) => {
+                self.display_message(format!("fileformat={}", self.document.format.line_ending));
+            }
+            ("fileformat" | "ff", Some(value)) => {
+                self.document.format.line_ending = value.parse::<LineEnding>()?;
+            }
+            ("fileencoding" | "fenc", None) => {
+                self.display_message(format!("fileencoding={}", self.document.format.encoding));
+            }
+            ("fileencoding" | "fenc", Some(value)) => {
+                self.document.format.encoding = value.parse::<Encoding>()?;
+            }
+            ("tabstop" | "ts", None) => {
+                self.display_message(format!("tabstop={}", self.config.tabstop));
+            }
+            ("tabstop" | "ts", Some(value)) => {
+                self.config.tabstop = parse_width(name, value)?;
+            }
+            ("shiftwidth" | "sw", None) => {
+                self.display_message(format!("shiftwidth={}", self.config.shiftwidth));
+            }
+            ("shiftwidth" | "sw", Some(value)) => {
+                self.config.shiftwidth = parse_width(name, value)?;
+            }
+            ("expandtab" | "et", None) if query => {
+                let prefix = if self.config.expandtab { "" } else { "no" };
+                self.display_message(format!("{prefix}expandtab"));
+            }
+            ("expandtab" | "et", None) => self.config.expandtab = true,
+            ("noexpandtab" | "noet", None) => self.config.expandtab = false,
+            ("focuswidth", None) => {
+                self.display_message(format!("focuswidth={}", self.config.focuswidth));
+            }
+            ("focuswidth", Some(value)) => {
+                self.config.focuswidth = parse_width(name, value)?;
+            }
+            ("scrolloff" | "so", None) => {
+                self.display_message(format!("scrolloff={}", self.config.scrolloff));
+            }
+            ("scrolloff" | "so", Some(value)) => {
+                self.config.scrolloff = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid {name} '{value}'"))?;
+            }
+            ("sentencecount", None) if query => {
+                let prefix = if self.config.sentencecount { "" } else { "no" };
+                self.display_message(format!("{prefix}sentencecount"));
+            }
+            ("sentencecount", None) => self.config.sentencecount = true,
+            ("nosentencecount", None) => self.config.sentencecount = false,
+            ("filetype" | "ft", None) => {
+                let filetype = self
+                    .document
+                    .filetype
+                    .map(|filetype| filetype.to_string())
+                    .unwrap_or_default();
+                self.display_message(format!("filetype={filetype}"));
+            }
+            ("filetype" | "ft", Some(value)) => {
+                self.document.filetype = Some(value.parse::<Filetype>()?);
+                self.apply_filetype_defaults();
+            }
+            ("spell" | "nospell" | "spelllang" | "spellfile", _) => {
+                self.set_spell_option(name, value, query)?;
+            }
+            ("lint" | "nolint" | "sentencelength", _) => {
+                self.set_lint_option(name, value, query)?;
+            }
+            _ => return Err(format!("Unknown option '{name}'")),
+        }
+        Ok(())
+    }
+
+    /// Set one of the spell checking options, or display its value
+    fn set_spell_option(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+        query: bool,
+    ) -> Result<(), String> {
+        match (name, value) {
+            ("spell", None) if query => {
+                let prefix = if self.config.spell { "" } else { "no" };
+                self.display_message(format!("{prefix}spell"));
+            }
+            ("spell", None) => {
+                self.load_dictionary()?;
+                self.config.spell = true;
+            }
+            ("nospell", None) => self.config.spell = false,
+            ("spelllang", None) => {
+                self.display_message(format!("spelllang={}", self.config.spelllang));
+            }
+            ("spelllang", Some(value)) => {
+                self.config.spelllang = value.to_string();
+                self.dictionary = None;
+                if self.config.spell {
+                    self.config.spell = false;
+                    self.load_dictionary()?;
+                    self.config.spell = true;
+                }
+            }
+            ("spellfile", None) => {
+                self.display_message(format!("spellfile={}", self.config.spellfile));
+            }
+            ("spellfile", Some(value)) => {
+                self.config.spellfile = value.to_string();
+                self.dictionary = None;
+                if self.config.spell {
+                    self.load_dictionary()?;
+                }
+            }
+            _ => return Err(format!("Invalid value for option '{name}'")),
+        }
+        Ok(())
+    }
+
+    /// Set one of the prose linter options, or display its value
+    fn set_lint_option(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+        query: bool,
+    ) -> Result<(), String> {
+        match (name, value) {
+            ("lint", None) if query => {
+                let prefix = if self.config.lint { "" } else { "no" };
+                self.display_message(format!("{prefix}lint"));
+            }
+            ("lint", None) => {
+                self.load_linter()?;
+                self.config.lint = true;
+            }
+            ("nolint", None) => self.config.lint = false,
+            ("sentencelength", None) => {
+                self.display_message(format!("sentencelength={}", self.config.sentencelength));
+            }
+            ("sentencelength", Some(value)) => {
+                self.config.sentencelength = parse_width(name, value)?;
+            }
+            _ => return Err(format!("Invalid value for option '{name}'")),
+        }
+        Ok(())
+    }
+
+    /// Load the rule sets of the prose linter, if they weren't loaded already
+    fn load_linter(&mut self) -> Result<(), String> {
+        if self.linter.is_none() {
+            self.linter = Some(Linter::load(&self.config.lint_directory)?);
+        }
+        Ok(())
+    }
+
+    /// Load the dictionary of the spell checking language, along with the
+    /// words of the personal dictionary, if it wasn't loaded already
+    fn load_dictionary(&mut self) -> Result<(), String> {
+        if self.dictionary.is_some() {
+            return Ok(());
+        }
+        let mut dictionary =
+            Dictionary::load(&self.config.spell_directories, &self.config.spelllang)?;
+        if let Ok(words) = fs::read_to_string(self.config.spellfile_path()) {
+            dictionary.add_words(&words);
+        }
+        self.dictionary = Some(dictionary);
+        Ok(())
+    }
+
+    /// Display the list of the style findings of the document, in place of the document
+    pub(crate) fn lint_command(&mut self, _invocation: &Invocation) {
+        if let Err(error) = self.load_linter() {
+            self.display_message(utils::red(&error));
+            return;
+        }
+        self.document.highlight_rows(self.document.num_rows());
+        let findings = self.lint_findings(0..self.document.num_rows());
+        if findings.is_empty() {
+            self.display_message("No style findings".to_string());
+            return;
+        }
+        // keep the list within the screen, leaving room for its title and the bars
+        let max_lines = self.terminal.size().height.saturating_sub(6) as usize;
+        let mut lines: Vec<String> = findings
+            .iter()
+            .take(max_lines)
+            .map(|(range, message)| {
+                let (row_index, x) = range.start;
+                format!(
+                    "Ln {}, Col {}: {message}",
+                    LineNumber::from(row_index).value,
+                    x.value + 1
+                )
+            })
+            .collect();
+        if findings.len() > lines.len() {
+            lines.push(format!("... and {} more", findings.len() - lines.len()));
+        }
+        self.alternate_screen_text =
+            format!("{} style findings\n\n{}", findings.len(), lines.join("\n"));
+        self.alternate_screen = true;
+    }
+
+    /// Replace the word under the cursor by the provided one
+    pub(crate) fn spellreplace_command(&mut self, invocation: &Invocation) {
+        let Some(range) = self.word_under_cursor() else {
+            return;
+        };
+        let row = self.current_row();
+        let start = row.grapheme_index(ByteIndex::new(range.start));
+        let end = row.grapheme_index(ByteIndex::new(range.end));
+        let y = self.current_row_index();
+        let position = Position {
+            x: start,
+            y: y.value,
+        };
+        let word = row.string[range].to_string();
+        self.history.register_forward_deletion(&word, position);
+        self.document.delete_between((y, start), (y, end));
+        self.history
+            .register_insertion(&invocation.arguments, position);
+        self.document.insert_string(&invocation.arguments, start, y);
+        self.goto_x_y(start, y);
+    }
+
+    /// Indent the document the way files of its type conventionally are
+    fn apply_filetype_defaults(&mut self) {
+        match self.document.filetype.and_then(Filetype::indentation) {
+            Some(Indentation::Tabs) => {
+                self.config.expandtab = false;
+                self.config.shiftwidth = self.config.tabstop;
+            }
+            Some(Indentation::Spaces(width)) => {
+                self.config.expandtab = true;
+                self.config.shiftwidth = width;
+            }
+            None => (),
+        }
+    }
+
+    /// Change the style of a highlight group (eg: ``Keyword blue bold``), or
+    /// display the style of all groups if none was provided.
+    pub(crate) fn highlight_command(&mut self, invocation: &Invocation) {
+        if invocation.arguments.is_empty() {
+            let styles: Vec<String> = HighlightGroup::ALL
+                .iter()
+                .map(|group| self.config.theme.describe(*group))
+                .collect();
+            self.display_message(styles.join(" "));
+        } else if let Err(error) = self.config.theme.set(&invocation.arguments) {
+            self.display_message(utils::red(&error));
+        }
+    }
+
+    pub(crate) fn map_command(&mut self, invocation: &Invocation) {
+        self.map_keys(MapMode::Normal, &invocation.arguments, true);
+    }
+
+    pub(crate) fn imap_command(&mut self, invocation: &Invocation) {
+        self.map_keys(MapMode::Insert, &invocation.arguments, true);
+    }
+
+    pub(crate) fn cmap_command(&mut self, invocation: &Invocation) {
+        self.map_keys(MapMode::Prompt, &invocation.arguments, true);
+    }
+
+    pub(crate) fn noremap_command(&mut self, invocation: &Invocation) {
+        self.map_keys(MapMode::Normal, &invocation.arguments, false);
+    }
+
+    pub(crate) fn inoremap_command(&mut self, invocation: &Invocation) {
+        self.map_keys(MapMode::Insert, &invocation.arguments, false);
+    }
+
+    pub(crate) fn cnoremap_command(&mut self, invocation: &Invocation) {
+        self.map_keys(MapMode::Prompt, &invocation.arguments, false);
+    }
+
+    /// Map the keys to other keys in the provided mode (eg: ``jk <Esc>``), or
+    /// display the mode mappings if no keys were provided.
+    fn map_keys(&mut self, mode: MapMode, arguments: &str, recursive: bool) {
+        if arguments.is_empty() {
+            let mappings: Vec<String> = self
+                .keymap
+                .describe(mode)
+                .iter()
+                .map(|(lhs, rhs)| format!("{lhs} {rhs}"))
+                .collect();
+            if mappings.is_empty() {
+                self.display_message(String::from("No mapping found"));
+            } else {
+                self.display_message(mappings.join(" | "));
+            }
+            return;
+        }
+        match arguments.split_once(char::is_whitespace) {
+            Some((lhs, rhs)) if !rhs.trim().is_empty() => {
+                let mapping = Mapping {
+                    keys: keymap::parse_keys(rhs.trim()),
+                    recursive,
+                };
+                self.keymap.insert(mode, keymap::parse_keys(lhs), mapping);
+            }
+            _ => self.display_message(utils::red("Argument required: <rhs>")),
+        }
+    }
+
+    /// Execute each prompt command of the config file, skipping empty lines and
+    /// comments (lines starting with ``"``). Nothing happens if the file does not exist.
+    pub fn load_config_file(&mut self, filename: &Path) {
+        if !filename.is_file() {
+            return;
+        }
+        match fs::read_to_string(filename) {
+            Ok(content) => {
+                for line in content.lines().map(str::trim) {
+                    if !line.is_empty() && !line.starts_with('"') {
+                        self.process_prompt_command(line);
+                    }
+                }
+            }
+            Err(error) => self.display_message(utils::red(&format!(
+                "Error reading {}: {error}",
+                filename.display()
+            ))),
+        }
+    }
+
+    /// Open the provided file in place of the current document
+    fn open(&mut self, filename: &str, encoding: Option<Encoding>) {
+        let path = PathBuf::from(utils::expand_tilde(filename));
+        if self.document.filename == Some(path.clone()) && encoding.is_none() {
+            self.display_message(format!("{filename} is already opened"));
+        } else {
+            self.reopen(&path, encoding);
+        }
+    }
+
+    /// Replace the current document by the content of the file, read with
+    /// the provided encoding (or the detected one)
+    fn reopen(&mut self, path: &Path, encoding: Option<Encoding>) {
+        match Document::open_with_encoding(path.to_path_buf(), encoding) {
+            Ok(document) => {
+                self.document = document;
+                self.last_saved_hash = self.document.hashed();
+                self.reset_message();
+                self.cursor_position = Position::default();
+                self.history = History::default();
+                self.apply_filetype_defaults();
+            }
+            Err(error) => self.display_message(utils::red(&format!(
+                "Error opening {}: {error}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Return the content of the lines in the range, joined by newlines
+    fn lines_content(&self, range: LineRange) -> String {
+        self.document
+            .iter()
+            .skip(range.start_row_index().value)
+            .take(range.num_lines())
+            .map(|row| row.string.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    /// Register the replacement of the lines in the range by the provided lines, joined
+    /// by newlines, as the insertion of the new lines followed by the deletion of the
+    /// original ones, undone at once. Nothing is registered if the lines are unchanged.
+    fn register_lines_replacement(&mut self, range: LineRange, original: &str, new: &str) {
+        if original == new {
+            return;
+        }
+        let row_index = range.start_row_index().value;
+        self.history.register_lines_insertion(new, row_index);
+        self.history
+            .register_lines_deletion(original, row_index.saturating_add(new.split('\n').count()));
+        self.history.group_last_operations(2);
+    }
+
+    /// Store the provided text in the register, as well as in the unnamed register
+    fn store_in_register(&mut self, register: char, text: String) {
+        if register != UNNAMED_REGISTER {
+            self.registers.insert(register, text.clone());
+        }
+        self.registers.insert(UNNAMED_REGISTER, text);
+    }
+
+    /// Delete the lines in the range, and store them in the provided register
+    fn delete_lines(&mut self, range: LineRange, register: char) {
+        let content = self.lines_content(range);
+        let last_row_index = range.end_row_index();
+        // As with ``dd``, the deletion is registered as if the lines had been deleted
+        // with Backspace, from the end of the last line to the end of the line before
+        // the range, which makes it possible to undo it.
+        self.history.register_deletion(
+            &format!("{}\n", content.graphemes(true).rev().collect::<String>()),
+            Position {
+                x: GraphemeIndex::new(self.get_row(last_row_index).map_or(0, Row::len)),
+                y: last_row_index.value,
+            },
+        );
+        for _ in 0..range.num_lines() {
+            self.document.delete_row(range.start_row_index());
+        }
+        self.store_in_register(register, format!("{content}\n"));
+        self.goto_line(
+            cmp::min(range.start, self.document.last_line_number()),
+            GraphemeIndex::new(0),
+        );
+        if range.num_lines() > 1 {
+            self.display_message(format!("{} fewer lines", range.num_lines()));
+        }
+    }
+
+    /// Store the lines in the range in the provided register
+    fn yank_lines(&mut self, range: LineRange, register: char) {
+        let content = self.lines_content(range);
+        self.store_in_register(register, format!("{content}\n"));
+        self.display_message(format!("{} lines yanked", range.num_lines()));
+    }
+
+    /// Resolve the destination address of a ``:m`` or ``:t`` command. Line 0 is a valid
+    /// destination, meaning the top of the document.
+    fn resolve_destination(&mut self, destination: &str) -> Option<LineNumber> {
+        let resolved = match address::parse_range(destination) {
+            Ok((Some(address_range), "")) if address_range.end.is_none() => address_range
+                .start
+                .resolve(&self.document, self.current_line_number()),
+            Ok((None, "")) => Err(String::from("Missing destination address")),
+            Ok(_) => Err(format!("Invalid destination address '{destination}'")),
+            Err(error) => Err(error),
+        };
+        match resolved {
+            Ok(line_number) if line_number <= self.document.last_line_number() => Some(line_number),
+            Ok(_) => {
+                self.display_message(utils::red("Invalid range"));
+                None
+            }
+            Err(error) => {
+                self.display_message(utils::red(&error));
+                None
+            }
+        }
+    }
+
+    /// Move the lines in the range below the destination line (0 meaning the top of the document)
+    fn move_lines(&mut self, range: LineRange, destination: &str) {
+        if let Some(destination) = self.resolve_destination(destination) {
+            if range.start <= destination && destination < range.end {
+                self.display_message(utils::red("Cannot move a range of lines into itself"));
+                return;
+            }
+            // The move is registered as the insertion of the lines below the destination,
+            // followed by the deletion of the original lines, undone at once.
+            let content = self.lines_content(range);
+            self.history
+                .register_lines_insertion(&content, destination.value);
+            self.history.register_lines_deletion(
+                &content,
+                if destination < range.start {
+                    range
+                        .start_row_index()
+                        .value
+                        .saturating_add(range.num_lines())
+                } else {
+                    range.start_row_index().value
+                },
+            );
+            self.history.group_last_operations(2);
+            // the marks of the moved lines follow them
+            let moved_marks: Vec<(char, usize)> = self
+                .document
+                .marks()
+                .iter()
+                .filter(|(_, line_number)| {
+                    range.start <= **line_number && **line_number <= range.end
+                })
+                .map(|(mark, line_number)| (*mark, line_number.value - range.start.value))
+                .collect();
+            let mut rows: Vec<Row> = Vec::with_capacity(range.num_lines());
+            for _ in 0..range.num_lines() {
+                rows.push(self.document.remove_row(range.start_row_index()));
+            }
+            let destination = if destination >= range.end {
+                destination.sub(range.num_lines())
+            } else {
+                destination
+            };
+            for (i, row) in rows.into_iter().enumerate() {
+                self.document
+                    .insert_row(RowIndex::new(destination.value.saturating_add(i)), row);
+            }
+            for (mark, offset) in moved_marks {
+                self.document
+                    .set_mark(mark, destination.add(offset.saturating_add(1)));
+            }
+            self.goto_line(destination.add(range.num_lines()), GraphemeIndex::new(0));
+        }
+    }
+
+    /// Copy the lines in the range below the destination line (0 meaning the top of the document)
+    fn copy_lines(&mut self, range: LineRange, destination: &str) {
+        if let Some(destination) = self.resolve_destination(destination) {
+            let content = self.lines_content(range);
+            let rows: Vec<Row> = content.split('\n').map(Row::from).collect();
+            self.history
+                .register_lines_insertion(&content, destination.value);
+            for (i, row) in rows.into_iter().enumerate() {
+                self.document
+                    .insert_row(RowIndex::new(destination.value.saturating_add(i)), row);
+            }
+            self.goto_line(destination.add(range.num_lines()), GraphemeIndex::new(0));
+        }
+    }
+
+    /// Join all the lines in the range with a space
+    fn join_lines(&mut self, range: LineRange) {
+        let start_row_index = range.start_row_index();
+        let original_content = self.lines_content(range);
+        for _ in 1..range.num_lines() {
+            if start_row_index.next().value >= self.document.num_rows() {
+                break;
+            }
+            let current_row_len = self.get_row(start_row_index).map_or(0, Row::len);
+            self.document.join_row_with_previous_one(
+                GraphemeIndex::new(current_row_len.saturating_sub(1)),
+                start_row_index.next(),
+                Some(' '),
+            );
+        }
+        self.register_lines_replacement(
+            range,
+            &original_content,
+            &self.lines_content(LineRange::single(range.start)),
+        );
+        self.goto_line(range.start, GraphemeIndex::new(0));
+        self.goto_start_or_end_of_line(&Boundary::End);
+    }
+
+    /// Indent (``Boundary::End``) or dedent (``Boundary::Start``) the lines in the range
+    /// by ``shiftwidth`` columns, ``times`` times. Empty lines are left untouched.
+    fn shift_lines(&mut self, range: LineRange, times: usize, direction: &Boundary) {
+        let shift = self.config.shiftwidth.saturating_mul(times);
+        let original_content = self.lines_content(range);
+        for index in range.start_row_index().value..=range.end_row_index().value {
+            let row_index = RowIndex::new(index);
+            let row = match self.get_row(row_index) {
+                Some(row) if !row.is_empty() => row,
+                _ => continue,
+            };
+            let width = row.indentation_width(self.config.tabstop);
+            let width = match direction {
+                Boundary::End => width.add(shift),
+                Boundary::Start => width.sub(shift),
+            };
+            let reindented = row.reindented(width, self.config.tabstop, self.config.expandtab);
+            self.document.replace_row(row_index, reindented);
+        }
+        self.register_lines_replacement(range, &original_content, &self.lines_content(range));
+        self.goto_line(range.end, GraphemeIndex::new(0));
+        self.goto_first_non_whitespace();
+    }
+
+    /// Convert the whitespace sequences containing tabs of the lines in the range so
+    /// that they are displayed the same with the new tab stop, using tabs or spaces
+    /// depending on ``expandtab``. With a bang, sequences of spaces are converted too.
+    fn retab_lines(&mut self, range: LineRange, new_tabstop: usize, convert_spaces: bool) {
+        let original_content = self.lines_content(range);
+        for index in range.start_row_index().value..=range.end_row_index().value {
+            let row_index = RowIndex::new(index);
+            if let Some(row) = self.get_row(row_index) {
+                let retabbed = row.retabbed(
+                    self.config.tabstop,
+                    new_tabstop,
+                    self.config.expandtab,
+                    convert_spaces,
+                );
+                if retabbed != row.string {
+                    self.document.replace_row(row_index, retabbed);
+                }
+            }
+        }
+        self.register_lines_replacement(range, &original_content, &self.lines_content(range));
+        self.config.tabstop = new_tabstop;
+        self.move_cursor_to_position_x(cmp::min(
+            self.current_x_position(),
+            GraphemeIndex::new(self.current_row().len().saturating_sub(1)),
+        ));
+    }
+
+    /// Execute the provided keys as normal mode commands, on each line of the range.
     ///
-    /// If only one command suggestion is found, it will be automatically selected.
+    /// Each line is executed on from its first character, and any pending command,
+    /// prompt or insert mode is terminated at the end of the line.
+    fn execute_normal_keys(&mut self, range: LineRange, keys: &str) {
+        self.stop_receiving_command();
+        self.enter_normal_mode();
+        for line_number in range.start.value..=range.end.value {
+            if line_number > self.document.last_line_number().value {
+                break;
+            }
+            self.goto_line(LineNumber::new(line_number), GraphemeIndex::new(0));
+            for c in keys.chars() {
+                self.process_keystroke(Key::Char(c));
+            }
+            self.flush_pending_keys();
+            self.normal_command_buffer = vec![];
+            self.normal_command_prefix.clear();
+            self.reset_autocompletions();
+            self.stop_receiving_command();
+            if self.mode == Mode::Insert {
+                self.enter_normal_mode();
+            }
+        }
+    }
+
+    /// Set the mark to the provided line
+    fn set_mark(&mut self, line_number: LineNumber, mark: &str) {
+        let mut chars = mark.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => {
+                self.document.set_mark(c, line_number);
+            }
+            _ => self.display_message(utils::red("Invalid mark name")),
+        }
+    }
+
+    /// Write the lines in the range to the provided file, without renaming the document
+    fn write_lines(&mut self, range: LineRange, filename: &str) {
+        if filename.is_empty() {
+            self.display_message(utils::red(
+                "Cannot write a partial buffer to the current file",
+            ));
+            return;
+        }
+        let path = PathBuf::from(utils::expand_tilde(filename));
+        if self
+            .document
+            .write_rows(&path, range.start_row_index(), range.end_row_index())
+            .is_ok()
+        {
+            self.display_message(format!("{} lines written to {filename}", range.num_lines()));
+        } else {
+            self.display_message(utils::red("Error writing to file!"));
+        }
+    }
+
+    /// Insert the lines of the file below the provided line
+    fn read_lines(&mut self, line_number: LineNumber, filename: &str) {
+        let path = PathBuf::from(utils::expand_tilde(filename));
+        let lines = match Document::read_lines(&path) {
+            Ok(lines) if !lines.is_empty() => lines,
+            Ok(_) => return,
+            Err(error) => {
+                self.display_message(utils::red(&format!("Error reading {filename}: {error}")));
+                return;
+            }
+        };
+        self.history
+            .register_lines_insertion(&lines.join("\n"), line_number.value);
+        let num_lines = lines.len();
+        for (i, line) in lines.into_iter().enumerate() {
+            self.document.insert_row(
+                RowIndex::new(line_number.value.saturating_add(i)),
+                Row::from(line),
+            );
+        }
+        self.goto_line(line_number.next(), GraphemeIndex::new(0));
+        self.display_message(format!("{num_lines} lines read from {filename}"));
+    }
+
+    /// Determine which commands, or command arguments, could be autocompleted into
+    /// based on the current state of the user provided command.
+    ///
+    /// The command name is completed until it is followed by an argument, after which
+    /// the argument gets completed depending on its kind (eg: register or mark names).
+    ///
+    /// If only one suggestion is found, it will be automatically selected.
     /// Else, the ``command_suggestions`` vector will be populated with the possible
-    /// commands.
+    /// suggestions.
     fn autocomplete_command(&mut self) {
-        let mut matches: Vec<String> = vec![];
+        // the whole command gets completed, and the cursor moved to its end
+        self.prompt_cursor_offset = 0;
         let current_command = self
             .command_buffer
             .strip_prefix(COMMAND_PREFIX)
-            .unwrap_or_default();
-        for command_str in ALL_COMMANDS {
-            if command_str.starts_with(&current_command) {
-                matches.push(command_str.to_owned());
-            }
-        }
+            .unwrap_or_default()
+            .to_string();
+        let Ok((range, rest)) = address::parse_range(&current_command) else {
+            return;
+        };
+        let (name, _, arguments) = commands::split_command(rest);
+        let completing_name =
+            arguments.is_empty() && !rest.ends_with(char::is_whitespace) && !rest.ends_with('!');
+        let (word, candidates) = if completing_name {
+            let candidates = commands::all_command_names()
+                .into_iter()
+                .filter(|command_name| {
+                    range.is_none()
+                        || commands::find(command_name.trim_end_matches('!'))
+                            .is_some_and(|command| command.range != RangeSpec::None)
+                })
+                .collect();
+            (rest.trim_start(), candidates)
+        } else {
+            let kind = commands::find(name).and_then(|command| command.argument.kind());
+            let word = match kind {
+                // only the last path component gets completed
+                Some(ArgumentKind::Filename) => arguments.rsplit('/').next().unwrap_or_default(),
+                _ => arguments,
+            };
+            (word, self.argument_suggestions(kind, arguments))
+        };
+        let matches: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .collect();
+        let prefix = &current_command[..current_command.len() - word.len()];
         match matches.len() {
             0 => (),
-            1 => self.command_buffer = format!("{}{}", COMMAND_PREFIX, matches[0]),
-            _ => self.command_suggestions = matches,
+            1 => self.command_buffer = format!("{COMMAND_PREFIX}{prefix}{}", matches[0]),
+            _ => {
+                self.autocompletion_prefix = prefix.to_string();
+                self.command_suggestions = matches;
+            }
         }
     }
 
+    /// Return the possible values of a command argument of the provided kind. Filename
+    /// suggestions are the entries of the directory the partial argument points to.
+    fn argument_suggestions(&self, kind: Option<ArgumentKind>, argument: &str) -> Vec<String> {
+        let names: Vec<&char> = match kind {
+            Some(ArgumentKind::Filename) => return utils::path_completions(argument),
+            Some(ArgumentKind::Option) => {
+                return OPTION_NAMES.iter().map(ToString::to_string).collect();
+            }
+            Some(ArgumentKind::Register) => self
+                .registers
+                .keys()
+                .filter(|&&name| name != UNNAMED_REGISTER)
+                .collect(),
+            Some(ArgumentKind::Mark) => self.document.marks().keys().collect(),
+            Some(ArgumentKind::Suggestion) => {
+                return match (&self.dictionary, self.word_under_cursor()) {
+                    (Some(dictionary), Some(range)) => {
+                        dictionary.suggestions(&self.current_row().string[range])
+                    }
+                    _ => vec![],
+                };
+            }
+            Some(ArgumentKind::Highlight) => {
+                return HighlightGroup::ALL
+                    .iter()
+                    .map(|group| group.name().to_string())
+                    .collect();
+            }
+            _ => vec![],
+        };
+        let mut suggestions: Vec<String> = names.iter().map(ToString::to_string).collect();
+        suggestions.sort();
+        suggestions
+    }
+
     /// Cycle through the possible command suggestions by incrementing (or resetting)
     /// the ``current_autocompletion_index`` value.
     fn cycle_through_command_suggestions(&mut self) {
@@ -445,10 +1734,12 @@ impl Editor {
     ///
     /// When the document is saved, all trailing spaces will automatically be deleted.
     fn save(&mut self, new_name: &str) {
+        // the rows that are not indexed yet would not be written
+        self.document.finish_indexing();
         // this will trim trailing spaces, which might cause the cursor to get out of bounds
         self.document.trim_trailing_spaces();
-        if self.cursor_position.x >= self.current_row().len() {
-            self.cursor_position.x = self.current_row().len().saturating_sub(1);
+        if self.cursor_position.x.value >= self.current_row().len() {
+            self.cursor_position.x = GraphemeIndex::new(self.current_row().len().saturating_sub(1));
         }
 
         let initial_filename = self.document.filename.clone();
@@ -456,16 +1747,18 @@ impl Editor {
             if self.document.filename.is_none() {
                 self.display_message(utils::red("No file name"));
                 return;
-            } else if self.document.save().is_ok() {
-                self.display_message("File successfully saved".to_string());
-                self.last_saved_hash = self.document.hashed();
-            } else {
-                self.display_message(utils::red("Error writing to file!"));
+            }
+            if let Err(error) = self.document.save() {
+                self.display_message(utils::red(&format!("Error writing to file: {error}")));
                 return;
             }
-        } else if self.document.save_as(new_name).is_ok() {
+            self.display_message("File successfully saved".to_string());
+            self.last_saved_hash = self.document.hashed();
+        } else if let Err(error) = self.document.save_as(new_name) {
+            self.display_message(utils::red(&format!("Error writing to file: {error}")));
+        } else {
             if initial_filename.is_none() {
-                self.display_message(format!("Buffer saved to {}", new_name));
+                self.display_message(format!("Buffer saved to {new_name}"));
             } else {
                 self.display_message(format!(
                     "{} successfully renamed to {}",
@@ -479,8 +1772,7 @@ impl Editor {
                 ));
             }
             self.document.filename = Some(PathBuf::from(new_name));
-        } else {
-            self.display_message(utils::red("Error writing to file!"));
+            self.apply_filetype_defaults();
         }
         self.unsaved_edits = 0;
         self.last_saved_hash = self.document.hashed();
@@ -510,7 +1802,8 @@ impl Editor {
     /// and move the cursor to the first occurence, if any.
     fn process_search_command(&mut self, search_pattern: &str) {
         self.reset_search();
-        for (row_index, row) in self.document.iter().enumerate() {
+        let mut row_index = 0;
+        while let Some(row) = self.document.get_row(RowIndex::new(row_index)) {
             if row.contains(search_pattern) {
                 for match_start_index in row.find_all(search_pattern) {
                     let match_start = Position {
@@ -519,13 +1812,26 @@ impl Editor {
                     };
                     let match_end = Position {
                         x: match_start_index
-                            .saturating_add(1)
-                            .saturating_add(search_pattern.len()),
+                            .next()
+                            .add(search_pattern.graphemes(true).count()),
                         y: row_index.saturating_add(1),
                     };
                     self.search_matches.push((match_start, match_end));
                 }
             }
+            row_index += 1;
+            if self.document.is_mapped() && row_index.is_multiple_of(SEARCH_PROGRESS_INTERVAL) {
+                // only keep the searched rows in memory for as long as needed
+                self.document
+                    .unload_rows_outside(&self.visible_row_indices());
+                self.display_message(format!(
+                    "Searching... {}%",
+                    row_index * 100 / self.document.num_rows()
+                ));
+                if self.refresh_screen().is_err() {
+                    break;
+                }
+            }
         }
         self.display_message(format!("{} matches", self.search_matches.len()));
         self.current_search_match_index = self.search_matches.len().saturating_sub(1);
@@ -559,6 +1865,18 @@ impl Editor {
             self.reset_message();
             self.reset_search();
         }
+        if !self.normal_command_prefix.is_empty() {
+            let mut keys = mem::take(&mut self.normal_command_prefix);
+            if let Key::Char(c) = key {
+                keys.push(c);
+                self.process_prefixed_normal_command(&keys);
+            }
+            return;
+        }
+        if let Key::Ctrl(c) = key {
+            self.process_scroll_command(c);
+            return;
+        }
         if let Key::Char(c) = key {
             match c {
                 '0' => {
@@ -575,7 +1893,9 @@ impl Editor {
                 ':' => self.start_receiving_command(),
                 '/' => self.start_receiving_search_pattern(),
                 'G' => self.goto_start_or_end_of_document(&Boundary::End),
-                'g' => self.goto_start_or_end_of_document(&Boundary::Start),
+                'c' | 'd' | 'g' | 'y' | 'z' | 'f' | 'F' | 't' | 'T' | '[' | ']' => {
+                    self.normal_command_prefix.push(c);
+                }
                 '$' => self.goto_start_or_end_of_line(&Boundary::End),
                 '^' => self.goto_first_non_whitespace(),
                 'H' => self.goto_first_line_of_terminal(),
@@ -585,7 +1905,6 @@ impl Editor {
                 'n' => self.goto_next_search_match(),
                 'N' => self.goto_previous_search_match(),
                 'q' => self.revert_to_main_screen(),
-                'd' => self.delete_current_line(),
                 'x' => self.delete_current_grapheme(),
                 'o' => self.insert_newline_after_current_line(),
                 'O' => self.insert_newline_before_current_line(),
@@ -602,18 +1921,402 @@ impl Editor {
                     self.process_normal_command_n_times(c, times);
                 }
             }
+        }
+    }
+
+    /// Process a multi-key normal command, made of the prefix keys followed by
+    /// the last typed one. The count typed before the first key applies to the
+    /// whole command.
+    fn process_prefixed_normal_command(&mut self, keys: &str) {
+        let (operator, motion) = match keys.chars().next() {
+            Some(operator @ ('c' | 'd' | 'y')) => (Some(operator), &keys[1..]),
+            _ => (None, keys),
+        };
+        let waiting = match motion {
+            // wait for the text object
+            "i" | "a" => operator.is_some(),
+            // wait for the grapheme to search for
+            "f" | "F" | "t" | "T" => true,
+            _ => false,
+        };
+        if waiting {
+            self.normal_command_prefix.push_str(keys);
+            return;
+        }
+        let times = self.pop_normal_command_repetitions();
+        let mut motion_chars = motion.chars();
+        if let Some(command @ ('f' | 'F' | 't' | 'T')) = motion_chars.next() {
+            let search = CharSearch::new(command, motion_chars.as_str());
+            self.last_char_search = Some(search.clone());
+            self.goto_grapheme(operator, &search, times, false);
+            return;
+        }
+        match keys {
+            "gg" => self.goto_start_or_end_of_document(&Boundary::Start),
+            "ge" => self.goto_word(Navigator::find_end_of_previous_word, WordKind::Word, times),
+            "gE" => {
+                self.goto_word(
+                    Navigator::find_end_of_previous_word,
+                    WordKind::BigWord,
+                    times,
+                );
+            }
+            "dd" => self.delete_current_line(),
+            "zz" => self.scroll_cursor_to(&ScreenLine::Middle),
+            "zt" => self.scroll_cursor_to(&ScreenLine::Top),
+            "zb" => self.scroll_cursor_to(&ScreenLine::Bottom),
+            "]s" => self.goto_misspelled_word(true, times),
+            "[s" => self.goto_misspelled_word(false, times),
+            "]l" => self.goto_lint_finding(true, times),
+            "[l" => self.goto_lint_finding(false, times),
+            "z=" => self.pick_spelling_suggestion(),
+            "zg" => self.add_word_to_personal_dictionary(),
+            "c;" | "d;" | "y;" => self.repeat_char_search(operator, false, times),
+            "c," | "d," | "y," => self.repeat_char_search(operator, true, times),
+            "cis" | "dis" | "yis" | "cas" | "das" | "yas" => {
+                let mut keys = keys.chars();
+                let operator = keys.next().unwrap_or_default();
+                let around = keys.next() == Some('a');
+                self.operate_on_sentence(operator, around, times);
+            }
+            _ => (),
+        }
+    }
+
+    /// Scroll the viewport with ``Ctrl-d``/``Ctrl-u`` (half a screen, or the
+    /// number of lines given as count), ``Ctrl-f``/``Ctrl-b`` (a screen minus
+    /// two lines) or ``Ctrl-e``/``Ctrl-y`` (a line, keeping the cursor in place)
+    fn process_scroll_command(&mut self, c: char) {
+        let count = if self.normal_command_buffer.is_empty() {
+            None
+        } else {
+            Some(self.pop_normal_command_repetitions())
+        };
+        let height = self.text_area_height();
+        let top = self.offset.rows;
+        let row = self.current_row_index().value;
+        match c {
+            'd' => {
+                let lines = count.unwrap_or(height / 2);
+                self.scroll_to(top.saturating_add(lines), row.saturating_add(lines));
+            }
+            'u' => {
+                let lines = count.unwrap_or(height / 2);
+                self.scroll_to(top.saturating_sub(lines), row.saturating_sub(lines));
+            }
+            'f' => {
+                let lines = count.unwrap_or(1) * height.saturating_sub(2).max(1);
+                self.scroll_to(top.saturating_add(lines), top.saturating_add(lines));
+            }
+            'b' => {
+                let lines = count.unwrap_or(1) * height.saturating_sub(2).max(1);
+                let top = top.saturating_sub(lines);
+                self.scroll_to(top, top + height.saturating_sub(1));
+            }
+            'e' => self.scroll_to(top.saturating_add(count.unwrap_or(1)), row),
+            'y' => self.scroll_to(top.saturating_sub(count.unwrap_or(1)), row),
+            _ => (),
+        }
+    }
+
+    /// Scroll the viewport so that the current row is displayed at the top,
+    /// middle or bottom of the screen (``zt``, ``zz`` and ``zb``), within the
+    /// ``scrolloff`` margins
+    fn scroll_cursor_to(&mut self, line: &ScreenLine) {
+        let row = self.current_row_index().value;
+        let scrolloff = self.scrolloff();
+        let top = match line {
+            ScreenLine::Top => row.saturating_sub(scrolloff),
+            ScreenLine::Middle => row
+                .saturating_sub(RowIndex::from(self.terminal.middle_of_screen_line_number()).value),
+            ScreenLine::Bottom => (row + scrolloff + 1).saturating_sub(self.text_area_height()),
+        };
+        self.scroll_to(top, row);
+    }
+
+    /// Apply the operator (``c``hange, ``d``elete or ``y``ank) to the sentence
+    /// under the cursor and the ``times - 1`` following ones. The affected text
+    /// is stored in the unnamed register.
+    fn operate_on_sentence(&mut self, operator: char, around: bool, times: usize) {
+        let Some((start, end)) = Navigator::find_sentence(
+            &self.document,
+            self.current_row_index(),
+            self.current_x_position(),
+            around,
+            times,
+        ) else {
+            return;
+        };
+        self.apply_operator(operator, start, end);
+    }
+
+    /// Move the cursor to the ``times``-th occurrence of the searched grapheme
+    /// in the current line, or apply the operator up to it. As in vim, forward
+    /// searches include the grapheme the cursor lands on, and backward
+    /// searches exclude the one under the cursor.
+    fn goto_grapheme(
+        &mut self,
+        operator: Option<char>,
+        search: &CharSearch,
+        times: usize,
+        repeated: bool,
+    ) {
+        let x = self.current_x_position();
+        let Some(target) = Navigator::find_grapheme(self.current_row(), x, search, times, repeated)
+        else {
+            return;
+        };
+        let row_index = self.current_row_index();
+        let Some(operator) = operator else {
+            self.goto_x_y(target, row_index);
+            return;
+        };
+        let (start, end) = if search.forward {
+            (x, target.next())
+        } else {
+            (target, x)
+        };
+        if start < end {
+            self.apply_operator(operator, (row_index, start), (row_index, end));
+        }
+    }
+
+    /// Repeat the last in-line grapheme search (``;``), or repeat it in the
+    /// opposite direction (``,``)
+    fn repeat_char_search(&mut self, operator: Option<char>, reverse: bool, times: usize) {
+        let Some(search) = self.last_char_search.clone() else {
+            return;
+        };
+        let search = if reverse { search.reversed() } else { search };
+        self.goto_grapheme(operator, &search, times, true);
+    }
+
+    /// Apply the operator (``c``hange, ``d``elete or ``y``ank) to the text
+    /// located between the provided positions, the end one being excluded,
+    /// and store that text in the unnamed register
+    fn apply_operator(&mut self, operator: char, start: DocumentPosition, end: DocumentPosition) {
+        let text = self.document.text_between(start, end);
+        if operator != 'y' {
+            self.history.register_forward_deletion(
+                &text,
+                Position {
+                    x: start.1,
+                    y: start.0.value,
+                },
+            );
+            self.document.delete_between(start, end);
+        }
+        self.store_in_register(UNNAMED_REGISTER, text);
+        if operator == 'c' {
+            self.enter_insert_mode();
+        }
+        self.goto_x_y(start.1, start.0);
+    }
+
+    /// Return the byte range of the word located under the cursor, in the current row
+    fn word_under_cursor(&self) -> Option<Range<usize>> {
+        let row = self.current_row();
+        let x = row.byte_index(self.current_x_position()).value;
+        row.string
+            .split_word_bound_indices()
+            .map(|(start, word)| start..start + word.len())
+            .find(|range| range.contains(&x))
+            .filter(|range| row.string[range.clone()].chars().any(char::is_alphabetic))
+    }
+
+    /// Return the byte ranges of the misspelled words of the row, which are
+    /// empty if spell checking is disabled. Markdown code isn't spell checked.
+    fn misspelled_words(&self, index: RowIndex) -> Vec<Range<usize>> {
+        let (Some(dictionary), Some(row)) = (&self.dictionary, self.get_row(index)) else {
+            return vec![];
+        };
+        if !self.config.spell {
+            return vec![];
+        }
+        let code: Vec<Range<usize>> = self
+            .document
+            .highlights(index)
+            .iter()
+            .filter(|span| span.group == HighlightGroup::Code)
+            .map(|span| span.range.clone())
+            .collect();
+        dictionary.misspelled_words(&row.string, &code)
+    }
+
+    /// Move the cursor to the start of the next (or previous) misspelled word,
+    /// wrapping around the document
+    fn goto_misspelled_word(&mut self, forward: bool, times: usize) {
+        if !self.config.spell {
+            self.display_message(utils::red("Spell checking is not enabled"));
+            return;
+        }
+        // the code spans of the whole document need to be known
+        self.document.highlight_rows(self.document.num_rows());
+        let num_rows = self.document.num_rows();
+        let mut y = self.current_row_index().value;
+        let mut x = self.current_x_position();
+        for _ in 0..times {
+            let mut found = None;
+            // the current row is searched again last, for the words preceding
+            // (or following) the cursor
+            for i in 0..=num_rows {
+                let index = if forward {
+                    (y + i) % num_rows
+                } else {
+                    (y + num_rows - i % num_rows) % num_rows
+                };
+                let Some(row) = self.get_row(RowIndex::new(index)) else {
+                    continue;
+                };
+                let starts: Vec<GraphemeIndex> = self
+                    .misspelled_words(RowIndex::new(index))
+                    .into_iter()
+                    .map(|range| row.grapheme_index(ByteIndex::new(range.start)))
+                    .collect();
+                found = if forward {
+                    starts.into_iter().find(|start| i > 0 || *start > x)
+                } else {
+                    starts.into_iter().rfind(|start| i > 0 || *start < x)
+                }
+                .map(|start| (start, index));
+                if found.is_some() {
+                    break;
+                }
+            }
+            let Some((start, index)) = found else {
+                self.display_message(utils::red("No misspelled words"));
+                return;
+            };
+            (x, y) = (start, index);
+        }
+        self.goto_x_y(x, RowIndex::new(y));
+    }
+
+    /// Open the prompt with the spelling suggestions for the word under the
+    /// cursor, which can be cycled through with Tab, the selected one
+    /// replacing the word when pressing Enter
+    fn pick_spelling_suggestion(&mut self) {
+        let Some(dictionary) = &self.dictionary else {
+            self.display_message(utils::red("Spell checking is not enabled"));
+            return;
+        };
+        let Some(range) = self.word_under_cursor() else {
+            return;
+        };
+        let suggestions = dictionary.suggestions(&self.current_row().string[range]);
+        if suggestions.is_empty() {
+            self.display_message("No spelling suggestions".to_string());
+            return;
+        }
+        self.start_receiving_command();
+        self.command_buffer.push_str("spellreplace ");
+        self.autocompletion_prefix = "spellreplace ".to_string();
+        self.command_suggestions = suggestions;
+    }
+
+    /// Add the word under the cursor to the personal dictionary
+    fn add_word_to_personal_dictionary(&mut self) {
+        let Some(range) = self.word_under_cursor() else {
+            return;
+        };
+        let word = self.current_row().string[range].to_string();
+        let path = self.config.spellfile_path();
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::OpenOptions::new().create(true).append(true).open(&path))
+            .and_then(|mut file| writeln!(file, "{word}"));
+        match written {
+            Ok(()) => {
+                if let Some(dictionary) = &mut self.dictionary {
+                    dictionary.add(&word);
+                }
+                self.display_message(format!("Word '{word}' added to {}", path.display()));
+            }
+            Err(error) => self.display_message(utils::red(&format!(
+                "Error writing to {}: {error}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Return the style findings of the paragraphs containing the rows, along
+    /// with the document positions they span. Markdown code isn't linted.
+    fn lint_findings(&self, rows: Range<usize>) -> Vec<(Range<DocumentPosition>, String)> {
+        let Some(linter) = &self.linter else {
+            return vec![];
+        };
+        let mut findings = vec![];
+        for paragraph in Navigator::paragraphs(&self.document, rows) {
+            let sentences = paragraph.sentences();
+            for finding in linter.lint(&paragraph.text, &sentences, self.config.sentencelength) {
+                let start = paragraph.position(&self.document, finding.range.start);
+                let end = paragraph.position(&self.document, finding.range.end);
+                if !self.is_code(start) {
+                    findings.push((start..end, finding.message));
+                }
+            }
+        }
+        findings
+    }
+
+    /// Return whether the document position is located in Markdown code
+    fn is_code(&self, (row_index, x): DocumentPosition) -> bool {
+        let Some(row) = self.get_row(row_index) else {
+            return false;
+        };
+        let byte_index = row.byte_index(x).value;
+        self.document
+            .highlights(row_index)
+            .iter()
+            .any(|span| span.group == HighlightGroup::Code && span.range.contains(&byte_index))
+    }
+
+    /// Move the cursor to the start of the next (or previous) style finding,
+    /// wrapping around the document, and display its message
+    fn goto_lint_finding(&mut self, forward: bool, times: usize) {
+        if let Err(error) = self.load_linter() {
+            self.display_message(utils::red(&error));
+            return;
+        }
+        // the code spans of the whole document need to be known
+        self.document.highlight_rows(self.document.num_rows());
+        let findings = self.lint_findings(0..self.document.num_rows());
+        if findings.is_empty() {
+            self.display_message("No style findings".to_string());
+            return;
+        }
+        let cursor = (self.current_row_index(), self.current_x_position());
+        let count = findings.len();
+        let index = if forward {
+            let next = findings.partition_point(|(range, _)| range.start <= cursor);
+            (next + times - 1) % count
+        } else {
+            let previous = findings.partition_point(|(range, _)| range.start < cursor);
+            (previous + count * times - times) % count
         };
+        let ((row_index, x), message) = (findings[index].0.start, &findings[index].1);
+        self.goto_x_y(x, row_index);
+        self.display_message(format!("{message} ({}/{count})", index + 1));
     }
 
     /// Execute the provided normal movement command n timess
     fn process_normal_command_n_times(&mut self, c: char, n: usize) {
         match c {
-            'b' => self.goto_start_or_end_of_word(&Boundary::Start, n),
-            'w' => self.goto_start_or_end_of_word(&Boundary::End, n),
+            'b' => self.goto_word(Navigator::find_start_of_previous_word, WordKind::Word, n),
+            'B' => self.goto_word(Navigator::find_start_of_previous_word, WordKind::BigWord, n),
+            'w' => self.goto_word(Navigator::find_start_of_next_word, WordKind::Word, n),
+            'W' => self.goto_word(Navigator::find_start_of_next_word, WordKind::BigWord, n),
+            'e' => self.goto_word(Navigator::find_end_of_next_word, WordKind::Word, n),
+            'E' => self.goto_word(Navigator::find_end_of_next_word, WordKind::BigWord, n),
             'h' => self.move_cursor(&Direction::Left, n),
             'j' => self.move_cursor(&Direction::Down, n),
             'k' => self.move_cursor(&Direction::Up, n),
             'l' => self.move_cursor(&Direction::Right, n),
+            ';' => self.repeat_char_search(None, false, n),
+            ',' => self.repeat_char_search(None, true, n),
+            ')' => self.goto_sentence(Navigator::find_start_of_next_sentence, n),
+            '(' => self.goto_sentence(Navigator::find_start_of_previous_sentence, n),
             '}' => self.goto_start_or_end_of_paragraph(&Boundary::End, n),
             '{' => self.goto_start_or_end_of_paragraph(&Boundary::Start, n),
             '%' => self.goto_percentage_in_document(n),
@@ -631,36 +2334,48 @@ impl Editor {
             Key::Backspace => {
                 // When Backspace is pressed on the first column of a line, it means that we
                 // should append the current line with the previous one
-                if self.cursor_position.x == 0 {
+                if self.cursor_position.x.value == 0 {
                     if self.cursor_position.y > 0 {
-                        let previous_line_len =
-                            self.get_row(self.previous_row_index()).unwrap().len();
+                        let previous_line_len = GraphemeIndex::new(
+                            self.get_row(self.previous_row_index()).unwrap().len(),
+                        );
                         // Delete newline from previous row
                         self.history.register_deletion("\n", self.cursor_position);
-                        self.document.delete(0, 0, self.current_row_index());
+                        self.document.delete(
+                            GraphemeIndex::new(0),
+                            GraphemeIndex::new(0),
+                            self.current_row_index(),
+                        );
                         self.goto_x_y(previous_line_len, self.previous_row_index());
                     }
                 } else {
-                    // Delete previous character
-                    let previous_grapheme = self.previous_grapheme().to_string();
-                    self.history
-                        .register_deletion(previous_grapheme.as_str(), self.cursor_position);
-                    self.document.delete(
-                        self.current_x_position().saturating_sub(1),
-                        self.current_x_position(),
-                        self.current_row_index(),
-                    );
-                    self.move_cursor(&Direction::Left, 1);
+                    // Delete previous character, or the previous soft tab
+                    for _ in 0..self.soft_tab_len_before_cursor().max(1) {
+                        let previous_grapheme = self.previous_grapheme().to_string();
+                        self.history
+                            .register_deletion(previous_grapheme.as_str(), self.cursor_position);
+                        self.document.delete(
+                            self.current_x_position().previous(),
+                            self.current_x_position(),
+                            self.current_row_index(),
+                        );
+                        self.move_cursor(&Direction::Left, 1);
+                    }
                 }
             }
             Key::Char('\n') => {
                 self.history.register_insertion("\n", self.cursor_position);
                 self.document
                     .insert_newline(self.current_x_position(), self.current_row_index());
-                self.goto_x_y(0, self.next_row_index());
+                self.goto_x_y(GraphemeIndex::new(0), self.next_row_index());
             }
-            Key::Char('\t') => {
-                for _ in 0..SPACES_PER_TAB {
+            Key::Char('\t') if self.config.expandtab => {
+                // insert spaces up to the next indentation level
+                let column = self
+                    .current_row()
+                    .column_of(self.current_x_position(), self.config.tabstop);
+                let shiftwidth = self.config.shiftwidth.max(1);
+                for _ in 0..shiftwidth - column.value % shiftwidth {
                     self.history.register_insertion(" ", self.cursor_position);
                     self.document
                         .insert(' ', self.current_x_position(), self.current_row_index());
@@ -682,6 +2397,29 @@ impl Editor {
         }
     }
 
+    /// Return the number of spaces preceding the cursor, up to the previous
+    /// indentation level, if ``expandtab`` is set, as they were then most likely
+    /// inserted by Tab and should be deleted at once.
+    fn soft_tab_len_before_cursor(&self) -> usize {
+        if !self.config.expandtab {
+            return 0;
+        }
+        let x = self.current_x_position();
+        let column = self.current_row().column_of(x, self.config.tabstop);
+        let shiftwidth = self.config.shiftwidth.max(1);
+        let max_len = match column.value % shiftwidth {
+            0 => shiftwidth,
+            len => len,
+        };
+        let preceding: Vec<&str> = self.current_row().graphemes().take(x.value).collect();
+        preceding
+            .iter()
+            .rev()
+            .take(max_len)
+            .take_while(|grapheme| **grapheme == " ")
+            .count()
+    }
+
     /// Return the row located at the provide row index if it exists
     fn get_row(&self, index: RowIndex) -> Option<&Row> {
         self.document.get_row(index)
@@ -708,8 +2446,59 @@ impl Editor {
     }
 
     /// Return the current x position, taking the offset into account
-    fn current_x_position(&self) -> usize {
-        self.cursor_position.x.saturating_add(self.offset.columns)
+    fn current_x_position(&self) -> GraphemeIndex {
+        self.cursor_position.x.add(self.offset.columns)
+    }
+
+    /// Return the display column of the first visible column of the rows, which
+    /// is where the first grapheme of the current row that isn't scrolled out of
+    /// view starts
+    fn scroll_column(&self) -> Column {
+        self.current_row()
+            .column_of(GraphemeIndex::new(self.offset.columns), self.config.tabstop)
+    }
+
+    /// Return the column at which the cursor is displayed in the text area
+    fn cursor_screen_column(&self) -> Column {
+        self.current_row()
+            .column_of(self.current_x_position(), self.config.tabstop)
+            .sub(self.scroll_column().value)
+    }
+
+    /// Return the number of columns available to display the rows
+    fn text_area_width(&self) -> usize {
+        let width = self.terminal.size().width as usize;
+        if self.config.focus {
+            cmp::min(width, self.config.focuswidth)
+        } else if self.row_prefix_length > 0 {
+            width
+                .saturating_sub(self.row_prefix_length as usize)
+                .saturating_sub(1)
+        } else {
+            width
+        }
+    }
+
+    /// Return the number of blank columns displayed on the left of the rows,
+    /// centering them on screen in focus mode
+    fn text_area_margin(&self) -> usize {
+        if self.config.focus {
+            (self.terminal.size().width as usize).saturating_sub(self.text_area_width()) / 2
+        } else {
+            0
+        }
+    }
+
+    /// Scroll the rows horizontally until the cursor is displayed within the
+    /// text area, as wide graphemes can push it past the right edge of the screen
+    fn scroll_to_cursor_column(&mut self) {
+        let text_area_width = self.text_area_width();
+        while self.cursor_position.x.value > 0
+            && self.cursor_screen_column().value >= text_area_width
+        {
+            self.cursor_position.x = self.cursor_position.x.previous();
+            self.offset.columns += 1;
+        }
     }
 
     /// Return the character currently under the cursor
@@ -720,7 +2509,7 @@ impl Editor {
     /// Return the character currently at the left of the cursor
     fn previous_grapheme(&self) -> &str {
         self.current_row()
-            .nth_grapheme(self.current_x_position().saturating_sub(1))
+            .nth_grapheme(self.current_x_position().previous())
     }
 
     /// Return the line number associated to the current cursor position / vertical offset
@@ -734,7 +2523,7 @@ impl Editor {
         self.history.register_deletion(
             &current_row_str,
             Position {
-                x: 0,
+                x: GraphemeIndex::new(0),
                 y: self.cursor_position.y,
             },
         );
@@ -748,12 +2537,14 @@ impl Editor {
                 // If not, move to the last character.
                 cmp::min(
                     self.cursor_position.x,
-                    self.document
-                        .get_row(RowIndex::new(self.cursor_position.y.saturating_sub(1)))
-                        .unwrap_or(&Row::from(""))
-                        .string
-                        .graphemes(true)
-                        .count(),
+                    GraphemeIndex::new(
+                        self.document
+                            .get_row(RowIndex::new(self.cursor_position.y.saturating_sub(1)))
+                            .unwrap_or(&Row::from(""))
+                            .string
+                            .graphemes(true)
+                            .count(),
+                    ),
                 ),
             );
         } else {
@@ -776,26 +2567,26 @@ impl Editor {
     /// Insert a newline after the current one, move cursor to it in insert mode
     fn insert_newline_after_current_line(&mut self) {
         let eol_position = Position {
-            x: self.current_row().len(),
+            x: GraphemeIndex::new(self.current_row().len()),
             y: self.current_row_index().value,
         };
         self.document
             .insert_newline(eol_position.x, RowIndex::new(eol_position.y));
         self.history.register_insertion("\n", eol_position);
-        self.goto_x_y(0, self.next_row_index());
+        self.goto_x_y(GraphemeIndex::new(0), self.next_row_index());
         self.enter_insert_mode();
     }
 
     /// Insert a newline before the current one, move cursor to it in insert mode
     fn insert_newline_before_current_line(&mut self) {
         let sol_position = Position {
-            x: 0,
+            x: GraphemeIndex::new(0),
             y: self.current_row_index().value,
         };
         self.document
             .insert_newline(sol_position.x, RowIndex::new(sol_position.y));
         self.history.register_insertion("\n", sol_position);
-        self.goto_x_y(0, self.current_row_index());
+        self.goto_x_y(GraphemeIndex::new(0), self.current_row_index());
         self.enter_insert_mode();
     }
 
@@ -811,7 +2602,7 @@ impl Editor {
         if self.current_row_index().value < self.document.num_rows() {
             // let next_line_row_index = self.cursor_position.y.saturating_add(1);
             self.document.join_row_with_previous_one(
-                self.current_row().len().saturating_sub(1), // discard the \n
+                GraphemeIndex::new(self.current_row().len().saturating_sub(1)), // discard the \n
                 self.next_row_index(),
                 Some(' '),
             );
@@ -828,37 +2619,65 @@ impl Editor {
                 self.current_line_number(),
                 boundary,
             );
-            self.goto_line(next_line_number, 0);
+            self.goto_line(next_line_number, GraphemeIndex::new(0));
         }
     }
 
     /// Move the cursor either to the first or last line of the document
     fn goto_start_or_end_of_document(&mut self, boundary: &Boundary) {
         match boundary {
-            Boundary::Start => self.goto_line(LineNumber::new(1), 0),
-            Boundary::End => self.goto_line(self.document.last_line_number(), 0),
+            Boundary::Start => self.goto_line(LineNumber::new(1), GraphemeIndex::new(0)),
+            Boundary::End => {
+                self.goto_line(self.document.last_line_number(), GraphemeIndex::new(0));
+            }
         }
     }
 
     /// Move the cursor either to the start or end of the line
     fn goto_start_or_end_of_line(&mut self, boundary: &Boundary) {
         match boundary {
-            Boundary::Start => self.move_cursor_to_position_x(0),
+            Boundary::Start => self.move_cursor_to_position_x(GraphemeIndex::new(0)),
             Boundary::End => {
-                self.move_cursor_to_position_x(self.current_row().len().saturating_sub(1));
+                self.move_cursor_to_position_x(GraphemeIndex::new(
+                    self.current_row().len().saturating_sub(1),
+                ));
             }
         }
     }
 
-    /// Move to the start of the next word or previous one.
-    fn goto_start_or_end_of_word(&mut self, boundary: &Boundary, times: usize) {
+    /// Move the cursor ``times`` times to the position returned by the provided
+    /// word motion, which can land on another line
+    fn goto_word(
+        &mut self,
+        motion: fn(&Document, RowIndex, GraphemeIndex, WordKind) -> DocumentPosition,
+        kind: WordKind,
+        times: usize,
+    ) {
         for _ in 0..times {
-            let x = Navigator::find_index_of_next_or_previous_word(
-                self.current_row(),
+            let (row_index, x) = motion(
+                &self.document,
+                self.current_row_index(),
                 self.current_x_position(),
-                boundary,
+                kind,
             );
-            self.move_cursor_to_position_x(x);
+            self.goto_x_y(x, row_index);
+        }
+    }
+
+    /// Move the cursor ``times`` times to the position returned by the provided
+    /// sentence motion
+    fn goto_sentence(
+        &mut self,
+        motion: fn(&Document, RowIndex, GraphemeIndex) -> DocumentPosition,
+        times: usize,
+    ) {
+        for _ in 0..times {
+            let (row_index, x) = motion(
+                &self.document,
+                self.current_row_index(),
+                self.current_x_position(),
+            );
+            self.goto_x_y(x, row_index);
         }
     }
 
@@ -875,13 +2694,16 @@ impl Editor {
             self.terminal
                 .middle_of_screen_line_number()
                 .add(self.offset.rows),
-            0,
+            GraphemeIndex::new(0),
         );
     }
 
     /// Move the cursor to the middle of the terminal
     fn goto_first_line_of_terminal(&mut self) {
-        self.goto_line(LineNumber::new(self.offset.rows.saturating_add(1)), 0);
+        self.goto_line(
+            LineNumber::new(self.offset.rows.saturating_add(1)),
+            GraphemeIndex::new(0),
+        );
     }
 
     /// Move the cursor to the last line of the terminal
@@ -890,7 +2712,7 @@ impl Editor {
             self.terminal
                 .bottom_of_screen_line_number()
                 .add(self.offset.rows),
-            0,
+            GraphemeIndex::new(0),
         );
     }
 
@@ -898,7 +2720,7 @@ impl Editor {
     fn goto_percentage_in_document(&mut self, percent: usize) {
         let percent = cmp::min(percent, 100);
         let line_number = LineNumber::new(self.document.last_line_number().value * percent / 100);
-        self.goto_line(line_number, 0);
+        self.goto_line(line_number, GraphemeIndex::new(0));
     }
 
     /// Go to the matching closing symbol (whether that's a quote, curly/square/regular brace, etc).
@@ -924,7 +2746,7 @@ impl Editor {
                 }
             }
             _ => (),
-        };
+        }
     }
 
     /// Move to the first character of the next search match
@@ -972,7 +2794,7 @@ impl Editor {
     }
 
     /// Move the cursor to the nth line in the file and adjust the viewport
-    fn goto_line(&mut self, line_number: LineNumber, x_position: usize) {
+    fn goto_line(&mut self, line_number: LineNumber, x_position: GraphemeIndex) {
         self.goto_x_y(x_position, RowIndex::from(line_number));
     }
 
@@ -981,7 +2803,7 @@ impl Editor {
     }
 
     /// Move the cursor to the first column of the nth line
-    fn goto_x_y(&mut self, x: usize, y: RowIndex) {
+    fn goto_x_y(&mut self, x: GraphemeIndex, y: RowIndex) {
         self.move_cursor_to_position_x(x);
         self.move_cursor_to_position_y(y);
     }
@@ -1021,16 +2843,18 @@ impl Editor {
                     }
                 }
                 Direction::Left => {
-                    if x >= term_width {
+                    if x.value >= term_width {
                         offset_x = offset_x.saturating_sub(1);
                     } else {
-                        x = x.saturating_sub(1);
+                        x = x.previous();
                     }
                 }
                 Direction::Right => {
-                    if x.saturating_add(offset_x) <= self.current_row().len().saturating_sub(1) {
-                        if x < term_width {
-                            x = x.saturating_add(1);
+                    if x.value.saturating_add(offset_x)
+                        <= self.current_row().len().saturating_sub(1)
+                    {
+                        if x.value < term_width {
+                            x = x.next();
                         } else {
                             offset_x = offset_x.saturating_add(1);
                         }
@@ -1046,7 +2870,10 @@ impl Editor {
         // would cause teh cursor to be placed outside of the destination line x boundary,
         // we make sure to place the cursor on the last character of the line.
         if self.mode == Mode::Normal {
-            self.cursor_position.x = cmp::min(self.current_row().len().saturating_sub(1), x);
+            self.cursor_position.x = cmp::min(
+                GraphemeIndex::new(self.current_row().len().saturating_sub(1)),
+                x,
+            );
         } else {
             self.cursor_position.x = x;
         }
@@ -1093,12 +2920,12 @@ impl Editor {
 
     /// Move the cursor to the associated x non-negative position, adjusting the x offset
     /// if that takes the cursor out of the current view.
-    fn move_cursor_to_position_x(&mut self, x: usize) {
+    fn move_cursor_to_position_x(&mut self, x: GraphemeIndex) {
         let term_width = self.terminal.size().width as usize;
-        let x = cmp::max(0, x);
-        if x > term_width {
-            self.cursor_position.x = term_width.saturating_sub(1);
+        if x.value > term_width {
+            self.cursor_position.x = GraphemeIndex::new(term_width.saturating_sub(1));
             self.offset.columns = x
+                .value
                 .saturating_sub(term_width)
                 .saturating_sub(self.offset.columns)
                 .saturating_add(1);
@@ -1108,36 +2935,149 @@ impl Editor {
         }
     }
 
+    /// Return the number of rows of the text area
+    fn text_area_height(&self) -> usize {
+        self.terminal.text_area_size().height as usize
+    }
+
+    /// Return the number of rows kept displayed above and below the cursor,
+    /// reduced so that both margins fit on screen
+    fn scrolloff(&self) -> usize {
+        cmp::min(
+            self.config.scrolloff,
+            self.text_area_height().saturating_sub(1) / 2,
+        )
+    }
+
+    /// Return the vertical offset at which the last row of the document is
+    /// displayed at the bottom of the screen, past which the viewport doesn't scroll
+    fn max_offset_rows(&self) -> usize {
+        self.document
+            .num_rows()
+            .saturating_sub(self.text_area_height())
+    }
+
+    /// Display the document from the ``top`` row, and move the cursor to the
+    /// provided row, or to the closest one that is displayed outside of the
+    /// ``scrolloff`` margins. The cursor keeps its column when possible.
+    fn scroll_to(&mut self, top: usize, row: usize) {
+        let top = cmp::min(top, self.max_offset_rows());
+        let height = self.text_area_height();
+        let scrolloff = self.scrolloff();
+        let last_row = self.document.num_rows().saturating_sub(1);
+        let first_allowed = if top == 0 { 0 } else { top + scrolloff };
+        let last_allowed = if top + height > last_row {
+            last_row
+        } else {
+            top + height - 1 - scrolloff
+        };
+        let row = row.clamp(first_allowed, cmp::max(first_allowed, last_allowed));
+        let x = self.current_x_position();
+        self.offset.rows = top;
+        self.cursor_position.y = row.saturating_sub(top);
+        let len = self.current_row().len();
+        self.move_cursor_to_position_x(cmp::min(x, GraphemeIndex::new(len.saturating_sub(1))));
+    }
+
+    /// Scroll the viewport by as few rows as possible for the cursor to be
+    /// displayed on screen, outside of the ``scrolloff`` margins
+    fn scroll_cursor_into_view(&mut self) {
+        let row = self.current_row_index().value;
+        if self.config.focus {
+            // typewriter scrolling: the current row stays in the middle of the screen
+            self.offset.rows = row
+                .saturating_sub(RowIndex::from(self.terminal.middle_of_screen_line_number()).value);
+            self.cursor_position.y = row - self.offset.rows;
+            return;
+        }
+        let scrolloff = self.scrolloff();
+        let min_top = (row + scrolloff + 1).saturating_sub(self.text_area_height());
+        let max_top = row.saturating_sub(scrolloff);
+        let top = self.offset.rows.clamp(min_top, cmp::max(min_top, max_top));
+        self.offset.rows = cmp::min(top, self.max_offset_rows());
+        self.cursor_position.y = row.saturating_sub(self.offset.rows);
+    }
+
+    /// Return the indices of the rows displayed on screen
+    fn visible_row_indices(&self) -> Range<usize> {
+        let height = self.terminal.text_area_size().height as usize;
+        self.offset.rows..self.offset.rows.saturating_add(height)
+    }
+
+    /// Add the newly indexed rows of a mapped file to the document, and free
+    /// the memory used by the rows that went out of view
+    fn index_mapped_rows(&mut self) {
+        if !self.document.is_mapped() {
+            return;
+        }
+        if self.document.indexing_progress().is_some() {
+            let was_dirty = self.is_dirty();
+            self.document.index_mapped_rows();
+            if !was_dirty {
+                // The newly indexed rows are part of the saved file. If the document was
+                // already modified, it will be considered as such until saved.
+                self.last_saved_hash = self.document.hashed();
+            }
+            match self.document.indexing_progress() {
+                Some(progress) => self.display_message(format!("Indexing... {progress}%")),
+                None => self.display_message(format!("{} lines indexed", self.document.num_rows())),
+            }
+        }
+        self.document
+            .unload_rows_outside(&self.visible_row_indices());
+    }
+
     /// Return whether the document has seen some edits since the last save
     fn is_dirty(&self) -> bool {
         self.last_saved_hash != self.document.hashed()
     }
 
-    /// Undo the last registered operation in history
+    /// Undo the last registered operation in history, along with the operations
+    /// grouped with it
     fn undo_last_operation(&mut self) {
-        if let Some(last_op_undone) = self
+        while let Some(last_op_undone) = self
             .history
             .last_operation_reversed(&self.document.row_lengths())
         {
+            let row_index = RowIndex::new(last_op_undone.start_position.y);
             match last_op_undone.op_type {
-                OperationType::Delete => self.document.delete_string(
-                    last_op_undone.content.as_str(),
-                    last_op_undone.start_position.x,
-                    RowIndex::new(last_op_undone.start_position.y),
-                ),
+                OperationType::Delete | OperationType::DeleteForward => {
+                    self.document.delete_string(
+                        last_op_undone.content.as_str(),
+                        last_op_undone.start_position.x,
+                        row_index,
+                    );
+                }
                 OperationType::Insert => self.document.insert_string(
                     last_op_undone.content.as_str(),
                     last_op_undone.start_position.x,
-                    RowIndex::new(last_op_undone.start_position.y),
+                    row_index,
                 ),
+                OperationType::DeleteLines => {
+                    for _ in last_op_undone.content.split('\n') {
+                        self.document.remove_row(row_index);
+                    }
+                }
+                OperationType::InsertLines => {
+                    for (i, line) in last_op_undone.content.split('\n').enumerate() {
+                        self.document.insert_row(
+                            RowIndex::new(row_index.value.saturating_add(i)),
+                            Row::from(line.to_string()),
+                        );
+                    }
+                }
             }
             self.goto_position(last_op_undone.end_position(&self.document.row_lengths()));
+            if !last_op_undone.grouped {
+                break;
+            }
         }
     }
 
     /// Return the x index of the first character of the currently selected autocompletion suggestion
     fn get_x_index_of_currently_selected_suggestion(&self) -> usize {
-        let mut x_index_of_currently_selected_suggestion = SEARCH_PREFIX.to_string().len();
+        let mut x_index_of_currently_selected_suggestion =
+            SEARCH_PREFIX.to_string().len() + self.autocompletion_prefix.len();
         let sep_len = AUTOCOMPLETION_SUGGESTIONS_SEPARATOR.to_string().len();
         for (i, suggestion) in self.command_suggestions.iter().enumerate() {
             if i >= self.current_autocompletion_index {
@@ -1148,23 +3088,39 @@ impl Editor {
         x_index_of_currently_selected_suggestion
     }
 
-    /// Refresh the screen by displaying all rows and bars
+    /// Refresh the screen by drawing all rows and bars into a new frame, and
+    /// only writing the cells that differ from the previously drawn one
     fn refresh_screen(&mut self) -> Result<(), std::io::Error> {
         self.terminal.hide_cursor();
         if !self.should_quit {
-            if self.alternate_screen {
-                self.terminal.clear_all();
-                self.terminal.to_alternate_screen();
-                self.draw_help_screen();
+            self.scroll_to_cursor_column();
+            let size = self.terminal.size();
+            let mut frame = Frame::new(size.width, size.height);
+            let first_row = if self.alternate_screen {
+                self.draw_alternate_screen(&mut frame);
+                None
             } else {
-                self.terminal.to_main_screen();
-                self.draw_rows();
+                self.document.highlight_rows(self.visible_row_indices().end);
+                self.visible_lint_findings = if self.config.lint {
+                    self.lint_findings(self.visible_row_indices())
+                } else {
+                    vec![]
+                };
+                self.draw_rows(&mut frame);
+                Some(self.offset.rows)
+            };
+            self.draw_status_bar(&mut frame);
+            self.draw_message_bar(&mut frame);
+            if !self.alternate_screen {
+                self.draw_pending_keys(&mut frame);
             }
-            self.draw_status_bar();
-            self.draw_message_bar();
+            self.scroll_last_frame(&frame, first_row);
+            frame.draw(self.last_frame.as_ref(), self.terminal.as_ref());
+            self.last_frame = Some(frame);
+            self.last_frame_first_row = first_row;
             if self.alternate_screen {
                 self.terminal.set_cursor_position_in_text_area(
-                    &Position::top_left(),
+                    &ScreenPosition::top_left(),
                     self.row_prefix_length,
                 );
             } else if self.is_receiving_command() {
@@ -1172,20 +3128,23 @@ impl Editor {
                     // if we're currently auto-completing the user-provided command,
                     // we move the cursor on the first character of the currently selected
                     // suggestion.
-                    self.terminal.set_cursor_position_anywhere(&Position {
-                        x: self.get_x_index_of_currently_selected_suggestion(),
+                    self.terminal.set_cursor_position_anywhere(&ScreenPosition {
+                        x: Column::new(self.get_x_index_of_currently_selected_suggestion()),
                         y: self.terminal.size().height as usize,
                     });
                 } else {
                     // if a command is being typed, put the cursor in the bottom bar
-                    self.terminal.set_cursor_position_anywhere(&Position {
-                        x: self.command_buffer.len(),
+                    self.terminal.set_cursor_position_anywhere(&ScreenPosition {
+                        x: Column::new(self.prompt_cursor_position()),
                         y: self.terminal.size().height as usize,
                     });
                 }
             } else {
                 self.terminal.set_cursor_position_in_text_area(
-                    &self.cursor_position,
+                    &ScreenPosition {
+                        x: self.cursor_screen_column().add(self.text_area_margin()),
+                        y: self.cursor_position.y,
+                    },
                     self.row_prefix_length,
                 );
             }
@@ -1194,6 +3153,36 @@ impl Editor {
         self.terminal.flush()
     }
 
+    /// When the document was scrolled by less than a screen since the last frame,
+    /// scroll the text area of the terminal accordingly, so that only the rows
+    /// uncovered by the scroll need to be written.
+    fn scroll_last_frame(&mut self, frame: &Frame, first_row: Option<usize>) {
+        let (Some(last_frame), Some(last_first_row), Some(first_row)) = (
+            self.last_frame.as_mut(),
+            self.last_frame_first_row,
+            first_row,
+        ) else {
+            return;
+        };
+        if last_frame.width != frame.width || last_frame.height != frame.height {
+            return;
+        }
+        let text_area_height = self.terminal.size().restrict_to_text_area().height;
+        let scrolled_lines = if first_row >= last_first_row {
+            i32::try_from(first_row - last_first_row)
+        } else {
+            i32::try_from(last_first_row - first_row).map(|lines| -lines)
+        };
+        match scrolled_lines {
+            Ok(lines) if lines != 0 && lines.unsigned_abs() < u32::from(text_area_height) => {
+                let bottom = text_area_height.saturating_sub(1);
+                self.terminal.scroll_region(0, bottom, lines);
+                last_frame.scroll(0, bottom, lines);
+            }
+            _ => (),
+        }
+    }
+
     /// Generate the content of the status bar
     fn generate_status(&self) -> String {
         let dirty_marker = if self.is_dirty() { " +" } else { "" };
@@ -1208,54 +3197,127 @@ impl Editor {
             dirty_marker,
             self.mode
         );
-        let stats = if self.config.display_stats {
-            format!(
-                "[{}L/{}W]",
-                self.document.last_line_number().value,
-                self.document.num_words()
-            )
+        let mut counts = vec![];
+        if self.config.display_stats {
+            counts.push(format!("{}L", self.document.last_line_number().value));
+        }
+        // counting the words or sentences would require decoding the whole file
+        if self.config.display_stats && !self.document.is_mapped() {
+            counts.push(format!("{}W", self.document.num_words()));
+        }
+        if self.config.sentencecount && !self.document.is_mapped() {
+            counts.push(format!("{}S", self.sentence_count()));
+        }
+        let stats = if counts.is_empty() {
+            String::new()
         } else {
-            "".to_string()
+            format!("[{}]", counts.join("/"))
         };
         let position = format!(
             "Ln {}, Col {}",
             self.current_line_number().value,
-            self.cursor_position
-                .x
-                .saturating_add(self.offset.columns)
+            self.current_row()
+                .column_of(self.current_x_position(), self.config.tabstop)
+                .value
                 .saturating_add(1),
         );
-        let right_status = format!("{} {}", stats, position);
+        let filetype = self
+            .document
+            .filetype
+            .map(|filetype| format!("[{filetype}] "))
+            .unwrap_or_default();
+        let right_status = format!("{stats} {filetype}[{}] {position}", self.document.format);
         let right_status = right_status.trim_start();
         let spaces = " ".repeat(
             (self.terminal.size().width as usize)
-                .saturating_sub(left_status.len())
-                .saturating_sub(right_status.len()),
+                .saturating_sub(str_width(&left_status))
+                .saturating_sub(str_width(right_status)),
         );
-        format!("{}{}{}\r", left_status, spaces, right_status)
+        format!("{left_status}{spaces}{right_status}\r")
+    }
+
+    /// Return the number of sentences of the document, only counted again if
+    /// the document was modified since they were last counted
+    fn sentence_count(&self) -> usize {
+        let hash = self.document.hashed();
+        match self.sentence_count.get() {
+            Some((counted_hash, count)) if counted_hash == hash => count,
+            _ => {
+                let count = Navigator::count_sentences(&self.document);
+                self.sentence_count.set(Some((hash, count)));
+                count
+            }
+        }
     }
 
-    /// Display the content of the status bar to the screen
-    fn draw_status_bar(&self) {
-        self.terminal.set_bg_color(STATUS_BG_COLOR);
-        self.terminal.set_fg_color(STATUS_FG_COLOR);
-        println!("{}", self.generate_status());
-        self.terminal.reset_fg_color();
-        self.terminal.reset_bg_color();
+    /// Draw the content of the status bar into the frame
+    fn draw_status_bar(&self, frame: &mut Frame) {
+        if self.config.focus && self.status_bar_hidden {
+            return;
+        }
+        let y = frame.height.saturating_sub(2);
+        frame.fill_line(y, STATUS_STYLE);
+        frame.put_str(
+            0,
+            y,
+            self.generate_status().trim_end_matches('\r'),
+            STATUS_STYLE,
+        );
     }
 
-    /// Display the content of the message bar to the screen
-    fn draw_message_bar(&self) {
-        self.terminal.clear_current_line();
+    /// Draw the content of the message bar into the frame
+    fn draw_message_bar(&self, frame: &mut Frame) {
+        let y = frame.height.saturating_sub(1);
         if self.is_receiving_command() {
             if self.is_autocompleting_command() {
-                print!(":{}\r", self.generate_command_autocompletion_message());
+                frame.put_styled_str(
+                    0,
+                    y,
+                    &format!(
+                        ":{}{}",
+                        self.autocompletion_prefix,
+                        self.generate_command_autocompletion_message()
+                    ),
+                    Style::default(),
+                );
             } else {
-                print!("{}\r", self.command_buffer);
+                frame.put_styled_str(0, y, &self.command_buffer, Style::default());
             }
         } else {
-            print!("{}\r", self.message);
+            frame.put_styled_str(0, y, &self.message, Style::default());
+        }
+    }
+
+    /// Draw the keys typed so far that are the start of a mapping, until the mapping is
+    /// resolved or times out: as in vim, they're displayed under the cursor in insert mode
+    /// and in the prompt, and at the right of the message bar in normal mode
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw_pending_keys(&self, frame: &mut Frame) {
+        if self.pending_keys.is_empty() {
+            return;
         }
+        let keys = keymap::format_keys(&self.pending_keys);
+        let (x, y) = if self.is_receiving_command() {
+            (
+                self.prompt_cursor_position(),
+                frame.height.saturating_sub(1),
+            )
+        } else if self.mode == Mode::Insert {
+            let row_prefix_length = usize::from(self.row_prefix_length);
+            (
+                self.cursor_screen_column().value
+                    + self.text_area_margin()
+                    + row_prefix_length
+                    + usize::from(row_prefix_length > 0),
+                self.cursor_position.y as u16,
+            )
+        } else {
+            (
+                (frame.width as usize).saturating_sub(PENDING_KEYS_COLUMNS),
+                frame.height.saturating_sub(1),
+            )
+        };
+        frame.put_str(x as u16, y, &keys, Style::default());
     }
 
     /// Generate the command autocompletion message, that will be displayed in the message bar
@@ -1265,7 +3327,7 @@ impl Editor {
             if i == self.current_autocompletion_index {
                 tokens.push(utils::red(utils::as_bold(suggestion).as_str()));
             } else {
-                tokens.push(suggestion.to_string());
+                tokens.push(suggestion.clone());
             }
         }
         tokens.join(AUTOCOMPLETION_SUGGESTIONS_SEPARATOR.to_string().as_str())
@@ -1278,61 +3340,65 @@ impl Editor {
 
     /// Erase the currently displayed message
     fn reset_message(&mut self) {
-        self.message = String::from("");
+        self.message = String::new();
     }
 
-    /// Display a welcome message, when no document has been opened
-    fn display_welcome_message(&self) {
+    /// Draw a welcome message on the provided line, when no document has been opened
+    fn display_welcome_message(&self, frame: &mut Frame, y: u16) {
         let term_width = self.terminal.size().width as usize;
         let welcome_msg = format!("{} v{}", PKG, utils::bo_version());
         let padding_len = term_width
-            .saturating_sub(welcome_msg.chars().count())
+            .saturating_sub(str_width(&welcome_msg))
             .saturating_sub(2) // -2 because of the starting '~ '
             .saturating_div(2);
         let padding = String::from(" ").repeat(padding_len);
-        let mut padded_welcome_message = format!("~ {}{}{}", padding, welcome_msg, padding);
-        padded_welcome_message.truncate(term_width); // make it fit on screen
-        println!("{}\r", padded_welcome_message);
+        let padded_welcome_message = format!("~ {padding}{welcome_msg}{padding}");
+        frame.put_str(0, y, &padded_welcome_message, Style::default());
     }
 
-    /// Display the automatically generated help panel on the screen
+    /// Draw the text of the alternate screen, such as the automatically
+    /// generated help panel, into the frame
     #[allow(clippy::cast_possible_truncation)]
-    fn draw_help_screen(&mut self) {
-        let help_text_lines = self.help_message.split('\n');
-        let help_text_lines_count = help_text_lines.count();
-        let term_height = self.terminal.size().height;
-        let v_padding = (term_height
+    fn draw_alternate_screen(&mut self, frame: &mut Frame) {
+        let text_lines_count = self.alternate_screen_text.split('\n').count();
+        let v_padding = (frame
+            .height
             .saturating_sub(2)
-            .saturating_sub(help_text_lines_count as u16))
+            .saturating_sub(text_lines_count as u16))
         .saturating_div(2);
-        let max_line_length = self.help_message.split('\n').map(str::len).max().unwrap();
-        let h_padding = " ".repeat((self.terminal.size().width as usize - max_line_length) / 2);
-        for _ in 0..=v_padding {
-            println!("\r");
-        }
-        for line in self.help_message.split('\n') {
-            println!("{}{}\r", h_padding, line);
-        }
-        for _ in 0..=v_padding {
-            println!("\r");
-        }
-        if (v_padding + help_text_lines_count as u16 + v_padding) == (term_height - 1) {
-            println!("\r");
+        let max_line_length = self
+            .alternate_screen_text
+            .split('\n')
+            .map(str::len)
+            .max()
+            .unwrap();
+        let h_padding = (frame.width as usize).saturating_sub(max_line_length) / 2;
+        for (i, line) in self.alternate_screen_text.split('\n').enumerate() {
+            let y = v_padding.saturating_add(1).saturating_add(i as u16);
+            frame.put_str(h_padding as u16, y, line, Style::default());
         }
         self.display_message("Press q to quit".to_string());
     }
 
-    /// Iterate over each visible document rows and display it on the screen.
+    /// Draw each visible document row into the frame.
     /// If no document is currently opened, display the welcome message.
     /// If the document is shorter than the viewport height, display empty lines as ``~``.
-    fn draw_rows(&self) {
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw_rows(&self, frame: &mut Frame) {
         let term_height = self.terminal.size().restrict_to_text_area().height;
-        for terminal_row_idx_val in self.offset.rows..(term_height as usize + self.offset.rows) {
-            let terminal_row_idx = RowIndex::new(terminal_row_idx_val);
+        let scroll_column = self.scroll_column();
+        let visible_columns = scroll_column..scroll_column.add(self.text_area_width());
+        let focused_lines = self.focused_line_numbers();
+        for y in 0..term_height {
+            let terminal_row_idx = RowIndex::new(self.offset.rows + y as usize);
             let line_number = LineNumber::from(terminal_row_idx);
-            self.terminal.clear_current_line();
             if let Some(row) = self.get_row(terminal_row_idx) {
-                self.draw_row(row, line_number);
+                let dimmed = focused_lines
+                    .as_ref()
+                    .is_some_and(|lines| !lines.contains(&line_number));
+                self.draw_row(frame, y, row, line_number, &visible_columns, dimmed);
+            } else if self.config.focus {
+                // leave the lines past the end of the document blank
             } else if line_number == self.terminal.middle_of_screen_line_number()
                 && self.document.filename.is_none()
                 && self
@@ -1340,29 +3406,134 @@ impl Editor {
                     .unwrap_or(&Row::default())
                     .is_empty()
             {
-                self.display_welcome_message();
+                self.display_welcome_message(frame, y);
             } else {
-                println!("~\r");
+                frame.put_str(0, y, "~", Style::default());
             }
         }
     }
 
-    /// Display the content of a particular document row to the screen
-    fn draw_row(&self, row: &Row, line_number: LineNumber) {
-        let row_visible_start = self.offset.columns;
-        let mut row_visible_end = self.terminal.size().width as usize + self.offset.columns;
-        if self.row_prefix_length > 0 {
-            row_visible_end = row_visible_end
-                .saturating_sub(self.row_prefix_length as usize)
-                .saturating_sub(1);
-        }
+    /// Draw the content of a particular document row on the provided line of the frame
+    fn draw_row(
+        &self,
+        frame: &mut Frame,
+        y: u16,
+        row: &Row,
+        line_number: LineNumber,
+        visible_columns: &Range<Column>,
+        dimmed: bool,
+    ) {
         let rendered_row = row.render(
-            row_visible_start,
-            row_visible_end,
+            visible_columns.start,
+            visible_columns.end,
             line_number.value,
             self.row_prefix_length as usize,
+            self.config.tabstop,
+        );
+        let x = u16::try_from(self.text_area_margin()).unwrap_or_default();
+        if dimmed {
+            frame.put_str(x, y, &rendered_row, DIMMED_STYLE);
+            return;
+        }
+        frame.put_str(x, y, &rendered_row, Style::default());
+        let visible_columns = visible_columns.start.value..visible_columns.end.value;
+        // restyle the cells displaying the highlighted parts of the row
+        let text_x = self.text_area_margin()
+            + if self.row_prefix_length > 0 {
+                self.row_prefix_length as usize + 1
+            } else {
+                0
+            };
+        let screen_x = |column: usize| {
+            u16::try_from(text_x + column - visible_columns.start).unwrap_or(u16::MAX)
+        };
+        let column_of = |byte_index: usize| {
+            row.column_of(
+                row.grapheme_index(ByteIndex::new(byte_index)),
+                self.config.tabstop,
+            )
+            .value
+        };
+        // return the screen columns of the visible part of the byte range, or
+        // None if it follows the visible columns, so that the ranges following
+        // it can be skipped
+        let screen_columns = |range: &Range<usize>| {
+            let start = column_of(range.start);
+            if start >= visible_columns.end {
+                return None;
+            }
+            let start = start.max(visible_columns.start);
+            let end = column_of(range.end).clamp(start, visible_columns.end);
+            Some(screen_x(start)..screen_x(end))
+        };
+        let index = RowIndex::from(line_number);
+        for span in self.document.highlights(index) {
+            let Some(columns) = screen_columns(&span.range) else {
+                break;
+            };
+            frame.set_style(y, columns, self.config.theme.style(span.group));
+        }
+        for (finding, _) in &self.visible_lint_findings {
+            let (start, end) = (finding.start, finding.end);
+            if index < start.0 || index > end.0 {
+                continue;
+            }
+            // a finding can span several rows
+            let start = if start.0 == index {
+                row.byte_index(start.1).value
+            } else {
+                0
+            };
+            let end = if end.0 == index {
+                row.byte_index(end.1).value
+            } else {
+                row.string.len()
+            };
+            if let Some(columns) = screen_columns(&(start..end)) {
+                frame.set_style(y, columns, self.config.theme.style(HighlightGroup::Lint));
+            }
+        }
+        for range in self.misspelled_words(index) {
+            let Some(columns) = screen_columns(&range) else {
+                break;
+            };
+            frame.underline(y, columns);
+        }
+    }
+
+    /// Return the line numbers of the current paragraph in focus mode, the
+    /// other rows being dimmed, or None outside of focus mode
+    fn focused_line_numbers(&self) -> Option<RangeInclusive<LineNumber>> {
+        if !self.config.focus {
+            return None;
+        }
+        let current_line_number = self.current_line_number();
+        if self.current_row().is_whitespace() {
+            return Some(current_line_number..=current_line_number);
+        }
+        let is_blank = |line_number| {
+            self.document
+                .row_for_line_number(line_number)
+                .is_none_or(Row::is_whitespace)
+        };
+        // the paragraph boundaries are the blank lines surrounding it, if any
+        let mut start = Navigator::find_line_number_of_start_or_end_of_paragraph(
+            &self.document,
+            current_line_number,
+            &Boundary::Start,
+        );
+        while start < current_line_number && is_blank(start) {
+            start = start.next();
+        }
+        let mut end = Navigator::find_line_number_of_start_or_end_of_paragraph(
+            &self.document,
+            current_line_number,
+            &Boundary::End,
         );
-        println!("{}\r", rendered_row);
+        while end > current_line_number && is_blank(end) {
+            end = end.previous();
+        }
+        Some(start..=end)
     }
 }
 
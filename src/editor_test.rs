@@ -1,97 +1,17 @@
-use super::SPACES_PER_TAB;
-use crate::LineNumber;
+use crate::filetype::Filetype;
+use crate::frame::{Color, Style};
+use crate::highlighting::HighlightGroup;
+use crate::keymap::MapMode;
+use crate::{utils, Encoding, GraphemeIndex, LineEnding, LineNumber};
 use crate::{
-    AnsiPosition, Console, ConsoleSize, Document, Editor, Mode, Operation, OperationType, Position,
-    Row, RowIndex,
+    AnsiPosition, Column, Document, Editor, Mode, Operation, OperationType, Position, Row,
+    RowIndex, ScreenPosition, VirtualConsole,
 };
-use std::fmt;
 use std::fs;
-use std::io::Error;
 use std::io::Write;
 use std::path::PathBuf;
 use tempfile::{tempdir, NamedTempFile};
-use termion::color;
-use termion::event::{Event, Key, MouseEvent};
-
-#[derive(Default)]
-struct MockConsole {}
-
-impl Console for MockConsole {
-    fn read_event(&mut self) -> Result<Event, Error> {
-        Ok(Event::Key(Key::Char('r')))
-    }
-
-    fn clear_screen(&self) {}
-
-    fn clear_current_line(&self) {}
-
-    /// # Errors
-    ///
-    /// Returns an error if stdout can't be flushed
-    fn flush(&self) -> Result<(), std::io::Error> {
-        Ok(())
-    }
-
-    fn hide_cursor(&self) {}
-
-    fn show_cursor(&self) {}
-
-    fn set_bg_color(&self, _color: color::Rgb) {}
-
-    fn reset_bg_color(&self) {}
-
-    fn set_fg_color(&self, _color: color::Rgb) {}
-
-    fn reset_fg_color(&self) {}
-
-    fn to_alternate_screen(&self) {}
-
-    fn to_main_screen(&self) {}
-
-    fn clear_all(&self) {}
-
-    fn set_cursor_as_steady_bar(&self) {}
-
-    fn set_cursor_as_steady_block(&self) {}
-
-    fn size(&self) -> ConsoleSize {
-        ConsoleSize::default()
-    }
-
-    fn text_area_size(&self) -> ConsoleSize {
-        ConsoleSize {
-            height: 78,
-            width: 120,
-        }
-    }
-
-    fn middle_of_screen_line_number(&self) -> LineNumber {
-        LineNumber::new(self.text_area_size().height as usize / 2)
-    }
-
-    fn bottom_of_screen_line_number(&self) -> LineNumber {
-        LineNumber::new(self.text_area_size().height as usize)
-    }
-
-    fn set_cursor_position_in_text_area(&self, _position: &Position, _row_prefix_length: u8) {}
-
-    fn set_cursor_position_anywhere(&self, _position: &Position) {}
-
-    #[must_use]
-    fn get_cursor_index_from_mouse_event(
-        &self,
-        _mouse_event: MouseEvent,
-        _x_offset: u8,
-    ) -> Position {
-        Position::default()
-    }
-}
-
-impl fmt::Debug for MockConsole {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Terminal").finish()
-    }
-}
+use termion::event::{Key, MouseButton, MouseEvent};
 
 fn get_short_document() -> Document {
     let lines: Vec<&str> = vec!["Hellö world", "Hello world!", "Hello world!!"];
@@ -111,7 +31,7 @@ fn get_long_document() -> Document {
 }
 
 fn get_test_editor() -> Editor {
-    let console = Box::new(MockConsole::default());
+    let console = Box::new(VirtualConsole::default());
     let mut editor = Editor::new(None, console);
     editor.document = get_short_document();
     editor.last_saved_hash = editor.document.hashed();
@@ -119,7 +39,7 @@ fn get_test_editor() -> Editor {
 }
 
 fn get_test_editor_with_long_document() -> Editor {
-    let console = Box::new(MockConsole::default());
+    let console = Box::new(VirtualConsole::default());
     let mut editor = Editor::new(None, console);
     editor.document = get_long_document();
     editor.last_saved_hash = editor.document.hashed();
@@ -127,7 +47,13 @@ fn get_test_editor_with_long_document() -> Editor {
 }
 
 fn assert_position_is(editor: &Editor, x: usize, y: usize) {
-    assert_eq!(editor.cursor_position, Position { x, y });
+    assert_eq!(
+        editor.cursor_position,
+        Position {
+            x: GraphemeIndex::new(x),
+            y
+        }
+    );
 }
 
 fn assert_nth_row_is(editor: &Editor, n: usize, s: &str) {
@@ -272,9 +198,37 @@ fn test_editor_search() {
     assert_eq!(
         editor.search_matches,
         vec![
-            (Position { x: 7, y: 1 }, Position { x: 13, y: 1 }),
-            (Position { x: 6, y: 2 }, Position { x: 12, y: 2 }),
-            (Position { x: 6, y: 3 }, Position { x: 12, y: 3 })
+            // "ö" is 2 bytes long, but a single grapheme
+            (
+                Position {
+                    x: GraphemeIndex::new(6),
+                    y: 1
+                },
+                Position {
+                    x: GraphemeIndex::new(12),
+                    y: 1
+                }
+            ),
+            (
+                Position {
+                    x: GraphemeIndex::new(6),
+                    y: 2
+                },
+                Position {
+                    x: GraphemeIndex::new(12),
+                    y: 2
+                }
+            ),
+            (
+                Position {
+                    x: GraphemeIndex::new(6),
+                    y: 3
+                },
+                Position {
+                    x: GraphemeIndex::new(12),
+                    y: 3
+                }
+            )
         ]
     );
     assert_eq!(editor.message, "Match 1/3");
@@ -290,7 +244,7 @@ fn test_editor_search() {
 
     editor.process_keystroke(Key::Char('n'));
     assert_eq!(editor.current_search_match_index, 0);
-    assert_position_is(&editor, 7, 0);
+    assert_position_is(&editor, 6, 0);
 
     editor.process_keystroke(Key::Char('N'));
     assert_eq!(editor.current_search_match_index, 2);
@@ -301,6 +255,28 @@ fn test_editor_search() {
     assert_eq!(editor.current_search_match_index, 0);
 }
 
+#[test]
+fn test_editor_search_and_word_motions_in_non_ascii_text() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from(
+            "Le caf\u{e9} cre\u{300}me est d\u{e9}j\u{e0} servi",
+        )],
+        PathBuf::from("test.txt"),
+    );
+    process_command(&mut editor, "/est");
+    assert_position_is(&editor, 14, 0);
+    assert_eq!(editor.current_grapheme(), "e");
+
+    editor.process_keystroke(Key::Char('w'));
+    assert_position_is(&editor, 18, 0);
+    assert_eq!(editor.current_word(), "d\u{e9}j\u{e0}");
+    editor.process_keystroke(Key::Char('b'));
+    editor.process_keystroke(Key::Char('b'));
+    assert_position_is(&editor, 8, 0);
+    assert_eq!(editor.current_word(), "cre\u{300}me");
+}
+
 #[test]
 fn test_editor_unknown_command() {
     let mut editor = get_test_editor();
@@ -321,7 +297,7 @@ fn test_editor_navigation() {
     editor.process_keystroke(Key::Char('G'));
     assert_position_is(&editor, 0, 2);
 
-    editor.process_keystroke(Key::Char('g'));
+    process_keystrokes(&mut editor, vec!['g', 'g']);
     assert_position_is(&editor, 0, 0);
 
     editor.process_keystroke(Key::Char('$'));
@@ -336,18 +312,38 @@ fn test_editor_navigation() {
     editor.process_keystroke(Key::Char('b'));
     assert_position_is(&editor, 0, 0);
 
+    // word motions continue on the next and previous lines
     process_keystrokes(&mut editor, vec!['2', 'w']);
-    assert_position_is(&editor, 10, 0);
+    assert_position_is(&editor, 0, 1);
 
     process_keystrokes(&mut editor, vec!['2', 'b']);
     assert_position_is(&editor, 0, 0);
+
+    editor.process_keystroke(Key::Char('e'));
+    assert_position_is(&editor, 4, 0);
+
+    process_keystrokes(&mut editor, vec!['3', 'e']);
+    assert_position_is(&editor, 10, 1);
+
+    process_keystrokes(&mut editor, vec!['2', 'g', 'e']);
+    assert_position_is(&editor, 10, 0);
+
+    // "world!" is a single WORD
+    process_keystrokes(&mut editor, vec!['2', 'W']);
+    assert_position_is(&editor, 6, 1);
+    editor.process_keystroke(Key::Char('E'));
+    assert_position_is(&editor, 11, 1);
+    editor.process_keystroke(Key::Char('B'));
+    assert_position_is(&editor, 6, 1);
+    process_keystrokes(&mut editor, vec!['g', 'E']);
+    assert_position_is(&editor, 4, 1);
 }
 
 #[test]
 fn test_editor_deletion() {
     let mut editor = get_test_editor();
 
-    editor.goto_x_y(1, RowIndex::new(1));
+    editor.goto_x_y(GraphemeIndex::new(1), RowIndex::new(1));
     editor.process_keystroke(Key::Char('i'));
     editor.process_keystroke(Key::Backspace);
     assert_eq!(editor.document.num_rows(), 3);
@@ -355,7 +351,7 @@ fn test_editor_deletion() {
         editor.document.get_row(RowIndex::new(1)).unwrap().string,
         "ello world!"
     );
-    editor.goto_x_y(0, RowIndex::new(1));
+    editor.goto_x_y(GraphemeIndex::new(0), RowIndex::new(1));
     editor.process_keystroke(Key::Backspace);
     assert_eq!(editor.document.num_rows(), 2);
     assert_eq!(
@@ -387,10 +383,10 @@ fn test_editor_edition() {
 
     editor.process_keystroke(Key::Esc);
     assert_eq!(editor.document.num_rows(), 5);
-    editor.process_keystroke(Key::Char('d'));
+    process_keystrokes(&mut editor, vec!['d', 'd']);
     assert_eq!(editor.document.num_rows(), 4);
 
-    editor.goto_x_y(0, RowIndex::new(1));
+    editor.goto_x_y(GraphemeIndex::new(0), RowIndex::new(1));
     editor.process_keystroke(Key::Char('i'));
     assert_eq!(editor.mode, Mode::Insert);
     process_keystrokes(&mut editor, vec!['b', 'o', 'o', 'p']);
@@ -407,7 +403,7 @@ fn test_editor_edition() {
     assert_eq!(editor.document.num_rows(), 3);
     assert_nth_row_is(&editor, 0, "Hellö worldboo");
 
-    editor.goto_x_y(11, RowIndex::new(0));
+    editor.goto_x_y(GraphemeIndex::new(11), RowIndex::new(0));
     assert_position_is(&editor, 11, 0);
     assert_eq!(editor.document.num_rows(), 3);
     editor.process_keystroke(Key::Char('\n'));
@@ -416,7 +412,7 @@ fn test_editor_edition() {
     assert_nth_row_is(&editor, 1, "boo");
     assert_position_is(&editor, 0, 1);
 
-    editor.goto_x_y(0, RowIndex::new(0));
+    editor.goto_x_y(GraphemeIndex::new(0), RowIndex::new(0));
     editor.process_keystroke(Key::Esc);
     editor.process_keystroke(Key::Char('x'));
     assert_nth_row_is(&editor, 0, "ellö world");
@@ -431,20 +427,60 @@ fn test_editor_insert_spaces_for_tab() {
     let mut editor = get_test_editor();
 
     process_keystrokes(&mut editor, vec!['i', '\t']);
-    assert_position_is(&editor, SPACES_PER_TAB, 0);
+    assert_position_is(&editor, editor.config.shiftwidth, 0);
     assert_nth_row_is(&editor, 0, "    Hellö world");
 }
 
+#[test]
+fn test_editor_insert_tab_and_delete_soft_tab() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['i', 'a', '\t']);
+    // spaces are inserted up to the next indentation level
+    assert_nth_row_is(&editor, 0, "a   Hellö world");
+    editor.process_keystroke(Key::Char('\t'));
+    assert_nth_row_is(&editor, 0, "a       Hellö world");
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "a   Hellö world");
+    // only the spaces inserted by Tab are deleted
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "aHellö world");
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "Hellö world");
+
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":set noexpandtab");
+    process_keystrokes(&mut editor, vec!['0', 'i', '\t']);
+    assert_nth_row_is(&editor, 0, "\tHellö world");
+    assert_position_is(&editor, 1, 0);
+}
+
+#[test]
+fn test_tabs_are_rendered_up_to_the_next_tab_stop() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = Document::new(vec![Row::from("\tfoo\tbar")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['l', 'l', 'l', 'l']);
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.line(0), "    foo bar");
+    assert_eq!(console.cursor_position(), (7, 0));
+    assert!(console.line(78).ends_with("Ln 1, Col 8"));
+
+    process_command(&mut editor, ":set ts=8");
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.line(0), "        foo     bar");
+    assert_eq!(console.cursor_position(), (11, 0));
+}
+
 #[test]
 fn test_editor_move_cursor_to_position_x() {
     let mut editor = get_test_editor();
 
     assert_position_is(&editor, 0, 0);
-    editor.move_cursor_to_position_x(1);
+    editor.move_cursor_to_position_x(GraphemeIndex::new(1));
     assert_position_is(&editor, 1, 0);
     assert_eq!(editor.offset.columns, 0);
 
-    editor.move_cursor_to_position_x(140);
+    editor.move_cursor_to_position_x(GraphemeIndex::new(140));
     assert_position_is(&editor, 119, 0);
     assert_eq!(editor.offset.columns, 21);
 }
@@ -534,7 +570,7 @@ fn test_editor_simple_utilities() {
     let editor = get_test_editor();
     assert_eq!(editor.current_row_index(), RowIndex::new(0));
     assert_eq!(editor.current_line_number(), LineNumber::new(1));
-    assert_eq!(editor.current_x_position(), 0);
+    assert_eq!(editor.current_x_position(), GraphemeIndex::new(0));
     assert_eq!(editor.current_grapheme(), "H");
     assert_eq!(editor.current_row().string, "Hellö world");
 }
@@ -545,7 +581,7 @@ fn test_editor_status() {
 
     assert_eq!(
         editor.generate_status(),
-        format!("[test] NORMAL{}Ln 1, Col 1\r", " ".repeat(96))
+        format!("[test] NORMAL{}[unix] Ln 1, Col 1\r", " ".repeat(89))
     );
 
     // insert new characters
@@ -553,29 +589,32 @@ fn test_editor_status() {
 
     assert_eq!(
         editor.generate_status(),
-        format!("[test] + INSERT{}Ln 1, Col 2\r", " ".repeat(94))
+        format!("[test] + INSERT{}[unix] Ln 1, Col 2\r", " ".repeat(87))
     );
 
     editor.process_keystroke(Key::Esc);
 
     assert_eq!(
         editor.generate_status(),
-        format!("[test] + NORMAL{}Ln 1, Col 2\r", " ".repeat(94))
+        format!("[test] + NORMAL{}[unix] Ln 1, Col 2\r", " ".repeat(87))
     );
 
-    editor.cursor_position.x = 1;
+    editor.cursor_position.x = GraphemeIndex::new(1);
     editor.cursor_position.y = 2;
     assert_eq!(
         editor.generate_status(),
-        format!("[test] + NORMAL{}Ln 3, Col 2\r", " ".repeat(94))
+        format!("[test] + NORMAL{}[unix] Ln 3, Col 2\r", " ".repeat(87))
     );
-    editor.cursor_position.x = 0;
+    editor.cursor_position.x = GraphemeIndex::new(0);
     editor.cursor_position.y = 0;
 
     editor.config.display_stats = true;
     assert_eq!(
         editor.generate_status(),
-        format!("[test] + NORMAL{}[3L/6W] Ln 1, Col 1\r", " ".repeat(86))
+        format!(
+            "[test] + NORMAL{}[3L/6W] [unix] Ln 1, Col 1\r",
+            " ".repeat(79)
+        )
     );
 }
 
@@ -652,10 +691,10 @@ fn test_editor_edit_long_document() {
 }
 
 #[test]
-fn test_position_from_ansiposition() {
+fn test_screen_position_from_ansiposition() {
     let ap = AnsiPosition { x: 10, y: 8 }; // 1-indexed
-    let p = Position::from(ap); // 0-indexed
-    assert_eq!(p.x, 9);
+    let p = ScreenPosition::from(ap); // 0-indexed
+    assert_eq!(p.x, Column::new(9));
     assert_eq!(p.y, 7);
 }
 
@@ -680,7 +719,7 @@ fn test_editor_serialize() {
   "search_matches": [],
   "current_search_match_index": 0,
   "unsaved_edits": 0,
-  "last_saved_hash": 6051608862860543045,
+  "last_saved_hash": 474330621994055969,
   "row_prefix_length": 0,
   "document": {
     "rows": [
@@ -694,7 +733,13 @@ fn test_editor_serialize() {
         "string": "Hello world!!"
       }
     ],
-    "filename": "test"
+    "filename": "test",
+    "format": {
+      "line_ending": "Unix",
+      "final_newline": true,
+      "bom": false,
+      "encoding": "Utf8"
+    }
   },
   "command_suggestions": [],
   "current_autocompletion_index": 0
@@ -704,7 +749,7 @@ fn test_editor_serialize() {
 
 #[test]
 fn test_open_existing_file() {
-    let console = Box::new(MockConsole::default());
+    let console = Box::new(VirtualConsole::default());
     let mut f = NamedTempFile::new().unwrap();
     f.write_all("Hello\nHello!\nHello!!\n".as_bytes()).unwrap();
     let f_name_pathbuf: PathBuf = f.path().to_path_buf();
@@ -751,7 +796,7 @@ fn test_new_file() {
 
 #[test]
 fn test_save_file() {
-    let console = Box::new(MockConsole::default());
+    let console = Box::new(VirtualConsole::default());
     let f = NamedTempFile::new().unwrap();
     let f_name_pathbuf: PathBuf = f.path().to_path_buf();
     let f_name_str: String = f_name_pathbuf.to_str().unwrap().to_string(); // gawd
@@ -768,7 +813,7 @@ fn test_save_file() {
 
 #[test]
 fn test_save_file_trim_whitespaces() {
-    let console = Box::new(MockConsole::default());
+    let console = Box::new(VirtualConsole::default());
     let f = NamedTempFile::new().unwrap();
     let f_name_pathbuf: PathBuf = f.path().to_path_buf();
     let f_name_str: String = f_name_pathbuf.to_str().unwrap().to_string(); // gawd
@@ -825,7 +870,10 @@ fn test_goto_matching_closing_symbol() {
         .clone();
     assert_eq!(first_line_content.chars().nth(11), Some('('));
     assert_eq!(first_line_content.chars().nth(14), Some(')'));
-    editor.cursor_position = Position { x: 11, y: 0 }; // first paren
+    editor.cursor_position = Position {
+        x: GraphemeIndex::new(11),
+        y: 0,
+    }; // first paren
     editor.process_keystroke(Key::Esc);
     editor.process_keystroke(Key::Char('m'));
     assert_position_is(&editor, 14, 0);
@@ -847,7 +895,7 @@ fn test_delete_last_line() {
     assert_eq!(editor.document.num_rows(), 3);
     editor.process_keystroke(Key::Char('G'));
     assert_position_is(&editor, 0, 2);
-    editor.process_keystroke(Key::Char('d'));
+    process_keystrokes(&mut editor, vec!['d', 'd']);
     assert_eq!(editor.document.num_rows(), 2);
     assert_position_is(&editor, 0, 1);
 }
@@ -860,7 +908,7 @@ fn test_delete_line_longer_than_previous_one() {
     editor.process_keystroke(Key::Char('A')); // go to last character
     editor.process_keystroke(Key::Esc); // go to last character
     assert_position_is(&editor, 13, 2);
-    editor.process_keystroke(Key::Char('d'));
+    process_keystrokes(&mut editor, vec!['d', 'd']);
     assert_position_is(&editor, 12, 1);
 }
 
@@ -868,7 +916,7 @@ fn test_delete_line_longer_than_previous_one() {
 fn test_process_command_not_found() {
     let mut editor = get_test_editor();
     process_command(&mut editor, ":nope");
-    assert_eq!(editor.message, r#"[38;5;1mUnknown command 'nope'[39m"#);
+    assert_eq!(editor.message, r"[38;5;1mUnknown command 'nope'[39m");
 }
 
 #[test]
@@ -883,7 +931,7 @@ fn test_save_and_quit() {
         process_command(&mut editor, ":wq");
         assert_eq!(editor.unsaved_edits, 0);
         assert!(editor.should_quit);
-    };
+    }
 }
 
 #[test]
@@ -958,13 +1006,21 @@ fn test_undo_insert() {
     // We simulate that the 3 lines were written in 2 separate operations
     editor.history.operations.push_back(Operation {
         op_type: OperationType::Insert,
+        grouped: false,
         content: String::from("Hello world\n"),
-        start_position: Position { x: 0, y: 0 },
+        start_position: Position {
+            x: GraphemeIndex::new(0),
+            y: 0,
+        },
     });
     editor.history.operations.push_back(Operation {
         op_type: OperationType::Insert,
+        grouped: false,
         content: String::from("Hello world!\nHello world!!"),
-        start_position: Position { x: 0, y: 1 },
+        start_position: Position {
+            x: GraphemeIndex::new(0),
+            y: 1,
+        },
     });
     assert_eq!(editor.document.num_rows(), 3);
     assert_eq!(editor.history.operations.len(), 2);
@@ -976,6 +1032,18 @@ fn test_undo_insert() {
     assert_position_is(&editor, 0, 1);
 }
 
+#[test]
+fn test_undo_to_saved_state_is_not_dirty() {
+    let mut editor = get_test_editor();
+    assert!(!editor.is_dirty());
+    process_keystrokes(&mut editor, vec!['i', 'a', 'b']);
+    editor.process_keystroke(Key::Esc);
+    assert!(editor.is_dirty());
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor)[0], "Hellö world");
+    assert!(!editor.is_dirty());
+}
+
 #[test]
 fn test_undo_delete() {
     let mut editor = get_test_editor();
@@ -983,25 +1051,37 @@ fn test_undo_delete() {
     // We simulate that the 3 lines were written in 2 separate operations
     editor.history.operations.push_back(Operation {
         op_type: OperationType::Insert,
+        grouped: false,
         content: String::from("Hello world\n"),
-        start_position: Position { x: 0, y: 0 },
+        start_position: Position {
+            x: GraphemeIndex::new(0),
+            y: 0,
+        },
     });
     editor.history.operations.push_back(Operation {
         op_type: OperationType::Insert,
+        grouped: false,
         content: String::from("Hello world!\nHello world!!"),
-        start_position: Position { x: 0, y: 1 },
+        start_position: Position {
+            x: GraphemeIndex::new(0),
+            y: 1,
+        },
     });
 
     // We now simulate that we deleted the " world!!" at the end of the last line
     editor.history.operations.push_back(Operation {
         op_type: OperationType::Delete,
+        grouped: false,
         content: String::from("!!dlrow "),
-        start_position: Position { x: 14, y: 2 },
+        start_position: Position {
+            x: GraphemeIndex::new(14),
+            y: 2,
+        },
     });
     editor.document.delete_row(RowIndex::new(2));
     editor
         .document
-        .insert_string("\nHello", 11, RowIndex::new(1));
+        .insert_string("\nHello", GraphemeIndex::new(12), RowIndex::new(1));
     // at that point the 3rd row contains the string "Hello"
 
     assert_eq!(editor.document.num_rows(), 3);
@@ -1021,7 +1101,7 @@ fn test_delete_line_then_undo() {
     let mut editor = get_test_editor();
     editor.process_keystroke(Key::Char('j'));
     assert_nth_row_is(&editor, 1, "Hello world!");
-    editor.process_keystroke(Key::Char('d'));
+    process_keystrokes(&mut editor, vec!['d', 'd']);
     assert_nth_row_is(&editor, 1, "Hello world!!");
     assert_eq!(
         editor.history.operations.back().unwrap().content,
@@ -1045,9 +1125,1353 @@ fn test_reset_history_at_open() {
     // We simulate that the 3 lines were written in one operation
     editor.history.operations.push_back(Operation {
         op_type: OperationType::Insert,
+        grouped: false,
         content: String::from("Hello world\nHello world!\nHello world!!"),
-        start_position: Position { x: 0, y: 0 },
+        start_position: Position {
+            x: GraphemeIndex::new(0),
+            y: 0,
+        },
     });
     process_command(&mut editor, ":open newfile");
     assert!(editor.history.operations.is_empty());
 }
+
+fn get_test_editor_with_numbered_lines() -> Editor {
+    let console = Box::new(VirtualConsole::default());
+    let mut editor = Editor::new(None, console);
+    let rows: Vec<Row> = (1..=6)
+        .map(|i| Row::from(format!("line {i}").as_str()))
+        .collect();
+    editor.document = Document::new(rows, PathBuf::from("test"));
+    editor.last_saved_hash = editor.document.hashed();
+    editor
+}
+
+fn document_lines(editor: &Editor) -> Vec<String> {
+    editor
+        .document
+        .iter()
+        .map(|row| row.string.clone())
+        .collect()
+}
+
+#[test]
+fn test_goto_line_with_address() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":$");
+    assert_eq!(editor.current_line_number(), LineNumber::new(6));
+    process_command(&mut editor, ":.-3");
+    assert_eq!(editor.current_line_number(), LineNumber::new(3));
+    process_command(&mut editor, ":/line 5/");
+    assert_eq!(editor.current_line_number(), LineNumber::new(5));
+    process_command(&mut editor, ":42");
+    assert_eq!(editor.current_line_number(), LineNumber::new(6));
+}
+
+#[test]
+fn test_delete_range_then_undo() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":2,4d");
+    assert_eq!(document_lines(&editor), vec!["line 1", "line 5", "line 6"]);
+    assert_eq!(editor.current_line_number(), LineNumber::new(2));
+    assert_eq!(
+        editor.registers.get(&'"').unwrap(),
+        "line 2\nline 3\nline 4\n"
+    );
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 2", "line 3", "line 4", "line 5", "line 6"]
+    );
+}
+
+#[test]
+fn test_delete_invalid_range() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":5,8d");
+    assert_eq!(editor.message, utils::red("Invalid range"));
+    assert_eq!(editor.document.num_rows(), 6);
+}
+
+#[test]
+fn test_yank_range_in_register() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":.,+1y a");
+    assert_eq!(editor.registers.get(&'a').unwrap(), "line 1\nline 2\n");
+    assert_eq!(editor.registers.get(&'"').unwrap(), "line 1\nline 2\n");
+    assert_eq!(editor.document.num_rows(), 6);
+}
+
+fn numbered_lines(numbers: &[usize]) -> Vec<String> {
+    numbers.iter().map(|n| format!("line {n}")).collect()
+}
+
+#[test]
+fn test_move_range() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":1,2m$");
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 3", "line 4", "line 5", "line 6", "line 1", "line 2"]
+    );
+    assert_eq!(editor.current_line_number(), LineNumber::new(6));
+    process_command(&mut editor, ":5,6m0");
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 2", "line 3", "line 4", "line 5", "line 6"]
+    );
+    process_command(&mut editor, ":1,3m2");
+    assert_eq!(
+        editor.message,
+        utils::red("Cannot move a range of lines into itself")
+    );
+}
+
+#[test]
+fn test_move_range_then_undo() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":1,2m4");
+    assert_eq!(document_lines(&editor), numbered_lines(&[3, 4, 1, 2, 5, 6]));
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor), numbered_lines(&[1, 2, 3, 4, 5, 6]));
+    process_command(&mut editor, ":5,6m0");
+    assert_eq!(document_lines(&editor), numbered_lines(&[5, 6, 1, 2, 3, 4]));
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor), numbered_lines(&[1, 2, 3, 4, 5, 6]));
+    assert_eq!(editor.current_line_number(), LineNumber::new(1));
+}
+
+#[test]
+fn test_copy_range_then_undo() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":1,2t4");
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 2", "line 3", "line 4", "line 1", "line 2", "line 5", "line 6"]
+    );
+    assert_eq!(editor.current_line_number(), LineNumber::new(6));
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 2", "line 3", "line 4", "line 5", "line 6"]
+    );
+    process_command(&mut editor, ":6co0");
+    assert_eq!(
+        editor.document.get_row(RowIndex::new(0)).unwrap().string,
+        "line 6"
+    );
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor), numbered_lines(&[1, 2, 3, 4, 5, 6]));
+}
+
+#[test]
+fn test_shift_range() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":%>>");
+    assert_nth_row_is(&editor, 0, "        line 1");
+    assert_nth_row_is(&editor, 5, "        line 6");
+    process_command(&mut editor, ":2,3<");
+    assert_nth_row_is(&editor, 0, "        line 1");
+    assert_nth_row_is(&editor, 1, "    line 2");
+    assert_nth_row_is(&editor, 2, "    line 3");
+    process_command(&mut editor, ":2<<<");
+    assert_nth_row_is(&editor, 1, "line 2");
+}
+
+#[test]
+fn test_shift_range_then_undo() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":%>");
+    assert_nth_row_is(&editor, 5, "    line 6");
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor), numbered_lines(&[1, 2, 3, 4, 5, 6]));
+    process_command(&mut editor, ":2,3<");
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor), numbered_lines(&[1, 2, 3, 4, 5, 6]));
+}
+
+#[test]
+fn test_shift_range_with_tabs() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":set noet sw=2");
+    process_command(&mut editor, ":1>");
+    assert_nth_row_is(&editor, 0, "  line 1");
+    process_command(&mut editor, ":1>");
+    assert_nth_row_is(&editor, 0, "\tline 1");
+    process_command(&mut editor, ":1>");
+    assert_nth_row_is(&editor, 0, "\t  line 1");
+    process_command(&mut editor, ":1<<");
+    assert_nth_row_is(&editor, 0, "  line 1");
+}
+
+#[test]
+fn test_retab() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![
+            Row::from("\tfoo"),
+            Row::from("        bar"),
+            Row::from("\tbaz"),
+        ],
+        PathBuf::from("test"),
+    );
+    process_command(&mut editor, ":1retab 8");
+    assert_nth_row_is(&editor, 0, "    foo");
+    assert_eq!(editor.config.tabstop, 8);
+    assert_nth_row_is(&editor, 2, "\tbaz");
+
+    process_command(&mut editor, ":set noet");
+    process_command(&mut editor, ":retab! 4");
+    assert_nth_row_is(&editor, 0, "\tfoo");
+    assert_nth_row_is(&editor, 1, "\t\tbar");
+    assert_nth_row_is(&editor, 2, "\t\tbaz");
+
+    process_command(&mut editor, ":retab 0");
+    assert_eq!(editor.message, utils::red("Invalid tabstop '0'"));
+}
+
+#[test]
+fn test_join_range() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":j");
+    assert_nth_row_is(&editor, 0, "line 1 line 2");
+    process_command(&mut editor, ":2,4j");
+    assert_nth_row_is(&editor, 1, "line 3 line 4 line 5");
+    assert_eq!(editor.document.num_rows(), 3);
+    process_command(&mut editor, ":1j");
+    assert_nth_row_is(&editor, 0, "line 1 line 2 line 3 line 4 line 5");
+    assert_eq!(editor.document.num_rows(), 2);
+}
+
+#[test]
+fn test_join_range_then_undo() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":2,4j");
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 2 line 3 line 4", "line 5", "line 6"]
+    );
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor), numbered_lines(&[1, 2, 3, 4, 5, 6]));
+    assert_eq!(editor.current_line_number(), LineNumber::new(2));
+}
+
+#[test]
+fn test_normal_on_range() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":2,3normal A!");
+    assert_nth_row_is(&editor, 0, "line 1");
+    assert_nth_row_is(&editor, 1, "line 2!");
+    assert_nth_row_is(&editor, 2, "line 3!");
+    assert_eq!(editor.mode, Mode::Normal);
+    assert!(!editor.is_receiving_command());
+}
+
+#[test]
+fn test_marks_in_range() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":2mark a");
+    process_command(&mut editor, ":4k b");
+    process_command(&mut editor, ":6k c");
+    process_command(&mut editor, ":'a,'bd");
+    assert_eq!(document_lines(&editor), vec!["line 1", "line 5", "line 6"]);
+    // the marks of the deleted lines are dropped, and the following ones shifted
+    process_command(&mut editor, ":'ad");
+    assert_eq!(editor.message, utils::red("Mark 'a' not set"));
+    process_command(&mut editor, ":'cm0");
+    assert_eq!(document_lines(&editor), vec!["line 6", "line 1", "line 5"]);
+    process_command(&mut editor, ":'c,'cd");
+    assert_eq!(document_lines(&editor), vec!["line 1", "line 5"]);
+    process_command(&mut editor, ":'<,'>d");
+    assert_eq!(editor.message, utils::red("No visual selection"));
+}
+
+#[test]
+fn test_write_range_to_file() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    let f = NamedTempFile::new().unwrap();
+    let f_name = f.path().to_str().unwrap().to_string();
+    process_command(&mut editor, &format!(":2,3w {f_name}"));
+    assert_eq!(fs::read_to_string(f).unwrap(), "line 2\nline 3\n");
+    assert_eq!(editor.document.filename, Some(PathBuf::from("test")));
+}
+
+#[test]
+fn test_read_file_below_line() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(b"read 1\r\nread 2\r\n").unwrap();
+    let f_name = f.path().to_str().unwrap().to_string();
+    process_command(&mut editor, &format!(":2r {f_name}"));
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 2", "read 1", "read 2", "line 3", "line 4", "line 5", "line 6"]
+    );
+    assert_eq!(editor.current_line_number(), LineNumber::new(3));
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(document_lines(&editor), numbered_lines(&[1, 2, 3, 4, 5, 6]));
+    process_command(&mut editor, ":r /does/not/exist");
+    assert!(editor.message.contains("Error reading /does/not/exist"));
+}
+
+#[test]
+fn test_range_not_allowed() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":1,2q");
+    assert_eq!(editor.message, utils::red("No range allowed"));
+    assert!(!editor.should_quit);
+}
+
+#[test]
+fn test_command_validation_errors() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":wq!");
+    assert_eq!(editor.message, utils::red("No ! allowed"));
+    process_command(&mut editor, ":ln foo");
+    assert_eq!(editor.message, utils::red("Trailing characters"));
+    process_command(&mut editor, ":normal");
+    assert_eq!(editor.message, utils::red("Argument required: <keys>"));
+    assert!(!editor.should_quit);
+}
+
+#[test]
+fn test_autocompletion_after_range() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command_no_enter(&mut editor, ":2,3no");
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, ":2,3normal");
+}
+
+#[test]
+fn test_autocompletion_of_register_argument() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":1y a");
+    process_command(&mut editor, ":2y b");
+    process_command_no_enter(&mut editor, ":d ");
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_suggestions, vec!["a", "b"]);
+    assert_eq!(editor.autocompletion_prefix, "d ");
+    editor.process_keystroke(Key::Char('\t'));
+    editor.process_keystroke(Key::Char('\n'));
+    assert_eq!(editor.registers.get(&'b'), Some(&String::from("line 1\n")));
+    assert_eq!(editor.document.num_rows(), 5);
+}
+
+#[test]
+fn test_autocompletion_of_filename_argument() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("notes.md"), "").unwrap();
+    fs::write(dir.path().join("novel.md"), "").unwrap();
+    let dir_path = dir.path().to_str().unwrap();
+    let mut editor = get_test_editor();
+    process_command_no_enter(&mut editor, &format!(":open {dir_path}/no"));
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_suggestions, vec!["notes.md", "novel.md"]);
+    assert_eq!(editor.autocompletion_prefix, format!("open {dir_path}/"));
+    editor.process_keystroke(Key::Char('\t'));
+    editor.process_keystroke(Key::Char('\n'));
+    assert_eq!(editor.document.filename, Some(dir.path().join("novel.md")));
+
+    process_command_no_enter(&mut editor, &format!(":w {dir_path}/not"));
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, format!(":w {dir_path}/notes.md"));
+    editor.process_keystroke(Key::Esc);
+
+    process_command_no_enter(&mut editor, &format!(":r {dir_path}/nov"));
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, format!(":r {dir_path}/novel.md"));
+}
+
+#[test]
+fn test_prompt_cursor_movements() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command_no_enter(&mut editor, ":2d");
+    editor.process_keystroke(Key::Left);
+    editor.process_keystroke(Key::Char(','));
+    editor.process_keystroke(Key::Char('3'));
+    assert_eq!(editor.command_buffer, ":2,3d");
+    editor.process_keystroke(Key::Home);
+    editor.process_keystroke(Key::Left);
+    assert_eq!(editor.prompt_cursor_position(), 1);
+    editor.process_keystroke(Key::Right);
+    editor.process_keystroke(Key::Backspace);
+    editor.process_keystroke(Key::Char('1'));
+    editor.process_keystroke(Key::End);
+    editor.process_keystroke(Key::Right);
+    assert_eq!(editor.prompt_cursor_position(), 5);
+    editor.process_keystroke(Key::Char('\n'));
+    assert_eq!(document_lines(&editor), vec!["line 4", "line 5", "line 6"]);
+}
+
+#[test]
+fn test_prompt_backspace_in_the_middle_of_the_line() {
+    let mut editor = get_test_editor();
+    process_command_no_enter(&mut editor, ":wqq");
+    editor.process_keystroke(Key::Left);
+    editor.process_keystroke(Key::Backspace);
+    assert_eq!(editor.command_buffer, ":wq");
+    assert_eq!(editor.prompt_cursor_position(), 2);
+    editor.process_keystroke(Key::Home);
+    editor.process_keystroke(Key::Backspace);
+    assert_eq!(editor.command_buffer, ":wq");
+}
+
+#[test]
+fn test_prompt_delete_word_and_line() {
+    let mut editor = get_test_editor();
+    process_command_no_enter(&mut editor, ":open foo/bar.md  ");
+    editor.process_keystroke(Key::Ctrl('w'));
+    assert_eq!(editor.command_buffer, ":open foo/bar.");
+    editor.process_keystroke(Key::Ctrl('w'));
+    assert_eq!(editor.command_buffer, ":open foo/bar");
+    editor.process_keystroke(Key::Left);
+    editor.process_keystroke(Key::Left);
+    editor.process_keystroke(Key::Left);
+    editor.process_keystroke(Key::Ctrl('u'));
+    assert_eq!(editor.command_buffer, ":bar");
+    assert_eq!(editor.prompt_cursor_position(), 1);
+}
+
+#[test]
+fn test_prompt_insert_register_and_word_under_cursor() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":3y a");
+    editor.cursor_position.x = GraphemeIndex::new(2);
+    process_command_no_enter(&mut editor, "/");
+    editor.process_keystroke(Key::Ctrl('r'));
+    editor.process_keystroke(Key::Char('a'));
+    assert_eq!(editor.command_buffer, "/line 3");
+    editor.process_keystroke(Key::Ctrl('u'));
+    editor.process_keystroke(Key::Ctrl('r'));
+    editor.process_keystroke(Key::Ctrl('w'));
+    assert_eq!(editor.command_buffer, "/line");
+    // unknown registers insert nothing
+    editor.process_keystroke(Key::Ctrl('r'));
+    editor.process_keystroke(Key::Char('z'));
+    assert_eq!(editor.command_buffer, "/line");
+}
+
+#[test]
+fn test_insert_mode_mapping() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":imap jk <Esc>");
+    editor.process_keystroke(Key::Char('i'));
+    process_command_no_enter(&mut editor, "ajk");
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(editor.current_row().string, "aHellö world");
+    assert!(editor.pending_keys.is_empty());
+
+    // a key that breaks the sequence gets the pending keys processed
+    editor.process_keystroke(Key::Char('i'));
+    process_command_no_enter(&mut editor, "jx");
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_eq!(editor.current_row().string, "ajxHellö world");
+}
+
+#[test]
+fn test_pending_mapping_keys_are_displayed() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = get_short_document();
+    process_command(&mut editor, ":imap jk <Esc>");
+    process_command(&mut editor, ":map QQ dd");
+    editor.process_keystroke(Key::Char('Q'));
+    editor.refresh_screen().unwrap();
+    assert!(console.line(79).ends_with('Q'));
+    assert_eq!(console.line(79).chars().count(), 110);
+    editor.process_keystroke(Key::Char('Q'));
+    editor.process_keystroke(Key::Char('A'));
+    editor.process_keystroke(Key::Char('j'));
+    editor.refresh_screen().unwrap();
+    // the pending key is displayed under the cursor, without being inserted
+    assert_eq!(editor.current_row().string, "Hello world!");
+    assert_eq!(console.line(0), "Hello world!j");
+    assert_eq!(console.cursor_position(), (12, 0));
+    editor.process_keystroke(Key::Char('k'));
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.line(0), "Hello world!");
+    assert_eq!(editor.mode, Mode::Normal);
+}
+
+#[test]
+fn test_pending_mapping_times_out() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":map Q :2d<CR>");
+    process_command(&mut editor, ":map QQ :3d<CR>");
+    editor.process_keystroke(Key::Char('Q'));
+    assert_eq!(editor.pending_keys, vec![Key::Char('Q')]);
+    assert_eq!(editor.document.num_rows(), 6);
+    // simulate the timeout
+    editor.flush_pending_keys();
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 3", "line 4", "line 5", "line 6"]
+    );
+    editor.process_keystroke(Key::Char('Q'));
+    editor.process_keystroke(Key::Char('Q'));
+    assert_eq!(
+        document_lines(&editor),
+        vec!["line 1", "line 3", "line 5", "line 6"]
+    );
+}
+
+#[test]
+fn test_recursive_and_non_recursive_mappings() {
+    let mut editor = get_test_editor_with_numbered_lines();
+    process_command(&mut editor, ":map x j");
+    process_command(&mut editor, ":map Y x");
+    process_command(&mut editor, ":noremap Z x");
+    editor.process_keystroke(Key::Char('Y'));
+    assert_eq!(editor.current_line_number(), LineNumber::new(2));
+    editor.cursor_position.x = GraphemeIndex::new(1);
+    editor.process_keystroke(Key::Char('Z'));
+    // x isn't remapped, and deletes the current character
+    assert_eq!(editor.current_line_number(), LineNumber::new(2));
+    assert_eq!(editor.current_row().string, "lne 2");
+
+    process_command(&mut editor, ":map a b");
+    process_command(&mut editor, ":map b a");
+    editor.process_keystroke(Key::Char('a'));
+    assert_eq!(editor.message, utils::red("Recursive mapping"));
+}
+
+#[test]
+fn test_list_mappings() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":imap");
+    assert_eq!(editor.message, "No mapping found");
+    process_command(&mut editor, ":inoremap jk <Esc>");
+    process_command(&mut editor, ":imap");
+    assert_eq!(editor.message, "jk <Esc>");
+    process_command(&mut editor, ":imap jk");
+    assert_eq!(editor.message, utils::red("Argument required: <rhs>"));
+    process_command(&mut editor, ":help");
+    assert!(editor.alternate_screen_text.contains("imap jk"));
+}
+
+#[test]
+fn test_load_config_file() {
+    let mut editor = get_test_editor();
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(b"\" insert mode\nimap jk <Esc>\n\nln\n")
+        .unwrap();
+    editor.load_config_file(f.path());
+    assert!(editor.config.display_line_numbers);
+    assert!(editor
+        .keymap
+        .get(MapMode::Insert, &[Key::Char('j'), Key::Char('k')])
+        .is_some());
+}
+
+#[test]
+fn test_set_fileformat() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":set ff");
+    assert_eq!(editor.message, "fileformat=unix");
+    assert!(!editor.is_dirty());
+    process_command(&mut editor, ":set fileformat=dos");
+    assert_eq!(editor.document.format.line_ending, LineEnding::Dos);
+    assert!(editor.is_dirty());
+    process_command(&mut editor, ":set fileformat=mac");
+    assert_eq!(editor.message, utils::red("Invalid fileformat 'mac'"));
+    process_command(&mut editor, ":set derp");
+    assert_eq!(editor.message, utils::red("Unknown option 'derp'"));
+}
+
+#[test]
+fn test_set_tab_options() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":set ts");
+    assert_eq!(editor.message, "tabstop=4");
+    process_command(&mut editor, ":set tabstop=8 sw=2");
+    assert_eq!(editor.config.tabstop, 8);
+    assert_eq!(editor.config.shiftwidth, 2);
+    process_command(&mut editor, ":set et?");
+    assert_eq!(editor.message, "expandtab");
+    process_command(&mut editor, ":set noexpandtab");
+    assert!(!editor.config.expandtab);
+    process_command(&mut editor, ":set expandtab?");
+    assert_eq!(editor.message, "noexpandtab");
+    process_command(&mut editor, ":set et");
+    assert!(editor.config.expandtab);
+    process_command(&mut editor, ":set sw=0");
+    assert_eq!(editor.message, utils::red("Invalid sw '0'"));
+    process_command(&mut editor, ":set noet=2");
+    assert_eq!(editor.message, utils::red("Unknown option 'noet'"));
+}
+
+#[test]
+fn test_set_option_autocompletion() {
+    let mut editor = get_test_editor();
+    process_command_no_enter(&mut editor, ":set fi");
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(
+        editor.command_suggestions,
+        vec!["fileencoding", "fileformat", "filetype"]
+    );
+}
+
+#[test]
+fn test_open_legacy_encoded_file() {
+    let console = Box::new(VirtualConsole::default());
+    let f = NamedTempFile::new().unwrap();
+    fs::write(f.path(), b"caf\xe9\n").unwrap();
+    let f_name = f.path().to_str().unwrap().to_string();
+    let mut editor = Editor::new(Some(f_name), console);
+    assert_eq!(editor.current_row().string, "café");
+    assert_eq!(editor.document.format.encoding, Encoding::Latin1);
+
+    // the file is written back in its original encoding
+    process_keystrokes(&mut editor, vec!['A', '!']);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":w");
+    assert_eq!(fs::read(f.path()).unwrap(), b"caf\xe9!\n");
+
+    // characters that can't be encoded are reported
+    process_command(&mut editor, ":set fenc=latin1");
+    process_keystrokes(&mut editor, vec!['A', '€']);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":w");
+    assert_eq!(
+        editor.message,
+        utils::red("Error writing to file: Cannot encode '€' in latin1")
+    );
+    // the file is left untouched
+    assert_eq!(fs::read(f.path()).unwrap(), b"caf\xe9!\n");
+    assert!(!f
+        .path()
+        .with_file_name(format!(
+            ".{}.tmp",
+            f.path().file_name().unwrap().to_str().unwrap()
+        ))
+        .exists());
+}
+
+#[test]
+fn test_reopen_file_with_encoding() {
+    let f = NamedTempFile::new().unwrap();
+    fs::write(f.path(), b"\x93quoted\x94\n").unwrap();
+    let f_name = f.path().to_str().unwrap().to_string();
+    let mut editor = get_test_editor();
+    process_command(&mut editor, &format!(":e {f_name}"));
+    assert_eq!(editor.current_row().string, "“quoted”");
+    process_command(&mut editor, ":e ++enc=latin1");
+    assert_eq!(editor.document.format.encoding, Encoding::Latin1);
+    assert_eq!(editor.current_row().string, "\u{93}quoted\u{94}");
+    process_command(&mut editor, ":e ++enc=klingon");
+    assert_eq!(editor.message, utils::red("Unknown encoding 'klingon'"));
+}
+
+#[test]
+fn test_open_invalid_file_displays_error() {
+    let console = Box::new(VirtualConsole::default());
+    let f = NamedTempFile::new().unwrap();
+    fs::write(f.path(), b"\xff\xfe\x00").unwrap();
+    let f_name = f.path().to_str().unwrap().to_string();
+    let editor = Editor::new(Some(f_name.clone()), console);
+    assert_eq!(editor.document.filename, None);
+    assert_eq!(
+        editor.message,
+        utils::red(&format!("Error opening {f_name}: Invalid utf-16le content"))
+    );
+}
+
+#[test]
+fn test_mapped_document_indexing_and_search() {
+    let f = NamedTempFile::new().unwrap();
+    let content = (0..3000)
+        .map(|i| format!("line {i}\n"))
+        .collect::<Vec<_>>()
+        .concat();
+    fs::write(f.path(), content).unwrap();
+    let mut editor = get_test_editor();
+    editor.document = Document::open_mapped(f.path().to_path_buf()).unwrap();
+    editor.last_saved_hash = editor.document.hashed();
+    while editor.document.indexing_progress().is_some() {
+        editor.index_mapped_rows();
+    }
+    assert_eq!(editor.message, "3000 lines indexed");
+    assert!(!editor.is_dirty());
+
+    process_command(&mut editor, "/line 2999");
+    assert_eq!(editor.message, "Match 1/1");
+    assert_eq!(editor.current_line_number().value, 3000);
+}
+
+#[test]
+fn test_refresh_screen_renders_document_and_bars() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = get_short_document();
+    editor.last_saved_hash = editor.document.hashed();
+    process_keystrokes(&mut editor, vec!['j', 'l', 'l']);
+    editor.display_message(utils::red("Oops"));
+    editor.refresh_screen().unwrap();
+
+    assert_eq!(console.line(0), "Hellö world");
+    assert_eq!(console.line(2), "Hello world!!");
+    assert_eq!(console.line(3), "~");
+    let status = console.line(78);
+    assert!(status.starts_with("[test] NORMAL"));
+    assert!(status.ends_with("Ln 2, Col 3"));
+    assert_eq!(console.cell(119, 78).unwrap().style, super::STATUS_STYLE);
+    assert_eq!(console.line(79), "Oops");
+    assert_eq!(console.cell(0, 79).unwrap().style.fg, Color::Ansi(1));
+    assert_eq!(console.cursor_position(), (2, 1));
+    assert!(console.is_cursor_visible());
+}
+
+#[test]
+fn test_refresh_screen_with_line_numbers_and_prompt() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = get_short_document();
+    process_command(&mut editor, ":ln");
+    process_command_no_enter(&mut editor, ":sta");
+    editor.refresh_screen().unwrap();
+
+    assert_eq!(console.line(0), "   1 Hellö world");
+    assert_eq!(console.line(1), "   2 Hello world!");
+    assert_eq!(console.line(79), ":sta");
+    assert_eq!(console.cursor_position(), (4, 79));
+}
+
+#[test]
+fn test_refresh_screen_after_scrolling_matches_full_redraw() {
+    let rows = (0..300).map(|i| Row::from(format!("line {i}").as_str()));
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = Document::new(rows.collect(), PathBuf::from("test"));
+    editor.refresh_screen().unwrap();
+    for command in [":100", ":90", ":150", ":1", ":300"] {
+        process_command(&mut editor, command);
+        editor.refresh_screen().unwrap();
+
+        let full_redraw_console = VirtualConsole::default();
+        editor.last_frame = None;
+        editor.terminal = Box::new(full_redraw_console.clone());
+        editor.refresh_screen().unwrap();
+        assert_eq!(console.contents(), full_redraw_console.contents());
+        editor.terminal = Box::new(console.clone());
+    }
+}
+
+#[test]
+fn test_cursor_is_placed_by_display_width() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = Document::new(
+        vec![Row::from("日本語 text"), Row::from("e\u{301}te\u{301}")],
+        PathBuf::from("test"),
+    );
+    process_keystrokes(&mut editor, vec!['l', 'l']);
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.line(0), "日本語 text");
+    assert_eq!(console.cursor_position(), (4, 0));
+    assert!(console.line(78).ends_with("Ln 1, Col 5"));
+
+    process_keystrokes(&mut editor, vec!['j']);
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.cursor_position(), (2, 1));
+    assert!(console.line(78).ends_with("Ln 2, Col 3"));
+
+    process_command(&mut editor, ":ln");
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.cursor_position(), (7, 1));
+}
+
+#[test]
+fn test_horizontal_scrolling_by_display_width() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    // 100 wide graphemes take 200 columns
+    editor.document = Document::new(
+        vec![Row::from("日".repeat(100).as_str())],
+        PathBuf::from("test"),
+    );
+    process_keystrokes(&mut editor, vec!['$']);
+    editor.refresh_screen().unwrap();
+    let (x, _) = console.cursor_position();
+    assert_eq!(console.cell(x, 0).unwrap().grapheme, "日");
+    assert!(x < 120);
+    assert_eq!(console.line(0), "日".repeat(60));
+}
+
+#[test]
+fn test_mouse_click_on_wide_grapheme() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("日本語 text")], PathBuf::from("test"));
+    // click on the right half of 本, 1-based
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 4, 1));
+    editor.process_mouse_event(MouseEvent::Release(4, 1));
+    assert_position_is(&editor, 1, 0);
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 8, 1));
+    editor.process_mouse_event(MouseEvent::Release(8, 1));
+    assert_position_is(&editor, 4, 0);
+}
+
+fn get_test_editor_with_prose() -> Editor {
+    let console = Box::new(VirtualConsole::default());
+    let mut editor = Editor::new(None, console);
+    let rows = vec![
+        Row::from("Dr. Watson came in. He sat down."),
+        Row::from("Holmes smiled."),
+    ];
+    editor.document = Document::new(rows, PathBuf::from("test"));
+    editor.last_saved_hash = editor.document.hashed();
+    editor
+}
+
+#[test]
+fn test_move_by_sentence() {
+    let mut editor = get_test_editor_with_prose();
+    editor.process_keystroke(Key::Char(')'));
+    assert_position_is(&editor, 20, 0);
+    editor.process_keystroke(Key::Char(')'));
+    assert_position_is(&editor, 0, 1);
+    editor.process_keystroke(Key::Char('('));
+    assert_position_is(&editor, 20, 0);
+    process_keystrokes(&mut editor, vec!['2', '(']);
+    assert_position_is(&editor, 0, 0);
+}
+
+#[test]
+fn test_delete_around_sentence_then_undo() {
+    let mut editor = get_test_editor_with_prose();
+    process_keystrokes(&mut editor, vec!['d', 'a', 's']);
+    assert_nth_row_is(&editor, 0, "He sat down.");
+    assert_position_is(&editor, 0, 0);
+    assert_eq!(editor.registers.get(&'"').unwrap(), "Dr. Watson came in. ");
+    editor.process_keystroke(Key::Char('u'));
+    assert_nth_row_is(&editor, 0, "Dr. Watson came in. He sat down.");
+}
+
+#[test]
+fn test_change_and_yank_inner_sentence() {
+    let mut editor = get_test_editor_with_prose();
+    editor.process_keystroke(Key::Char('j'));
+    process_keystrokes(&mut editor, vec!['y', 'i', 's']);
+    assert_eq!(editor.registers.get(&'"').unwrap(), "Holmes smiled.");
+    assert_nth_row_is(&editor, 1, "Holmes smiled.");
+
+    editor.process_keystroke(Key::Char('k'));
+    process_keystrokes(&mut editor, vec![')', 'c', 'i', 's']);
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_nth_row_is(&editor, 0, "Dr. Watson came in. ");
+    process_keystrokes(&mut editor, vec!['O', 'k', '.']);
+    assert_nth_row_is(&editor, 0, "Dr. Watson came in. Ok.");
+}
+
+#[test]
+fn test_display_sentence_count() {
+    let mut editor = get_test_editor_with_prose();
+    process_command(&mut editor, ":set sentencecount");
+    assert!(editor.config.sentencecount);
+    assert_eq!(
+        editor.generate_status(),
+        format!("[test] NORMAL{}[3S] [unix] Ln 1, Col 1\r", " ".repeat(84))
+    );
+    // the sentences are only counted again once the document is modified
+    assert_eq!(editor.sentence_count.get().map(|(_, count)| count), Some(3));
+    process_keystrokes(&mut editor, vec!['I', 'H', 'i', '.', ' ']);
+    editor.process_keystroke(Key::Esc);
+    assert!(editor.generate_status().contains("[4S]"));
+    process_command(&mut editor, ":set sentencecount?");
+    assert_eq!(editor.message, "sentencecount");
+    process_command(&mut editor, ":set nosentencecount");
+    assert!(!editor.config.sentencecount);
+}
+
+#[test]
+fn test_find_grapheme_in_line_and_repeat() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['j', 'f', 'o']);
+    assert_position_is(&editor, 4, 1);
+    editor.process_keystroke(Key::Char(';'));
+    assert_position_is(&editor, 7, 1);
+    editor.process_keystroke(Key::Char(','));
+    assert_position_is(&editor, 4, 1);
+    process_keystrokes(&mut editor, vec!['F', 'H']);
+    assert_position_is(&editor, 0, 1);
+    process_keystrokes(&mut editor, vec!['2', 't', 'l']);
+    assert_position_is(&editor, 2, 1);
+    process_keystrokes(&mut editor, vec!['$', 'T', 'w']);
+    assert_position_is(&editor, 7, 1);
+    // the searched grapheme can be a non-ASCII one
+    process_keystrokes(&mut editor, vec!['k', '0', 'f', 'ö']);
+    assert_position_is(&editor, 4, 0);
+    // nothing happens when the grapheme isn't found
+    process_keystrokes(&mut editor, vec!['f', 'z']);
+    assert_position_is(&editor, 4, 0);
+}
+
+#[test]
+fn test_operators_with_find_grapheme_motions() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("call(foo, bar) baz")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['f', '(', 'l', 'd', 't', ')']);
+    assert_current_line_is(&editor, "call() baz");
+    assert_position_is(&editor, 5, 0);
+    assert_eq!(editor.registers.get(&'"').unwrap(), "foo, bar");
+    editor.process_keystroke(Key::Char('u'));
+    assert_current_line_is(&editor, "call(foo, bar) baz");
+
+    process_keystrokes(&mut editor, vec!['$', 'd', 'F', 'b']);
+    assert_current_line_is(&editor, "call(foo, bar) z");
+    process_keystrokes(&mut editor, vec!['0', 'c', 'f', '(']);
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_current_line_is(&editor, "foo, bar) z");
+}
+
+fn assert_viewport_is(editor: &Editor, offset_rows: usize, cursor_y: usize) {
+    assert_eq!(editor.offset.rows, offset_rows);
+    assert_eq!(editor.cursor_position.y, cursor_y);
+}
+
+#[test]
+fn test_scroll_by_half_and_full_pages() {
+    // The terminal text area is 78 lines high, and the document 200 lines long
+    let mut editor = get_test_editor_with_long_document();
+    editor.process_keystroke(Key::Ctrl('d'));
+    assert_viewport_is(&editor, 39, 0);
+    editor.process_keystroke(Key::Ctrl('u'));
+    assert_viewport_is(&editor, 0, 0);
+    process_keystrokes(&mut editor, vec!['1', '0']);
+    editor.process_keystroke(Key::Ctrl('d'));
+    assert_viewport_is(&editor, 10, 0);
+
+    editor.process_keystroke(Key::Ctrl('f'));
+    assert_viewport_is(&editor, 86, 0);
+    // the last row of the document is displayed at the bottom of the screen
+    editor.process_keystroke(Key::Ctrl('f'));
+    assert_viewport_is(&editor, 122, 40);
+    editor.process_keystroke(Key::Ctrl('b'));
+    assert_viewport_is(&editor, 46, 77);
+    process_keystrokes(&mut editor, vec!['2']);
+    editor.process_keystroke(Key::Ctrl('b'));
+    assert_viewport_is(&editor, 0, 77);
+}
+
+#[test]
+fn test_scroll_by_line_keeps_cursor_in_place() {
+    let mut editor = get_test_editor_with_long_document();
+    process_keystrokes(&mut editor, vec!['5', 'j']);
+    editor.process_keystroke(Key::Ctrl('e'));
+    assert_viewport_is(&editor, 1, 4);
+    process_keystrokes(&mut editor, vec!['3']);
+    editor.process_keystroke(Key::Ctrl('e'));
+    assert_viewport_is(&editor, 4, 1);
+    // the cursor is dragged along when its row scrolls out of view
+    process_keystrokes(&mut editor, vec!['2']);
+    editor.process_keystroke(Key::Ctrl('e'));
+    assert_viewport_is(&editor, 6, 0);
+    assert_eq!(editor.current_row_index(), RowIndex::new(6));
+    editor.process_keystroke(Key::Ctrl('y'));
+    assert_viewport_is(&editor, 5, 1);
+}
+
+#[test]
+fn test_reposition_view_around_cursor() {
+    let mut editor = get_test_editor_with_long_document();
+    editor.move_cursor_to_position_y(RowIndex::new(100));
+    process_keystrokes(&mut editor, vec!['z', 't']);
+    assert_viewport_is(&editor, 100, 0);
+    process_keystrokes(&mut editor, vec!['z', 'b']);
+    assert_viewport_is(&editor, 23, 77);
+    process_keystrokes(&mut editor, vec!['z', 'z']);
+    assert_viewport_is(&editor, 62, 38);
+    assert_eq!(editor.current_row_index(), RowIndex::new(100));
+}
+
+#[test]
+fn test_scrolloff() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":set so=5");
+    process_command(&mut editor, ":set scrolloff?");
+    assert_eq!(editor.message, "scrolloff=5");
+
+    editor.move_cursor_to_position_y(RowIndex::new(100));
+    process_keystrokes(&mut editor, vec!['z', 't']);
+    assert_viewport_is(&editor, 95, 5);
+    process_keystrokes(&mut editor, vec!['k', 'k']);
+    assert_viewport_is(&editor, 93, 5);
+    editor.process_keystroke(Key::Ctrl('e'));
+    assert_viewport_is(&editor, 94, 5);
+    assert_eq!(editor.current_row_index(), RowIndex::new(99));
+    process_keystrokes(&mut editor, vec!['z', 'b']);
+    assert_viewport_is(&editor, 27, 72);
+
+    // the margins don't apply at the start of the document
+    process_keystrokes(&mut editor, vec!['g', 'g']);
+    assert_viewport_is(&editor, 0, 0);
+
+    process_command(&mut editor, ":set so=x");
+    assert_eq!(editor.message, utils::red("Invalid so 'x'"));
+}
+
+#[test]
+fn test_focus_mode_dims_other_paragraphs() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = Document::new(
+        vec![
+            Row::from("First paragraph,"),
+            Row::from("on two lines."),
+            Row::from(""),
+            Row::from(""),
+            Row::from("Second paragraph,"),
+            Row::from("also on two lines."),
+            Row::from(""),
+            Row::from("Third paragraph."),
+        ],
+        PathBuf::from("test"),
+    );
+    process_command(&mut editor, ":ln");
+    process_keystrokes(&mut editor, vec!['5', 'j']);
+    process_command(&mut editor, ":focus");
+    assert!(editor.config.focus);
+    editor.refresh_screen().unwrap();
+
+    // the text is centered in a 80 columns wide column, without line numbers
+    assert_eq!(
+        console.line(4),
+        format!("{}Second paragraph,", " ".repeat(20))
+    );
+    assert_eq!(console.cursor_position(), (20, 5));
+    for (y, dimmed) in [(0, true), (1, true), (4, false), (5, false), (7, true)] {
+        let style = console.cell(20, y).unwrap().style;
+        assert_eq!(style == super::DIMMED_STYLE, dimmed, "line {y}");
+    }
+    // the lines past the end of the document are blank
+    assert_eq!(console.line(8), "");
+    // the status bar is hidden until a key is pressed
+    assert_eq!(console.line(78), "");
+    editor.process_keystroke(Key::Char('k'));
+    editor.refresh_screen().unwrap();
+    assert!(console.line(78).contains("NORMAL"));
+
+    process_command(&mut editor, ":set focuswidth=40");
+    editor.refresh_screen().unwrap();
+    assert_eq!(
+        console.line(4),
+        format!("{}Second paragraph,", " ".repeat(40))
+    );
+
+    process_command(&mut editor, ":focus");
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.line(4), "   5 Second paragraph,");
+    assert_eq!(console.cell(5, 0).unwrap().style, Style::default());
+}
+
+#[test]
+fn test_focus_mode_typewriter_scrolling() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":focus");
+    process_command(&mut editor, ":100");
+    // the current line stays in the middle of the 78 lines high text area
+    assert_eq!(editor.offset.rows, 61);
+    assert_position_is(&editor, 0, 38);
+    process_keystrokes(&mut editor, vec!['j', 'j']);
+    assert_eq!(editor.offset.rows, 63);
+    assert_position_is(&editor, 0, 38);
+    // including at the end of the document
+    editor.process_keystroke(Key::Char('G'));
+    assert_eq!(editor.offset.rows, 161);
+    assert_position_is(&editor, 0, 38);
+    // but not at its start
+    process_keystrokes(&mut editor, vec!['g', 'g']);
+    assert_eq!(editor.offset.rows, 0);
+    assert_position_is(&editor, 0, 0);
+
+    // the status bar is hidden while typing
+    process_keystrokes(&mut editor, vec!['i', 'a']);
+    assert!(editor.status_bar_hidden);
+    editor.process_keystroke(Key::Esc);
+    assert!(!editor.status_bar_hidden);
+}
+
+#[test]
+fn test_markdown_rows_are_highlighted() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = Document::new(
+        vec![
+            Row::from("# Título"),
+            Row::from("Some `code` here"),
+            Row::from("```"),
+            Row::from("fenced"),
+        ],
+        PathBuf::from("post.md"),
+    );
+    process_command(&mut editor, ":ln");
+    editor.refresh_screen().unwrap();
+    let code_style = HighlightGroup::Code.default_style();
+
+    assert_eq!(
+        console.cell(5, 0).unwrap().style,
+        HighlightGroup::Heading.default_style()
+    );
+    assert_eq!(
+        console.cell(12, 0).unwrap().style,
+        HighlightGroup::Heading.default_style()
+    );
+    // the line number isn't highlighted
+    assert_eq!(console.cell(3, 0).unwrap().style, Style::default());
+    assert_eq!(console.cell(9, 1).unwrap().style, Style::default());
+    assert_eq!(console.cell(10, 1).unwrap().style, code_style);
+    assert_eq!(console.cell(15, 1).unwrap().style, code_style);
+    assert_eq!(console.cell(16, 1).unwrap().style, Style::default());
+    assert_eq!(console.cell(5, 3).unwrap().style, code_style);
+
+    // editing a row updates its highlighting
+    process_keystrokes(&mut editor, vec!['j', 'f', '`', 'x']);
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.line(1), "   2 Some code` here");
+    assert_eq!(console.cell(10, 1).unwrap().style, Style::default());
+}
+
+#[test]
+fn test_filetype() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = Document::new(
+        vec![Row::from("#!/bin/sh"), Row::from("echo hello")],
+        PathBuf::from("build"),
+    );
+    editor.refresh_screen().unwrap();
+    assert_eq!(editor.document.filetype, Some(Filetype::Shell));
+    assert!(console.line(78).ends_with("[sh] [unix] Ln 1, Col 1"));
+    process_command(&mut editor, ":set ft");
+    assert_eq!(editor.message, "filetype=sh");
+
+    assert_eq!(
+        console.cell(0, 0).unwrap().style,
+        HighlightGroup::Comment.default_style()
+    );
+
+    // setting the filetype changes the highlighting and the indentation
+    process_command(&mut editor, ":set filetype=json");
+    editor.refresh_screen().unwrap();
+    assert_eq!(console.cell(0, 0).unwrap().style, Style::default());
+    process_command(&mut editor, ":set filetype=make");
+    assert!(!editor.config.expandtab);
+    assert_eq!(editor.config.shiftwidth, editor.config.tabstop);
+    process_command(&mut editor, ":set ft=yaml");
+    assert!(editor.config.expandtab);
+    assert_eq!(editor.config.shiftwidth, 2);
+    process_command(&mut editor, ":set ft=cobol");
+    assert_eq!(editor.message, utils::red("Invalid filetype 'cobol'"));
+    assert_eq!(editor.document.filetype, Some(Filetype::Yaml));
+}
+
+#[test]
+fn test_highlight_command_changes_the_theme() {
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.document = Document::new(
+        vec![Row::from("fn main() { // entry point")],
+        PathBuf::from("main.rs"),
+    );
+    editor.refresh_screen().unwrap();
+    assert_eq!(
+        console.cell(0, 0).unwrap().style,
+        HighlightGroup::Keyword.default_style()
+    );
+    assert_eq!(console.cell(3, 0).unwrap().style, Style::default());
+    assert_eq!(
+        console.cell(12, 0).unwrap().style,
+        HighlightGroup::Comment.default_style()
+    );
+
+    process_command(&mut editor, ":hi Keyword #ff8700 bold");
+    editor.refresh_screen().unwrap();
+    let keyword_style = Style {
+        fg: Color::Rgb(255, 135, 0),
+        bg: Color::Default,
+        bold: true,
+        underline: false,
+    };
+    assert_eq!(console.cell(0, 0).unwrap().style, keyword_style);
+    assert_eq!(
+        editor.config.theme.describe(HighlightGroup::Keyword),
+        "Keyword=#ff8700,bold"
+    );
+
+    process_command(&mut editor, ":highlight Keyword nobold");
+    process_command(&mut editor, ":highlight Keyword");
+    assert_eq!(
+        editor.config.theme.style(HighlightGroup::Keyword),
+        Style {
+            bold: false,
+            ..keyword_style
+        }
+    );
+    process_command(&mut editor, ":highlight Keywords red");
+    assert_eq!(
+        editor.message,
+        utils::red("Unknown highlight group 'Keywords'")
+    );
+    process_command(&mut editor, ":highlight Comment grey");
+    assert_eq!(editor.message, utils::red("Invalid color 'grey'"));
+    process_command(&mut editor, ":highlight");
+    assert!(editor.message.starts_with("Heading=4,bold Emphasis=5 "));
+}
+
+#[test]
+fn test_spell_checking() {
+    let dir = tempdir().unwrap();
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.config.spell_directories = vec![dir.path().to_path_buf()];
+    editor.config.spellfile = dir.path().join("personal.add").display().to_string();
+    editor.document = Document::new(
+        vec![
+            Row::from("The cta sat on `teh` mat"),
+            Row::from("the mta and the bo"),
+        ],
+        PathBuf::from("notes.md"),
+    );
+    process_command(&mut editor, ":set spell");
+    assert_eq!(
+        editor.message,
+        utils::red("No dictionary found for language 'en_US'")
+    );
+    assert!(!editor.config.spell);
+    process_keystrokes(&mut editor, vec![']', 's']);
+    assert_eq!(editor.message, utils::red("Spell checking is not enabled"));
+
+    fs::write(
+        dir.path().join("en_US.txt"),
+        "the\ncat\nsat\non\nmat\nand\nto\n",
+    )
+    .unwrap();
+    process_command(&mut editor, ":set spell");
+    assert!(editor.config.spell);
+    editor.refresh_screen().unwrap();
+    // misspelled words are underlined, but not the ones in Markdown code
+    assert!(!console.cell(3, 0).unwrap().style.underline);
+    assert!(console.cell(4, 0).unwrap().style.underline);
+    assert!(console.cell(6, 0).unwrap().style.underline);
+    assert!(!console.cell(7, 0).unwrap().style.underline);
+    assert!(!console.cell(17, 0).unwrap().style.underline);
+
+    process_keystrokes(&mut editor, vec![']', 's']);
+    assert_position_is(&editor, 4, 0);
+    process_keystrokes(&mut editor, vec!['2', ']', 's']);
+    assert_position_is(&editor, 16, 1);
+    // the search wraps around the document
+    process_keystrokes(&mut editor, vec![']', 's']);
+    assert_position_is(&editor, 4, 0);
+    process_keystrokes(&mut editor, vec!['[', 's']);
+    assert_position_is(&editor, 16, 1);
+    process_keystrokes(&mut editor, vec!['[', 's']);
+    assert_position_is(&editor, 4, 1);
+
+    // z= suggests replacements for the word under the cursor
+    process_keystrokes(&mut editor, vec!['z', '=']);
+    assert!(editor.is_receiving_command());
+    assert_eq!(editor.command_buffer, ":spellreplace ");
+    assert_eq!(editor.command_suggestions, vec!["mat", "cat", "sat", "to"]);
+    editor.process_keystroke(Key::Char('\n'));
+    assert_nth_row_is(&editor, 1, "the mat and the bo");
+    assert_position_is(&editor, 4, 1);
+    // like a change, the replacement is undone as a deletion and an insertion
+    process_keystrokes(&mut editor, vec!['u', 'u']);
+    assert_nth_row_is(&editor, 1, "the mta and the bo");
+
+    // zg adds the word under the cursor to the personal dictionary
+    process_keystrokes(&mut editor, vec!['$', 'z', 'g']);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("personal.add")).unwrap(),
+        "bo\n"
+    );
+    process_keystrokes(&mut editor, vec![']', 's']);
+    assert_position_is(&editor, 4, 0);
+    // the personal dictionary is loaded along with the dictionary
+    editor.dictionary = None;
+    process_command(&mut editor, ":set spell");
+    assert!(editor.dictionary.as_ref().unwrap().contains("bo"));
+
+    process_command(&mut editor, ":set nospell");
+    editor.refresh_screen().unwrap();
+    assert!(!console.cell(4, 0).unwrap().style.underline);
+}
+
+#[test]
+fn test_prose_linter() {
+    let dir = tempdir().unwrap();
+    let console = VirtualConsole::default();
+    let mut editor = Editor::new(None, Box::new(console.clone()));
+    editor.config.lint_directory = dir.path().to_path_buf();
+    editor.document = Document::new(
+        vec![
+            Row::from("The plan was written quickly."),
+            Row::from("It is `very` good, very good."),
+            Row::from(""),
+            Row::from("The the end."),
+        ],
+        PathBuf::from("notes.md"),
+    );
+    let rules = dir.path().join("house.rules");
+    fs::write(&rules, "synergy\n").unwrap();
+    process_command(&mut editor, ":set lint");
+    assert_eq!(
+        editor.message,
+        utils::red(&format!(
+            "Invalid rule set {}: line 1: Pattern 'synergy' outside of a rule",
+            rules.display()
+        ))
+    );
+    assert!(!editor.config.lint);
+
+    fs::remove_file(&rules).unwrap();
+    process_command(&mut editor, ":set lint");
+    process_command(&mut editor, ":set lint?");
+    assert_eq!(editor.message, "lint");
+    editor.refresh_screen().unwrap();
+    // the findings are highlighted, but not the ones in Markdown code
+    let lint_style = HighlightGroup::Lint.default_style();
+    assert_ne!(console.cell(8, 0).unwrap().style, lint_style);
+    assert_eq!(console.cell(9, 0).unwrap().style, lint_style);
+    assert_eq!(console.cell(19, 0).unwrap().style, lint_style);
+    assert_ne!(console.cell(20, 0).unwrap().style, lint_style);
+    assert_ne!(console.cell(7, 1).unwrap().style, lint_style);
+    assert_eq!(console.cell(20, 1).unwrap().style, lint_style);
+    assert_eq!(console.cell(6, 3).unwrap().style, lint_style);
+
+    process_keystrokes(&mut editor, vec![']', 'l']);
+    assert_position_is(&editor, 9, 0);
+    assert_eq!(editor.message, "Passive voice: 'was written' (1/4)");
+    process_keystrokes(&mut editor, vec!['2', ']', 'l']);
+    assert_position_is(&editor, 19, 1);
+    assert_eq!(editor.message, "Weasel word: 'very' (3/4)");
+    process_keystrokes(&mut editor, vec![']', 'l']);
+    assert_position_is(&editor, 0, 3);
+    // the search wraps around the document
+    process_keystrokes(&mut editor, vec![']', 'l']);
+    assert_position_is(&editor, 9, 0);
+    process_keystrokes(&mut editor, vec!['[', 'l']);
+    assert_position_is(&editor, 0, 3);
+    assert_eq!(editor.message, "Repeated word: 'the' (4/4)");
+
+    process_command(&mut editor, ":set sentencelength=0");
+    assert_eq!(editor.message, utils::red("Invalid sentencelength '0'"));
+    process_command(&mut editor, ":set sentencelength=5");
+    process_command(&mut editor, ":lint");
+    assert!(editor.alternate_screen);
+    assert_eq!(
+        editor.alternate_screen_text,
+        "5 style findings
+
+Ln 1, Col 10: Passive voice: 'was written'
+Ln 1, Col 22: Adverb: 'quickly'
+Ln 2, Col 20: Weasel word: 'very'
+Ln 2, Col 25: Long sentence: 6 words
+Ln 4, Col 1: Repeated word: 'the'"
+    );
+    process_keystrokes(&mut editor, vec!['q']);
+
+    process_command(&mut editor, ":set nolint");
+    editor.refresh_screen().unwrap();
+    assert_ne!(console.cell(9, 0).unwrap().style, lint_style);
+}
@@ -0,0 +1,145 @@
+/// This module implements the character encodings a file can be read from
+/// and written to. Documents are always edited as UTF-8 strings, and are
+/// transcoded from and to their original encoding when opened and saved.
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// Characters that Windows-1252 maps to the 0x80-0x9F range, where Latin-1 has
+/// control characters. Undefined bytes are mapped to the matching control character.
+const WINDOWS_1252_HIGH_CONTROLS: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+pub enum Encoding {
+    Utf8,
+    Latin1,
+    Windows1252,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Encoding::Utf8 => write!(f, "utf-8"),
+            Encoding::Latin1 => write!(f, "latin1"),
+            Encoding::Windows1252 => write!(f, "cp1252"),
+            Encoding::Utf16Le => write!(f, "utf-16le"),
+            Encoding::Utf16Be => write!(f, "utf-16be"),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "latin1" | "iso-8859-1" | "iso8859-1" => Ok(Encoding::Latin1),
+            "cp1252" | "windows-1252" => Ok(Encoding::Windows1252),
+            "utf-16" | "utf-16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" => Ok(Encoding::Utf16Be),
+            _ => Err(format!("Unknown encoding '{s}'")),
+        }
+    }
+}
+
+impl Encoding {
+    /// Guess the encoding of the file content. UTF-16 is detected through its
+    /// byte order mark. Content that isn't valid UTF-8 is considered as
+    /// Windows-1252 if it contains any of the characters it defines in the
+    /// 0x80-0x9F range (eg: curly quotes), and as Latin-1 otherwise.
+    #[must_use]
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&[0xff, 0xfe]) {
+            Encoding::Utf16Le
+        } else if bytes.starts_with(&[0xfe, 0xff]) {
+            Encoding::Utf16Be
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else if bytes
+            .iter()
+            .any(|&b| (0x80..=0x9f).contains(&b) && !matches!(b, 0x81 | 0x8d | 0x8f | 0x90 | 0x9d))
+        {
+            Encoding::Windows1252
+        } else {
+            Encoding::Latin1
+        }
+    }
+
+    /// Decode the bytes into a string. A UTF-16 byte order mark is decoded as
+    /// a U+FEFF character, like a UTF-8 one.
+    ///
+    /// # Errors
+    /// Returns an error if the bytes aren't valid in the encoding.
+    pub fn decode(self, bytes: &[u8]) -> Result<String, String> {
+        match self {
+            Encoding::Utf8 => {
+                String::from_utf8(bytes.to_vec()).map_err(|_| String::from("Invalid utf-8 content"))
+            }
+            Encoding::Latin1 => Ok(bytes.iter().map(|&b| char::from(b)).collect()),
+            Encoding::Windows1252 => Ok(bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9f => WINDOWS_1252_HIGH_CONTROLS[usize::from(b - 0x80)],
+                    _ => char::from(b),
+                })
+                .collect()),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                if !bytes.len().is_multiple_of(2) {
+                    return Err(format!("Invalid {self} content"));
+                }
+                let units = bytes.chunks_exact(2).map(|pair| {
+                    if self == Encoding::Utf16Le {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    }
+                });
+                char::decode_utf16(units)
+                    .collect::<Result<String, _>>()
+                    .map_err(|_| format!("Invalid {self} content"))
+            }
+        }
+    }
+
+    /// Encode the string into bytes.
+    ///
+    /// # Errors
+    /// Returns an error if the string contains a character that can't be
+    /// represented in the encoding.
+    pub fn encode(self, s: &str) -> Result<Vec<u8>, String> {
+        let unrepresentable = |c: char| format!("Cannot encode '{c}' in {self}");
+        match self {
+            Encoding::Utf8 => Ok(s.as_bytes().to_vec()),
+            Encoding::Latin1 => s
+                .chars()
+                .map(|c| u8::try_from(u32::from(c)).map_err(|_| unrepresentable(c)))
+                .collect(),
+            Encoding::Windows1252 => s
+                .chars()
+                .map(|c| {
+                    if let Some(i) = WINDOWS_1252_HIGH_CONTROLS.iter().position(|&hc| hc == c) {
+                        // i < 32, as the table has 32 entries
+                        return Ok(0x80 + u8::try_from(i).unwrap_or_default());
+                    }
+                    match u8::try_from(u32::from(c)) {
+                        Ok(b) if !(0x80..=0x9f).contains(&b) => Ok(b),
+                        _ => Err(unrepresentable(c)),
+                    }
+                })
+                .collect(),
+            Encoding::Utf16Le => Ok(s.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Encoding::Utf16Be => Ok(s.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./encoding_test.rs"]
+mod encoding_test;
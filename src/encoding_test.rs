@@ -0,0 +1,62 @@
+use crate::encoding::Encoding;
+
+#[test]
+fn test_detect() {
+    assert_eq!(Encoding::detect(b"hello"), Encoding::Utf8);
+    assert_eq!(Encoding::detect("héllo".as_bytes()), Encoding::Utf8);
+    assert_eq!(Encoding::detect(b"h\xe9llo"), Encoding::Latin1);
+    assert_eq!(Encoding::detect(b"\x93hello\x94"), Encoding::Windows1252);
+    assert_eq!(Encoding::detect(b"\xff\xfeh\x00"), Encoding::Utf16Le);
+    assert_eq!(Encoding::detect(b"\xfe\xff\x00h"), Encoding::Utf16Be);
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(
+        Encoding::Latin1.decode(b"h\xe9llo"),
+        Ok(String::from("héllo"))
+    );
+    assert_eq!(
+        Encoding::Windows1252.decode(b"\x93hello\x94 \x80"),
+        Ok(String::from("“hello” €"))
+    );
+    assert_eq!(
+        Encoding::Utf16Le.decode(b"\xff\xfeh\x00\xe9\x00"),
+        Ok(String::from("\u{feff}hé"))
+    );
+    assert_eq!(
+        Encoding::Utf16Be.decode(b"\x00h\x00i"),
+        Ok(String::from("hi"))
+    );
+    assert!(Encoding::Utf16Le.decode(b"h\x00i").is_err());
+    assert!(Encoding::Utf8.decode(b"h\xe9llo").is_err());
+}
+
+#[test]
+fn test_encode() {
+    assert_eq!(Encoding::Latin1.encode("héllo"), Ok(b"h\xe9llo".to_vec()));
+    assert_eq!(
+        Encoding::Latin1.encode("€"),
+        Err(String::from("Cannot encode '€' in latin1"))
+    );
+    assert_eq!(
+        Encoding::Windows1252.encode("“é” €"),
+        Ok(b"\x93\xe9\x94 \x80".to_vec())
+    );
+    assert_eq!(
+        Encoding::Utf16Le.encode("hé"),
+        Ok(b"h\x00\xe9\x00".to_vec())
+    );
+    assert_eq!(
+        Encoding::Utf16Be.encode("hé"),
+        Ok(b"\x00h\x00\xe9".to_vec())
+    );
+}
+
+#[test]
+fn test_parse_encoding_name() {
+    assert_eq!("latin1".parse::<Encoding>(), Ok(Encoding::Latin1));
+    assert_eq!("ISO8859-1".parse::<Encoding>(), Ok(Encoding::Latin1));
+    assert_eq!("UTF-16".parse::<Encoding>(), Ok(Encoding::Utf16Le));
+    assert!("klingon".parse::<Encoding>().is_err());
+}
@@ -0,0 +1,153 @@
+/// This module describes how a document is laid out on disk, independently
+/// of its content: the line ending style, whether the last line is followed
+/// by a newline, whether the file starts with a byte order mark and its
+/// character encoding.
+///
+/// The format is detected when a file is opened, and used to write the
+/// document back exactly as it was found.
+use crate::Encoding;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+const BOM: char = '\u{feff}';
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+pub enum LineEnding {
+    /// ``\n``
+    Unix,
+    /// ``\r\n``
+    Dos,
+}
+
+impl LineEnding {
+    /// Detect the line ending of the content, which is considered as using DOS
+    /// line endings only if every line ends with ``\r\n``
+    #[must_use]
+    pub fn detect(content: &[u8]) -> Self {
+        let num_newlines = content.split(|&b| b == b'\n').count() - 1;
+        let num_dos_newlines = content.windows(2).filter(|pair| pair == b"\r\n").count();
+        if num_newlines > 0 && num_dos_newlines == num_newlines {
+            LineEnding::Dos
+        } else {
+            LineEnding::Unix
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Unix => "\n",
+            LineEnding::Dos => "\r\n",
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LineEnding::Unix => write!(f, "unix"),
+            LineEnding::Dos => write!(f, "dos"),
+        }
+    }
+}
+
+impl FromStr for LineEnding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unix" => Ok(LineEnding::Unix),
+            "dos" => Ok(LineEnding::Dos),
+            _ => Err(format!("Invalid fileformat '{s}'")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+pub struct FileFormat {
+    pub line_ending: LineEnding,
+    pub final_newline: bool,
+    pub bom: bool,
+    pub encoding: Encoding,
+}
+
+impl Default for FileFormat {
+    fn default() -> Self {
+        Self {
+            line_ending: LineEnding::Unix,
+            final_newline: true,
+            bom: false,
+            encoding: Encoding::Utf8,
+        }
+    }
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.line_ending)?;
+        if self.encoding != Encoding::Utf8 {
+            write!(f, ", {}", self.encoding)?;
+        }
+        if !self.final_newline {
+            write!(f, ", noeol")?;
+        }
+        if self.bom {
+            write!(f, ", bom")?;
+        }
+        Ok(())
+    }
+}
+
+impl FileFormat {
+    /// Detect the format of the decoded file content, and split it into lines.
+    ///
+    /// The file is considered as using DOS line endings only if every line ends
+    /// with ``\r\n``. Otherwise, any ``\r`` is kept as part of the line content,
+    /// so that it can be written back unchanged.
+    #[must_use]
+    pub fn detect(content: &str) -> (Self, Vec<&str>) {
+        let (bom, content) = match content.strip_prefix(BOM) {
+            Some(stripped) => (true, stripped),
+            None => (false, content),
+        };
+        let final_newline = content.is_empty() || content.ends_with('\n');
+        let line_ending = LineEnding::detect(content.as_bytes());
+        let mut lines: Vec<&str> = content.split(line_ending.as_str()).collect();
+        if final_newline {
+            // the empty string following the final newline isn't a line
+            lines.pop();
+        }
+        let format = Self {
+            line_ending,
+            final_newline,
+            bom,
+            encoding: Encoding::Utf8,
+        };
+        (format, lines)
+    }
+
+    /// Return the text to write before the first line
+    #[must_use]
+    pub fn header(&self) -> &'static str {
+        if self.bom {
+            "\u{feff}"
+        } else {
+            ""
+        }
+    }
+
+    /// Return the text to write after the line, depending on whether it is the last one
+    #[must_use]
+    pub fn line_terminator(&self, is_last_line: bool) -> &'static str {
+        if is_last_line && !self.final_newline {
+            ""
+        } else {
+            self.line_ending.as_str()
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./file_format_test.rs"]
+mod file_format_test;
@@ -0,0 +1,54 @@
+use crate::file_format::{FileFormat, LineEnding};
+use crate::Encoding;
+
+#[test]
+fn test_detect_unix() {
+    let (format, lines) = FileFormat::detect("a\nb\n");
+    assert_eq!(format, FileFormat::default());
+    assert_eq!(lines, vec!["a", "b"]);
+}
+
+#[test]
+fn test_detect_dos() {
+    let (format, lines) = FileFormat::detect("a\r\nb\r\n");
+    assert_eq!(format.line_ending, LineEnding::Dos);
+    assert_eq!(lines, vec!["a", "b"]);
+}
+
+#[test]
+fn test_detect_mixed_line_endings_keeps_carriage_returns() {
+    let (format, lines) = FileFormat::detect("a\r\nb\n");
+    assert_eq!(format.line_ending, LineEnding::Unix);
+    assert_eq!(lines, vec!["a\r", "b"]);
+}
+
+#[test]
+fn test_detect_missing_final_newline_and_bom() {
+    let (format, lines) = FileFormat::detect("\u{feff}a\nb");
+    assert!(format.bom);
+    assert!(!format.final_newline);
+    assert_eq!(lines, vec!["a", "b"]);
+    assert_eq!(format.to_string(), "unix, noeol, bom");
+}
+
+#[test]
+fn test_detect_empty_content() {
+    let (format, lines) = FileFormat::detect("");
+    assert_eq!(format, FileFormat::default());
+    assert!(lines.is_empty());
+}
+
+#[test]
+fn test_line_terminator() {
+    let format = FileFormat {
+        line_ending: LineEnding::Dos,
+        final_newline: false,
+        bom: false,
+        encoding: Encoding::Latin1,
+    };
+    assert_eq!(format.line_terminator(false), "\r\n");
+    assert_eq!(format.line_terminator(true), "");
+    assert_eq!(format.to_string(), "dos, latin1, noeol");
+    assert_eq!("dos".parse::<LineEnding>(), Ok(LineEnding::Dos));
+    assert!("mac".parse::<LineEnding>().is_err());
+}
@@ -0,0 +1,225 @@
+/// This module detects the type of the edited file, from its modeline,
+/// its name, its extension or its shebang line, in that order of priority.
+/// The filetype selects the syntax the file is highlighted with, the
+/// indentation it is edited with, and the comments its grammar highlights.
+use crate::highlighting::Syntax;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Number of lines at the start and at the end of a file searched for a modeline
+pub const MODELINE_LINES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filetype {
+    GitIgnore,
+    Json,
+    Make,
+    Markdown,
+    Python,
+    Rust,
+    Shell,
+    Text,
+    Toml,
+    Yaml,
+}
+
+/// How a filetype is conventionally indented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indentation {
+    Tabs,
+    Spaces(usize),
+}
+
+/// How a filetype writes comments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentSyntax {
+    /// Marker starting a comment that runs until the end of the line (ex: ``#``)
+    pub line: Option<&'static str>,
+    /// Markers opening and closing a block comment (ex: ``/*`` and ``*/``)
+    pub block: Option<(&'static str, &'static str)>,
+}
+
+impl CommentSyntax {
+    pub const NONE: Self = Self {
+        line: None,
+        block: None,
+    };
+    /// Comments of shell, Python, TOML, YAML or Makefiles
+    pub const HASH: Self = Self {
+        line: Some("#"),
+        block: None,
+    };
+    /// Comments of C-like languages, such as Rust
+    pub const C: Self = Self {
+        line: Some("//"),
+        block: Some(("/*", "*/")),
+    };
+    /// HTML comments, used in Markdown
+    pub const HTML: Self = Self {
+        line: None,
+        block: Some(("<!--", "-->")),
+    };
+}
+
+impl fmt::Display for Filetype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Filetype::GitIgnore => "gitignore",
+            Filetype::Json => "json",
+            Filetype::Make => "make",
+            Filetype::Markdown => "markdown",
+            Filetype::Python => "python",
+            Filetype::Rust => "rust",
+            Filetype::Shell => "sh",
+            Filetype::Text => "text",
+            Filetype::Toml => "toml",
+            Filetype::Yaml => "yaml",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Filetype {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gitignore" => Ok(Filetype::GitIgnore),
+            "json" => Ok(Filetype::Json),
+            "make" => Ok(Filetype::Make),
+            "markdown" | "md" => Ok(Filetype::Markdown),
+            "python" => Ok(Filetype::Python),
+            "rust" => Ok(Filetype::Rust),
+            "sh" | "bash" | "zsh" => Ok(Filetype::Shell),
+            "text" => Ok(Filetype::Text),
+            "toml" => Ok(Filetype::Toml),
+            "yaml" => Ok(Filetype::Yaml),
+            _ => Err(format!("Invalid filetype '{s}'")),
+        }
+    }
+}
+
+impl Filetype {
+    /// Detect the filetype of a file from its first and last lines and its
+    /// name. A modeline (ex: ``# vim: ft=yaml``) takes precedence over the
+    /// filename, which takes precedence over the shebang line.
+    #[must_use]
+    pub fn detect(filename: Option<&Path>, head: &[&str], tail: &[&str]) -> Option<Self> {
+        head.iter()
+            .chain(tail)
+            .find_map(|line| Self::from_modeline(line))
+            .or_else(|| filename.and_then(Self::from_filename))
+            .or_else(|| head.first().and_then(|line| Self::from_shebang(line)))
+    }
+
+    /// Return the filetype of a well-known filename (ex: ``Makefile``), or
+    /// the one associated with the extension of the filename
+    #[must_use]
+    pub fn from_filename(filename: &Path) -> Option<Self> {
+        match filename.file_name()?.to_str()? {
+            "Makefile" | "makefile" | "GNUmakefile" => return Some(Filetype::Make),
+            "Cargo.lock" | "Pipfile" => return Some(Filetype::Toml),
+            ".gitignore" | ".dockerignore" => return Some(Filetype::GitIgnore),
+            ".bashrc" | ".bash_profile" | ".profile" | ".zshrc" => return Some(Filetype::Shell),
+            _ => (),
+        }
+        match filename.extension()?.to_str()? {
+            "json" => Some(Filetype::Json),
+            "mk" => Some(Filetype::Make),
+            "md" | "markdown" => Some(Filetype::Markdown),
+            "py" => Some(Filetype::Python),
+            "rs" => Some(Filetype::Rust),
+            "sh" | "bash" | "zsh" => Some(Filetype::Shell),
+            "txt" => Some(Filetype::Text),
+            "toml" => Some(Filetype::Toml),
+            "yaml" | "yml" => Some(Filetype::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Return the filetype of the script run by the interpreter of a shebang
+    /// line (ex: ``#!/usr/bin/env python3``)
+    #[must_use]
+    pub fn from_shebang(line: &str) -> Option<Self> {
+        let mut words = line.strip_prefix("#!")?.split_whitespace();
+        let mut interpreter = words.next()?.rsplit('/').next()?;
+        if interpreter == "env" {
+            interpreter = words.find(|word| !word.starts_with('-'))?;
+        }
+        match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "sh" | "bash" | "dash" | "ksh" | "zsh" => Some(Filetype::Shell),
+            "python" => Some(Filetype::Python),
+            "make" => Some(Filetype::Make),
+            _ => None,
+        }
+    }
+
+    /// Return the filetype set by a vim-style modeline contained in the line
+    /// (ex: ``# vim: set ft=yaml:`` or ``<!-- bo: filetype=markdown -->``)
+    #[must_use]
+    pub fn from_modeline(line: &str) -> Option<Self> {
+        let options = ["vim:", "vi:", "ex:", "bo:"].iter().find_map(|marker| {
+            let start = line.find(marker)?;
+            let preceded_by_whitespace = line[..start]
+                .chars()
+                .next_back()
+                .is_none_or(char::is_whitespace);
+            preceded_by_whitespace.then(|| &line[start + marker.len()..])
+        })?;
+        options
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .find_map(|option| {
+                option
+                    .strip_prefix("ft=")
+                    .or_else(|| option.strip_prefix("filetype="))
+            })
+            .and_then(|name| name.parse().ok())
+    }
+
+    /// Return the syntax the filetype is highlighted with
+    #[must_use]
+    pub fn syntax(self) -> Option<Syntax> {
+        match self {
+            Filetype::Json => Some(Syntax::Json),
+            Filetype::Make => Some(Syntax::Make),
+            Filetype::Markdown => Some(Syntax::Markdown),
+            Filetype::Rust => Some(Syntax::Rust),
+            Filetype::Shell => Some(Syntax::Shell),
+            Filetype::Toml => Some(Syntax::Toml),
+            Filetype::Yaml => Some(Syntax::Yaml),
+            Filetype::GitIgnore | Filetype::Python | Filetype::Text => None,
+        }
+    }
+
+    /// Return the conventional indentation of the filetype, if it has one
+    #[must_use]
+    pub fn indentation(self) -> Option<Indentation> {
+        match self {
+            Filetype::Make => Some(Indentation::Tabs),
+            Filetype::Json | Filetype::Yaml => Some(Indentation::Spaces(2)),
+            Filetype::Python | Filetype::Rust => Some(Indentation::Spaces(4)),
+            _ => None,
+        }
+    }
+
+    /// Return the comment syntax of the filetype, if it has comments
+    #[must_use]
+    pub fn comment_syntax(self) -> Option<CommentSyntax> {
+        match self {
+            Filetype::GitIgnore
+            | Filetype::Make
+            | Filetype::Python
+            | Filetype::Shell
+            | Filetype::Toml
+            | Filetype::Yaml => Some(CommentSyntax::HASH),
+            Filetype::Markdown => Some(CommentSyntax::HTML),
+            Filetype::Rust => Some(CommentSyntax::C),
+            Filetype::Json | Filetype::Text => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./filetype_test.rs"]
+mod filetype_test;
@@ -0,0 +1,111 @@
+use crate::filetype::{CommentSyntax, Filetype, Indentation};
+use crate::highlighting::Syntax;
+use std::path::Path;
+
+#[test]
+fn test_filetype_from_filename() {
+    let from_filename = |filename| Filetype::from_filename(Path::new(filename));
+    assert_eq!(from_filename("post.md"), Some(Filetype::Markdown));
+    assert_eq!(
+        from_filename("notes/README.markdown"),
+        Some(Filetype::Markdown)
+    );
+    assert_eq!(from_filename("src/main.rs"), Some(Filetype::Rust));
+    assert_eq!(from_filename(".github/ci.yml"), Some(Filetype::Yaml));
+    assert_eq!(from_filename("Cargo.toml"), Some(Filetype::Toml));
+    assert_eq!(from_filename("Cargo.lock"), Some(Filetype::Toml));
+    assert_eq!(from_filename("Makefile"), Some(Filetype::Make));
+    assert_eq!(
+        from_filename("project/.gitignore"),
+        Some(Filetype::GitIgnore)
+    );
+    assert_eq!(from_filename("~/.bashrc"), Some(Filetype::Shell));
+    assert_eq!(from_filename("notes.txt"), Some(Filetype::Text));
+    assert_eq!(from_filename("LICENSE"), None);
+    assert_eq!(from_filename("archive.tar.gz"), None);
+}
+
+#[test]
+fn test_filetype_from_shebang() {
+    assert_eq!(Filetype::from_shebang("#!/bin/sh"), Some(Filetype::Shell));
+    assert_eq!(
+        Filetype::from_shebang("#!/usr/bin/env bash"),
+        Some(Filetype::Shell)
+    );
+    assert_eq!(
+        Filetype::from_shebang("#!/usr/bin/env -S python3.11 -u"),
+        Some(Filetype::Python)
+    );
+    assert_eq!(
+        Filetype::from_shebang("#! /usr/bin/make -f"),
+        Some(Filetype::Make)
+    );
+    assert_eq!(Filetype::from_shebang("#!/usr/bin/perl"), None);
+    assert_eq!(Filetype::from_shebang("# /bin/sh"), None);
+}
+
+#[test]
+fn test_filetype_from_modeline() {
+    assert_eq!(
+        Filetype::from_modeline("# vim: set ft=yaml:"),
+        Some(Filetype::Yaml)
+    );
+    assert_eq!(
+        Filetype::from_modeline("// vim: ts=4 sw=4 filetype=rust"),
+        Some(Filetype::Rust)
+    );
+    assert_eq!(
+        Filetype::from_modeline("<!-- bo: ft=md -->"),
+        Some(Filetype::Markdown)
+    );
+    // the marker must follow a whitespace, and the filetype must be known
+    assert_eq!(Filetype::from_modeline("nvim: ft=yaml"), None);
+    assert_eq!(Filetype::from_modeline("# vim: ft=cobol"), None);
+    assert_eq!(Filetype::from_modeline("# vim: ts=4"), None);
+}
+
+#[test]
+fn test_filetype_detection_priority() {
+    let script = Some(Path::new("build"));
+    assert_eq!(
+        Filetype::detect(script, &["#!/bin/bash", "echo"], &[]),
+        Some(Filetype::Shell)
+    );
+    // the filename takes precedence over the shebang
+    assert_eq!(
+        Filetype::detect(Some(Path::new("build.py")), &["#!/bin/bash"], &[]),
+        Some(Filetype::Python)
+    );
+    // and a modeline, even at the end of the file, over both
+    assert_eq!(
+        Filetype::detect(
+            Some(Path::new("build.py")),
+            &["#!/bin/bash"],
+            &["# vim: ft=make"]
+        ),
+        Some(Filetype::Make)
+    );
+    assert_eq!(Filetype::detect(None, &["text"], &["text"]), None);
+}
+
+#[test]
+fn test_filetype_names_and_defaults() {
+    assert_eq!("sh".parse::<Filetype>(), Ok(Filetype::Shell));
+    assert_eq!(Filetype::Shell.to_string(), "sh");
+    assert_eq!(
+        "cobol".parse::<Filetype>(),
+        Err("Invalid filetype 'cobol'".to_string())
+    );
+    assert_eq!(Filetype::Rust.syntax(), Some(Syntax::Rust));
+    assert_eq!(Filetype::Text.syntax(), None);
+    assert_eq!(Filetype::Make.indentation(), Some(Indentation::Tabs));
+    assert_eq!(Filetype::Yaml.indentation(), Some(Indentation::Spaces(2)));
+    assert_eq!(Filetype::Markdown.indentation(), None);
+    assert_eq!(Filetype::Rust.comment_syntax(), Some(CommentSyntax::C));
+    assert_eq!(Filetype::Python.comment_syntax(), Some(CommentSyntax::HASH));
+    assert_eq!(
+        Filetype::Markdown.comment_syntax().unwrap().block,
+        Some(("<!--", "-->"))
+    );
+    assert_eq!(Filetype::Json.comment_syntax(), None);
+}
@@ -0,0 +1,367 @@
+/// This module implements the frame buffer the editor draws the screen into.
+///
+/// A frame is a grid of cells, each holding a grapheme and its style. Instead
+/// of printing the whole screen on every refresh, the new frame is compared
+/// to the previous one, and only the cells that changed are written to the
+/// console.
+use crate::display_width::grapheme_width;
+use crate::Console;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+const ESCAPE: char = '\x1b';
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    #[default]
+    Default,
+    /// One of the 256 colors of the terminal palette
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// Names of the 8 basic colors of the terminal palette, by index
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Color::Default => write!(f, "default"),
+            Color::Ansi(value) => write!(f, "{value}"),
+            Color::Rgb(r, g, b) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    /// Parse a color from its name (ex: ``red``), its index in the terminal
+    /// palette (ex: ``244``) or its hexadecimal RGB value (ex: ``#ff8700``)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid color '{s}'");
+        if s == "default" {
+            return Ok(Color::Default);
+        }
+        if let Some(index) = COLOR_NAMES.iter().position(|name| *name == s) {
+            return Ok(Color::Ansi(u8::try_from(index).unwrap_or_default()));
+        }
+        if let Some(hex) = s.strip_prefix('#') {
+            let value = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+            if hex.len() != 6 {
+                return Err(invalid());
+            }
+            let [_, r, g, b] = value.to_be_bytes();
+            return Ok(Color::Rgb(r, g, b));
+        }
+        s.parse::<u8>().map(Color::Ansi).map_err(|_| invalid())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    /// Apply the parameters of an SGR (Select Graphic Rendition) escape
+    /// sequence, such as the ones used by ``utils::red`` (ex: ``38;5;1``)
+    fn apply_sgr(&mut self, parameters: &str, base: Self) {
+        let mut parameters = parameters
+            .split(';')
+            .map(|parameter| parameter.parse::<u8>().unwrap_or_default());
+        while let Some(parameter) = parameters.next() {
+            match parameter {
+                0 => *self = base,
+                1 => self.bold = true,
+                22 => self.bold = base.bold,
+                4 => self.underline = true,
+                24 => self.underline = base.underline,
+                38 | 48 => {
+                    let color = match parameters.next() {
+                        Some(5) => Color::Ansi(parameters.next().unwrap_or_default()),
+                        Some(2) => Color::Rgb(
+                            parameters.next().unwrap_or_default(),
+                            parameters.next().unwrap_or_default(),
+                            parameters.next().unwrap_or_default(),
+                        ),
+                        _ => continue,
+                    };
+                    if parameter == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                }
+                30..=37 => self.fg = Color::Ansi(parameter - 30),
+                39 => self.fg = base.fg,
+                40..=47 => self.bg = Color::Ansi(parameter - 40),
+                49 => self.bg = base.bg,
+                _ => (),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub grapheme: String,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            grapheme: String::from(" "),
+            style: Style::default(),
+        }
+    }
+}
+
+/// A run of consecutive cells of a line that changed since the previous frame
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change<'a> {
+    pub x: u16,
+    pub y: u16,
+    pub cells: &'a [Cell],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: u16,
+    pub height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    #[must_use]
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); usize::from(width) * usize::from(height)],
+        }
+    }
+
+    #[must_use]
+    pub fn cell(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get(self.index(x, y))
+    }
+
+    /// Return the text of a line, trailing spaces excluded
+    #[must_use]
+    pub fn line(&self, y: u16) -> String {
+        let start = self.index(0, y);
+        let line: String = self.cells[start..start + usize::from(self.width)]
+            .iter()
+            .map(|cell| cell.grapheme.as_str())
+            .collect();
+        String::from(line.trim_end())
+    }
+
+    /// Write the text on the line, starting at the provided column, and return
+    /// the column following it. Anything past the frame width is cut.
+    ///
+    /// A wide grapheme is stored in its first cell, followed by a continuation
+    /// cell with an empty grapheme, and a zero-width one is appended to the
+    /// grapheme of the previous cell.
+    pub fn put_str(&mut self, x: u16, y: u16, text: &str, style: Style) -> u16 {
+        let mut x = x;
+        if y >= self.height {
+            return x;
+        }
+        for grapheme in text.graphemes(true) {
+            if x >= self.width {
+                break;
+            }
+            match grapheme_width(grapheme) {
+                0 => {
+                    if x > 0 {
+                        let index = self.index(x - 1, y);
+                        self.cells[index].grapheme.push_str(grapheme);
+                    }
+                }
+                1 => {
+                    self.put_cell(x, y, grapheme, style);
+                    x += 1;
+                }
+                _ if x + 1 >= self.width => {
+                    // not enough room left for a wide grapheme
+                    self.put_cell(x, y, " ", style);
+                    x += 1;
+                }
+                _ => {
+                    self.put_cell(x, y, grapheme, style);
+                    self.put_cell(x + 1, y, "", style);
+                    x += 2;
+                }
+            }
+        }
+        x
+    }
+
+    /// Replace the cell content, blanking the other half of any wide grapheme
+    /// it overwrites
+    fn put_cell(&mut self, x: u16, y: u16, grapheme: &str, style: Style) {
+        let index = self.index(x, y);
+        if self.cells[index].grapheme.is_empty() && x > 0 {
+            self.cells[index - 1].grapheme = String::from(" ");
+        }
+        if x + 1 < self.width && self.cells[index + 1].grapheme.is_empty() {
+            self.cells[index + 1].grapheme = String::from(" ");
+        }
+        self.cells[index] = Cell {
+            grapheme: String::from(grapheme),
+            style,
+        };
+    }
+
+    /// Write text containing SGR escape sequences (ex: colored with ``utils::red``),
+    /// turning them into cell styles applied on top of the provided one.
+    pub fn put_styled_str(&mut self, x: u16, y: u16, text: &str, base: Style) -> u16 {
+        let mut x = x;
+        let mut style = base;
+        let mut rest = text;
+        while let Some(escape_index) = rest.find(ESCAPE) {
+            x = self.put_str(x, y, &rest[..escape_index], style);
+            rest = &rest[escape_index + ESCAPE.len_utf8()..];
+            // an escape sequence is terminated by its first alphabetic character
+            let end = rest
+                .find(|c: char| c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            if let Some(parameters) = rest[..end].strip_prefix('[') {
+                if rest[end..].starts_with('m') {
+                    style.apply_sgr(parameters, base);
+                }
+            }
+            rest = rest.get(end + 1..).unwrap_or_default();
+        }
+        self.put_str(x, y, rest, style)
+    }
+
+    /// Set the style of every cell of the line
+    pub fn fill_line(&mut self, y: u16, style: Style) {
+        if y >= self.height {
+            return;
+        }
+        let start = self.index(0, y);
+        for cell in &mut self.cells[start..start + usize::from(self.width)] {
+            cell.style = style;
+        }
+    }
+
+    /// Change the style of the cells of the line located within the provided columns
+    pub fn set_style(&mut self, y: u16, columns: Range<u16>, style: Style) {
+        if y >= self.height {
+            return;
+        }
+        for x in columns.start..columns.end.min(self.width) {
+            let index = self.index(x, y);
+            self.cells[index].style = style;
+        }
+    }
+
+    /// Underline the cells of the line located within the provided columns,
+    /// keeping their style otherwise
+    pub fn underline(&mut self, y: u16, columns: Range<u16>) {
+        if y >= self.height {
+            return;
+        }
+        for x in columns.start..columns.end.min(self.width) {
+            let index = self.index(x, y);
+            self.cells[index].style.underline = true;
+        }
+    }
+
+    /// Scroll the lines located between top and bottom (both included) by the
+    /// provided number of lines, upwards if positive, and downwards otherwise.
+    /// The lines uncovered by the scroll are left blank, like a terminal does.
+    pub fn scroll(&mut self, top: u16, bottom: u16, lines: i32) {
+        let width = usize::from(self.width);
+        let start = self.index(0, top);
+        let end = self.index(0, bottom.min(self.height - 1) + 1);
+        let region = &mut self.cells[start..end];
+        let shift = (lines.unsigned_abs() as usize * width).min(region.len());
+        if lines > 0 {
+            region.rotate_left(shift);
+            let len = region.len();
+            region[len - shift..].fill(Cell::default());
+        } else {
+            region.rotate_right(shift);
+            region[..shift].fill(Cell::default());
+        }
+    }
+
+    /// Return the runs of cells that differ from the previous frame
+    #[must_use]
+    pub fn changes<'a>(&'a self, previous: &Self) -> Vec<Change<'a>> {
+        let mut changes = Vec::new();
+        for y in 0..self.height {
+            let mut x = 0;
+            while x < self.width {
+                if self.cell(x, y) == previous.cell(x, y) {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < self.width && self.cell(x, y) != previous.cell(x, y) {
+                    x += 1;
+                }
+                let index = self.index(start, y);
+                changes.push(Change {
+                    x: start,
+                    y,
+                    cells: &self.cells[index..index + usize::from(x - start)],
+                });
+            }
+        }
+        changes
+    }
+
+    /// Draw the frame on the console. If a previous frame of the same size was
+    /// drawn, only the cells that changed since then are written.
+    pub fn draw(&self, previous: Option<&Self>, console: &dyn Console) {
+        let blank_frame;
+        let previous = match previous {
+            Some(previous) if previous.width == self.width && previous.height == self.height => {
+                previous
+            }
+            _ => {
+                console.clear_all();
+                blank_frame = Self::new(self.width, self.height);
+                &blank_frame
+            }
+        };
+        let mut current_style = None;
+        for change in self.changes(previous) {
+            console.move_cursor_to(change.x, change.y);
+            for cell in change.cells {
+                if current_style != Some(cell.style) {
+                    console.set_style(cell.style);
+                    current_style = Some(cell.style);
+                }
+                console.write(&cell.grapheme);
+            }
+        }
+        console.set_style(Style::default());
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        usize::from(y) * usize::from(self.width) + usize::from(x)
+    }
+}
+
+#[cfg(test)]
+#[path = "./frame_test.rs"]
+mod frame_test;
@@ -0,0 +1,158 @@
+use crate::frame::{Color, Frame, Style};
+use crate::utils;
+
+#[test]
+fn test_frame_put_str_is_cut_at_frame_width() {
+    let mut frame = Frame::new(5, 2);
+    assert_eq!(frame.put_str(2, 1, "Hellö", Style::default()), 5);
+    assert_eq!(frame.line(0), "");
+    assert_eq!(frame.line(1), "  Hel");
+}
+
+#[test]
+fn test_frame_put_styled_str() {
+    let mut frame = Frame::new(20, 1);
+    let text = format!("a {} c", utils::red(&utils::as_bold("b")));
+    frame.put_styled_str(0, 0, &text, Style::default());
+    assert_eq!(frame.line(0), "a b c");
+    assert_eq!(frame.cell(0, 0).unwrap().style, Style::default());
+    assert_eq!(
+        frame.cell(2, 0).unwrap().style,
+        Style {
+            fg: Color::Ansi(1),
+            bg: Color::Default,
+            bold: true,
+            underline: false,
+        }
+    );
+    assert_eq!(frame.cell(4, 0).unwrap().style, Style::default());
+}
+
+#[test]
+fn test_frame_changes() {
+    let mut previous = Frame::new(10, 3);
+    previous.put_str(0, 0, "Hello", Style::default());
+    previous.put_str(0, 1, "world", Style::default());
+    let mut frame = previous.clone();
+    assert!(frame.changes(&previous).is_empty());
+
+    frame.put_str(1, 0, "ah", Style::default());
+    frame.put_str(3, 2, "!", Style::default());
+    let changes = frame.changes(&previous);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(
+        (changes[0].x, changes[0].y, changes[0].cells.len()),
+        (1, 0, 2)
+    );
+    assert_eq!(changes[0].cells[1].grapheme, "h");
+    assert_eq!(
+        (changes[1].x, changes[1].y, changes[1].cells.len()),
+        (3, 2, 1)
+    );
+}
+
+#[test]
+fn test_frame_style_change_is_a_change() {
+    let mut previous = Frame::new(10, 1);
+    previous.put_str(0, 0, "Hello", Style::default());
+    let mut frame = previous.clone();
+    frame.fill_line(
+        0,
+        Style {
+            bold: true,
+            ..Style::default()
+        },
+    );
+    let changes = frame.changes(&previous);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].cells.len(), 10);
+}
+
+#[test]
+fn test_frame_scroll() {
+    let mut frame = Frame::new(3, 4);
+    for (y, text) in (0..4).zip(["a", "b", "c", "d"]) {
+        frame.put_str(0, y, text, Style::default());
+    }
+    frame.scroll(0, 2, 1);
+    let lines: Vec<String> = (0..4).map(|y| frame.line(y)).collect();
+    assert_eq!(lines, vec!["b", "c", "", "d"]);
+
+    frame.scroll(0, 2, -2);
+    let lines: Vec<String> = (0..4).map(|y| frame.line(y)).collect();
+    assert_eq!(lines, vec!["", "", "b", "d"]);
+}
+
+#[test]
+fn test_frame_put_str_wide_and_zero_width_graphemes() {
+    let mut frame = Frame::new(6, 1);
+    assert_eq!(frame.put_str(0, 0, "a日e\u{301}", Style::default()), 4);
+    assert_eq!(frame.cell(1, 0).unwrap().grapheme, "日");
+    assert_eq!(frame.cell(2, 0).unwrap().grapheme, "");
+    assert_eq!(frame.cell(3, 0).unwrap().grapheme, "e\u{301}");
+    assert_eq!(frame.line(0), "a日e\u{301}");
+
+    // a wide grapheme that doesn't fit is replaced by a space
+    assert_eq!(frame.put_str(4, 0, "x語", Style::default()), 6);
+    assert_eq!(frame.line(0), "a日e\u{301}x");
+
+    // overwriting half of a wide grapheme blanks the other half
+    frame.put_str(2, 0, "b", Style::default());
+    assert_eq!(frame.line(0), "a be\u{301}x");
+}
+
+#[test]
+fn test_frame_set_style() {
+    let mut frame = Frame::new(6, 2);
+    frame.put_str(0, 0, "Hello", Style::default());
+    let style = Style {
+        fg: Color::Ansi(2),
+        bg: Color::Default,
+        bold: false,
+        underline: false,
+    };
+    frame.set_style(0, 1..3, style);
+    frame.set_style(1, 4..10, style);
+    assert_eq!(frame.cell(0, 0).unwrap().style, Style::default());
+    assert_eq!(frame.cell(1, 0).unwrap().style, style);
+    assert_eq!(frame.cell(2, 0).unwrap().style, style);
+    assert_eq!(frame.cell(3, 0).unwrap().style, Style::default());
+    assert_eq!(frame.cell(5, 1).unwrap().style, style);
+    assert_eq!(frame.line(0), "Hello");
+}
+
+#[test]
+fn test_frame_underline() {
+    let mut frame = Frame::new(6, 1);
+    let style = Style {
+        fg: Color::Ansi(2),
+        ..Style::default()
+    };
+    frame.set_style(0, 0..6, style);
+    frame.underline(0, 2..10);
+    assert!(!frame.cell(1, 0).unwrap().style.underline);
+    // the underlined cells keep their color
+    assert_eq!(
+        frame.cell(2, 0).unwrap().style,
+        Style {
+            underline: true,
+            ..style
+        }
+    );
+    assert!(frame.cell(5, 0).unwrap().style.underline);
+}
+
+#[test]
+fn test_parse_color() {
+    assert_eq!("default".parse::<Color>(), Ok(Color::Default));
+    assert_eq!("red".parse::<Color>(), Ok(Color::Ansi(1)));
+    assert_eq!("244".parse::<Color>(), Ok(Color::Ansi(244)));
+    assert_eq!("#ff8700".parse::<Color>(), Ok(Color::Rgb(255, 135, 0)));
+    assert_eq!(Color::Rgb(255, 135, 0).to_string(), "#ff8700");
+    for invalid in ["grey", "256", "#fff", "#gg8700"] {
+        assert_eq!(
+            invalid.parse::<Color>(),
+            Err(format!("Invalid color '{invalid}'"))
+        );
+    }
+}
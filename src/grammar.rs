@@ -0,0 +1,343 @@
+/// This module defines the grammars of the programming and configuration
+/// languages highlighted by bo. Each grammar is a declarative set of rules,
+/// listing the comment and string delimiters, keywords and constants of the
+/// language, that a single tokenizer interprets.
+use crate::filetype::{CommentSyntax, Filetype};
+use crate::highlighting::{HighlightGroup, LineState, Span};
+
+/// Delimiters of a kind of string literal
+#[derive(Debug)]
+pub struct StringRule {
+    pub open: &'static str,
+    pub close: &'static str,
+    /// Whether a backslash escapes the following character
+    pub escapes: bool,
+    /// Whether the string can span several lines
+    pub multiline: bool,
+}
+
+impl StringRule {
+    const fn new(open: &'static str, close: &'static str, escapes: bool, multiline: bool) -> Self {
+        Self {
+            open,
+            close,
+            escapes,
+            multiline,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Grammar {
+    /// Filetype highlighted with the grammar, which describes its comment syntax
+    pub filetype: Filetype,
+    /// Whether a line comment must start the line or follow a whitespace (ex: ``a#b`` isn't a comment in shell)
+    pub comment_after_whitespace: bool,
+    /// Whether block comments can be nested
+    pub nested_comments: bool,
+    /// String rules, tried in order, so that the longest delimiters must come first
+    pub strings: &'static [StringRule],
+    /// Whether strings can directly follow a word (ex: ``b"bytes"`` in Rust, but not ``don't`` in YAML)
+    pub strings_after_words: bool,
+    /// Whether single quotes delimit character literals (ex: ``'a'``), but not lifetimes (ex: ``'a``)
+    pub char_literals: bool,
+    /// Keywords, sorted alphabetically
+    pub keywords: &'static [&'static str],
+    /// Constants, sorted alphabetically
+    pub constants: &'static [&'static str],
+}
+
+pub const RUST: Grammar = Grammar {
+    filetype: Filetype::Rust,
+    comment_after_whitespace: false,
+    nested_comments: true,
+    strings: &[
+        StringRule::new("r##\"", "\"##", false, true),
+        StringRule::new("r#\"", "\"#", false, true),
+        StringRule::new("r\"", "\"", false, true),
+        StringRule::new("\"", "\"", true, true),
+    ],
+    strings_after_words: true,
+    char_literals: true,
+    keywords: &[
+        "Self", "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else",
+        "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+        "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+    constants: &["None", "Some"],
+};
+
+pub const TOML: Grammar = Grammar {
+    filetype: Filetype::Toml,
+    comment_after_whitespace: false,
+    nested_comments: false,
+    strings: &[
+        StringRule::new("\"\"\"", "\"\"\"", true, true),
+        StringRule::new("'''", "'''", false, true),
+        StringRule::new("\"", "\"", true, false),
+        StringRule::new("'", "'", false, false),
+    ],
+    strings_after_words: false,
+    char_literals: false,
+    keywords: &[],
+    constants: &["false", "inf", "nan", "true"],
+};
+
+pub const YAML: Grammar = Grammar {
+    filetype: Filetype::Yaml,
+    comment_after_whitespace: true,
+    nested_comments: false,
+    strings: &[
+        StringRule::new("\"", "\"", true, true),
+        StringRule::new("'", "'", false, true),
+    ],
+    strings_after_words: false,
+    char_literals: false,
+    keywords: &[],
+    constants: &[
+        "False", "NULL", "Null", "TRUE", "True", "false", "no", "null", "true", "yes",
+    ],
+};
+
+pub const SHELL: Grammar = Grammar {
+    filetype: Filetype::Shell,
+    comment_after_whitespace: true,
+    nested_comments: false,
+    strings: &[
+        StringRule::new("\"", "\"", true, true),
+        StringRule::new("'", "'", false, true),
+    ],
+    strings_after_words: true,
+    char_literals: false,
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "select", "then", "until", "while",
+    ],
+    constants: &["false", "true"],
+};
+
+pub const MAKE: Grammar = Grammar {
+    filetype: Filetype::Make,
+    comment_after_whitespace: false,
+    nested_comments: false,
+    strings: &[],
+    strings_after_words: false,
+    char_literals: false,
+    keywords: &[
+        "define", "else", "endef", "endif", "export", "ifdef", "ifeq", "ifndef", "ifneq",
+        "include", "override", "unexport",
+    ],
+    constants: &[],
+};
+
+pub const JSON: Grammar = Grammar {
+    filetype: Filetype::Json,
+    comment_after_whitespace: false,
+    nested_comments: false,
+    strings: &[StringRule::new("\"", "\"", true, false)],
+    strings_after_words: false,
+    char_literals: false,
+    keywords: &[],
+    constants: &["false", "null", "true"],
+};
+
+/// Return whether the byte is part of an identifier, considering that
+/// non-ASCII characters are
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || !byte.is_ascii()
+}
+
+impl Grammar {
+    /// Return the comment syntax of the filetype highlighted with the grammar
+    #[must_use]
+    pub fn comments(&self) -> CommentSyntax {
+        self.filetype
+            .comment_syntax()
+            .unwrap_or(CommentSyntax::NONE)
+    }
+
+    /// Highlight a line, starting from the state at the end of the previous
+    /// line, and return the highlighted spans along with the state at the end
+    /// of the line
+    #[must_use]
+    pub fn highlight_line(&self, line: &str, state: LineState) -> (Vec<Span>, LineState) {
+        let bytes = line.as_bytes();
+        let comments = self.comments();
+        let mut spans = vec![];
+        let mut state = state;
+        let mut i = 0;
+        // continue the string or comment left open by the previous line
+        match state {
+            LineState::String { rule } => {
+                let (end, closed) = string_end(bytes, 0, &self.strings[rule]);
+                spans.push(Span::new(0..end, HighlightGroup::String));
+                if closed {
+                    state = LineState::Normal;
+                }
+                i = end;
+            }
+            LineState::Comment { depth } => {
+                let (end, depth) = self.block_comment_end(bytes, 0, depth);
+                spans.push(Span::new(0..end, HighlightGroup::Comment));
+                state = if depth == 0 {
+                    LineState::Normal
+                } else {
+                    LineState::Comment { depth }
+                };
+                i = end;
+            }
+            _ => (),
+        }
+        while i < bytes.len() && state == LineState::Normal {
+            let rest = &line[i..];
+            let preceded_by_whitespace = i == 0 || bytes[i - 1].is_ascii_whitespace();
+            if let Some(marker) = comments.line {
+                if rest.starts_with(marker)
+                    && (preceded_by_whitespace || !self.comment_after_whitespace)
+                {
+                    spans.push(Span::new(i..bytes.len(), HighlightGroup::Comment));
+                    break;
+                }
+            }
+            if let Some((open, _)) = comments.block {
+                if rest.starts_with(open) {
+                    let (end, depth) = self.block_comment_end(bytes, i + open.len(), 1);
+                    spans.push(Span::new(i..end, HighlightGroup::Comment));
+                    if depth > 0 {
+                        state = LineState::Comment { depth };
+                    }
+                    i = end;
+                    continue;
+                }
+            }
+            if let Some((index, rule)) = self
+                .strings
+                .iter()
+                .enumerate()
+                .find(|(_, rule)| rest.starts_with(rule.open))
+                .filter(|_| self.strings_after_words || i == 0 || !is_word_byte(bytes[i - 1]))
+            {
+                let (end, closed) = string_end(bytes, i + rule.open.len(), rule);
+                spans.push(Span::new(i..end, HighlightGroup::String));
+                if !closed && rule.multiline {
+                    state = LineState::String { rule: index };
+                }
+                i = end;
+                continue;
+            }
+            if self.char_literals && bytes[i] == b'\'' {
+                if let Some(end) = char_literal_end(bytes, i) {
+                    spans.push(Span::new(i..end, HighlightGroup::String));
+                    i = end;
+                    continue;
+                }
+            }
+            let (span, end) = self.word(line, i);
+            spans.extend(span);
+            i = end;
+        }
+        (spans, state)
+    }
+
+    /// Return the keyword, constant or number starting at the provided offset,
+    /// along with the offset following it
+    fn word(&self, line: &str, start: usize) -> (Option<Span>, usize) {
+        let bytes = line.as_bytes();
+        let len = bytes[start..]
+            .iter()
+            .take_while(|byte| is_word_byte(**byte))
+            .count();
+        if len == 0 {
+            return (None, start + 1);
+        }
+        let word = &line[start..start + len];
+        let mut end = start + len;
+        let group = if bytes[start].is_ascii_digit() {
+            // decimal part and exponent (ex: 1.5e-3)
+            while end < bytes.len()
+                && (is_word_byte(bytes[end])
+                    || bytes[end] == b'.' && bytes.get(end + 1).is_some_and(u8::is_ascii_digit)
+                    || matches!(bytes[end], b'+' | b'-') && matches!(bytes[end - 1], b'e' | b'E'))
+            {
+                end += 1;
+            }
+            HighlightGroup::Number
+        } else if self.keywords.binary_search(&word).is_ok() {
+            HighlightGroup::Keyword
+        } else if self.constants.binary_search(&word).is_ok() {
+            HighlightGroup::Constant
+        } else {
+            return (None, end);
+        };
+        (Some(Span::new(start..end, group)), end)
+    }
+
+    /// Return the offset following the end of the block comment whose content
+    /// starts at the provided offset, along with the nesting depth of the
+    /// comment at that offset, which is 0 once it's closed
+    fn block_comment_end(&self, bytes: &[u8], start: usize, depth: usize) -> (usize, usize) {
+        let Some((open, close)) = self.comments().block else {
+            return (start, 0);
+        };
+        let (open, close) = (open.as_bytes(), close.as_bytes());
+        let mut depth = depth;
+        let mut i = start;
+        while i < bytes.len() {
+            if bytes[i..].starts_with(close) {
+                i += close.len();
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            } else if self.nested_comments && bytes[i..].starts_with(open) {
+                i += open.len();
+                depth += 1;
+            } else {
+                i += 1;
+            }
+        }
+        (i, depth)
+    }
+}
+
+/// Return the offset following the end of the string whose content starts
+/// at the provided offset, along with whether the string was closed in the line
+fn string_end(bytes: &[u8], start: usize, rule: &StringRule) -> (usize, bool) {
+    let close = rule.close.as_bytes();
+    let mut i = start;
+    while i < bytes.len() {
+        if rule.escapes && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i..].starts_with(close) {
+            return (i + close.len(), true);
+        } else {
+            i += 1;
+        }
+    }
+    (bytes.len(), false)
+}
+
+/// Return the offset following the character literal (ex: ``'a'``, ``'\n'``)
+/// starting at the provided offset, or None if the quote starts a lifetime
+fn char_literal_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let content = bytes.get(start + 1..)?;
+    let len = if content.first() == Some(&b'\\') {
+        content.iter().skip(2).position(|byte| *byte == b'\'')? + 2
+    } else {
+        // a single character, possibly encoded on several bytes
+        let first = *content.first()?;
+        match first {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        }
+    };
+    (content.get(len) == Some(&b'\'')).then_some(start + len + 2)
+}
+
+#[cfg(test)]
+#[path = "./grammar_test.rs"]
+mod grammar_test;
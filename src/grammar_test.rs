@@ -0,0 +1,154 @@
+use crate::filetype::{CommentSyntax, Filetype};
+use crate::grammar::{Grammar, JSON, MAKE, RUST, SHELL, TOML, YAML};
+use crate::highlighting::{HighlightGroup, LineState, Syntax};
+
+fn spans<'a>(
+    grammar: &Grammar,
+    line: &'a str,
+    state: LineState,
+) -> (Vec<(&'a str, HighlightGroup)>, LineState) {
+    let (spans, state) = grammar.highlight_line(line, state);
+    let spans = spans
+        .iter()
+        .map(|span| (&line[span.range.clone()], span.group))
+        .collect();
+    (spans, state)
+}
+
+#[test]
+fn test_keywords_and_constants_are_sorted() {
+    // they are looked up with a binary search
+    for grammar in [&RUST, &TOML, &YAML, &SHELL, &MAKE, &JSON] {
+        assert!(grammar.keywords.windows(2).all(|words| words[0] < words[1]));
+        assert!(grammar
+            .constants
+            .windows(2)
+            .all(|words| words[0] < words[1]));
+    }
+}
+
+#[test]
+fn test_grammars_take_their_comments_from_their_filetype() {
+    // each grammar highlights the syntax of its own filetype
+    for grammar in [&RUST, &TOML, &YAML, &SHELL, &MAKE, &JSON] {
+        assert_eq!(
+            grammar
+                .filetype
+                .syntax()
+                .and_then(Syntax::grammar)
+                .map(|grammar| grammar.filetype),
+            Some(grammar.filetype)
+        );
+    }
+    assert_eq!(RUST.comments(), CommentSyntax::C);
+    assert_eq!(SHELL.comments(), CommentSyntax::HASH);
+    // JSON has no comments
+    assert_eq!(Filetype::Json.comment_syntax(), None);
+    assert_eq!(JSON.comments(), CommentSyntax::NONE);
+}
+
+#[test]
+fn test_highlight_rust() {
+    assert_eq!(
+        spans(
+            &RUST,
+            "pub fn main() -> u8 { let c = '\\n'; x::<'a>(\"a \\\" b\", 1.5e-3) } // done",
+            LineState::Normal
+        ),
+        (
+            vec![
+                ("pub", HighlightGroup::Keyword),
+                ("fn", HighlightGroup::Keyword),
+                ("let", HighlightGroup::Keyword),
+                ("'\\n'", HighlightGroup::String),
+                ("\"a \\\" b\"", HighlightGroup::String),
+                ("1.5e-3", HighlightGroup::Number),
+                ("// done", HighlightGroup::Comment),
+            ],
+            LineState::Normal
+        )
+    );
+    // identifiers containing keywords or digits aren't highlighted
+    assert_eq!(
+        spans(&RUST, "letter x1 r#\"raw\"#", LineState::Normal).0,
+        vec![("r#\"raw\"#", HighlightGroup::String)]
+    );
+}
+
+#[test]
+fn test_highlight_multiline_rust_comments_and_strings() {
+    let (highlighted, state) = spans(&RUST, "a /* b /* c */", LineState::Normal);
+    assert_eq!(highlighted, vec![("/* b /* c */", HighlightGroup::Comment)]);
+    assert_eq!(state, LineState::Comment { depth: 1 });
+    let (highlighted, state) = spans(&RUST, "d */ fn", state);
+    assert_eq!(
+        highlighted,
+        vec![
+            ("d */", HighlightGroup::Comment),
+            ("fn", HighlightGroup::Keyword)
+        ]
+    );
+    assert_eq!(state, LineState::Normal);
+
+    let (highlighted, state) = spans(&RUST, "let s = \"multi", LineState::Normal);
+    assert_eq!(highlighted[1], ("\"multi", HighlightGroup::String));
+    let (highlighted, state) = spans(&RUST, "line\"; 2", state);
+    assert_eq!(
+        highlighted,
+        vec![
+            ("line\"", HighlightGroup::String),
+            ("2", HighlightGroup::Number)
+        ]
+    );
+    assert_eq!(state, LineState::Normal);
+}
+
+#[test]
+fn test_highlight_configuration_languages() {
+    assert_eq!(
+        spans(&TOML, "version = \"0.4\" # comment", LineState::Normal).0,
+        vec![
+            ("\"0.4\"", HighlightGroup::String),
+            ("# comment", HighlightGroup::Comment)
+        ]
+    );
+    let (_, state) = spans(&TOML, "text = '''", LineState::Normal);
+    assert_eq!(state, LineState::String { rule: 1 });
+    assert_eq!(
+        spans(&YAML, "key: don't # it's true", LineState::Normal).0,
+        vec![("# it's true", HighlightGroup::Comment)]
+    );
+    assert_eq!(
+        spans(&YAML, "url: a#b, enabled: true", LineState::Normal).0,
+        vec![("true", HighlightGroup::Constant)]
+    );
+    assert_eq!(
+        spans(&JSON, "{\"a\": [1, null, -2]}", LineState::Normal).0,
+        vec![
+            ("\"a\"", HighlightGroup::String),
+            ("1", HighlightGroup::Number),
+            ("null", HighlightGroup::Constant),
+            ("2", HighlightGroup::Number)
+        ]
+    );
+}
+
+#[test]
+fn test_highlight_shell() {
+    assert_eq!(
+        spans(
+            &SHELL,
+            "if [ $# -eq 0 ]; then echo 'no args'; fi # usage",
+            LineState::Normal
+        )
+        .0,
+        vec![
+            ("if", HighlightGroup::Keyword),
+            ("0", HighlightGroup::Number),
+            ("then", HighlightGroup::Keyword),
+            ("'no args'", HighlightGroup::String),
+            ("fi", HighlightGroup::Keyword),
+            ("# usage", HighlightGroup::Comment)
+        ]
+    );
+}
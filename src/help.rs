@@ -1,12 +1,169 @@
+use crate::commands::COMMANDS;
+use crate::keymap::{Keymap, MapMode};
 use crate::utils;
 use std::collections::HashMap;
 
+/// Entries of the normal commands section
+const NORMAL_COMMANDS: &[(&str, &str)] = &[
+    ("j", "move cursor down one row (<n>j moves it by n rows)"),
+    ("k", "move cursor up one row (<n>k moves it by n rows)"),
+    ("h", "move cursor left (<n>h moves it n times)"),
+    ("l", "move cursor right (<n>l moves it n times)"),
+    (
+        "}",
+        "move to the end of the current paragraph (<n>} moves n times)",
+    ),
+    (
+        "{",
+        "move to the start of the current paragraph (<n>{ moves n times)",
+    ),
+    (
+        "w",
+        "move to the start of the next word (<n>w moves n times)",
+    ),
+    (
+        "b",
+        "move to the start of the previous word (<n>b moves n times)",
+    ),
+    ("e", "move to the end of the next word (<n>e moves n times)"),
+    (
+        "ge",
+        "move to the end of the previous word (<n>ge moves n times)",
+    ),
+    (
+        "W/B/E/gE",
+        "same as w/b/e/ge, with words made of any non-blank characters",
+    ),
+    (
+        "f<c>/F<c>",
+        "move to the next/previous <c> in the line (<n>f<c> moves n times)",
+    ),
+    (
+        "t<c>/T<c>",
+        "move right before the next/previous <c> in the line",
+    ),
+    (
+        ";/,",
+        "repeat the last f, F, t or T, in the same/opposite direction",
+    ),
+    (
+        "df<c>/dt<c>",
+        "delete up to <c> (also with F, T, ; and , and the c and y operators)",
+    ),
+    ("i", "switch to insert mode"),
+    ("gg", "go to beginining of document"),
+    ("G", "go to end of document"),
+    ("0", "go to first character in line"),
+    ("^", "go to first non-whitespace character in line"),
+    ("$", "go to end of line"),
+    ("H", "go to first line in screen"),
+    ("M", "go to line in the middle of the screen"),
+    ("L", "go to last line in screen"),
+    (
+        "Ctrl-d/Ctrl-u",
+        "scroll down/up half a screen (<n>Ctrl-d scrolls n lines)",
+    ),
+    (
+        "Ctrl-f/Ctrl-b",
+        "scroll down/up a screen (<n>Ctrl-f scrolls n screens)",
+    ),
+    (
+        "Ctrl-e/Ctrl-y",
+        "scroll down/up a line, keeping the cursor in place",
+    ),
+    (
+        "zt/zz/zb",
+        "scroll the current line to the top/middle/bottom of the screen",
+    ),
+    ("n%", "move to n% in the file"),
+    ("/", "open search prompt"),
+    ("n", "go to next search match"),
+    ("N", "go to previous search match"),
+    ("dd", "delete current line"),
+    (
+        "(",
+        "move to the start of the current sentence (<n>( moves n times)",
+    ),
+    (
+        ")",
+        "move to the start of the next sentence (<n>) moves n times)",
+    ),
+    (
+        "dis/das",
+        "delete the sentence, excluding/including its surrounding spaces",
+    ),
+    (
+        "cis/cas",
+        "change the sentence, as dis/das then insert mode",
+    ),
+    ("yis/yas", "yank the sentence"),
+    ("x", "delete current character"),
+    ("o", "insert newline after current line & enter insert mode"),
+    (
+        "O",
+        "insert newline before current line & enter insert mode",
+    ),
+    ("A", "go to end of line & enter insert mode"),
+    ("J", "join the current line with the next one"),
+    (":", "open command prompt"),
+    ("u", "undo last operation"),
+    ("]s/[s", "go to the next/previous misspelled word"),
+    (
+        "z=",
+        "pick a spelling suggestion for the word under the cursor",
+    ),
+    (
+        "zg",
+        "add the word under the cursor to the personal dictionary",
+    ),
+    (
+        "]l/[l",
+        "go to the next/previous style finding of the linter",
+    ),
+];
+
 pub struct Section {
     pub title: String,
-    pub entries: HashMap<&'static str, &'static str>,
+    pub entries: HashMap<String, String>,
 }
 
 impl Section {
+    #[must_use]
+    pub fn new(title: &str, entries: &[(&str, &str)]) -> Self {
+        Self {
+            title: String::from(title),
+            entries: entries
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+                .collect(),
+        }
+    }
+
+    /// Build the normal commands section
+    fn normal_commands() -> Self {
+        Self::new("Normal commands", NORMAL_COMMANDS)
+    }
+
+    /// Build the prompt commands section out of the command registry
+    fn prompt_commands() -> Self {
+        let mut section = Self::new(
+            "Prompt commands",
+            &[
+                ("<n>", "go to line n"),
+                (
+                    "<range>",
+                    "n, ., $, %, 'a, /pattern/ or a,b, with optional +n/-n offsets",
+                ),
+            ],
+        );
+        for command in &COMMANDS {
+            section
+                .entries
+                .insert(command.synopsis(), command.description.to_string());
+        }
+        section
+    }
+
     /// Returns the size of the biggest entry key in the section
     fn max_entry_key_size(&self) -> usize {
         if let Some(key) = self.entries.keys().max_by_key(|k| k.len()) {
@@ -39,76 +196,40 @@ impl Help {
     pub fn new() -> Help {
         Help {
             sections: vec![
-                Section {
-                    title: String::from("Normal commands"),
-                    entries: HashMap::from([
-                        ("j", "move cursor down one row (<n>j moves it by n rows)"),
-                        ("k", "move cursor up one row (<n>k moves it by n rows)"),
-                        ("h", "move cursor left (<n>h moves it n times)"),
-                        ("l", "move cursor right (<n>l moves it n times)"),
-                        (
-                            "}",
-                            "move to the end of the current paragraph (<n>} moves n times)",
-                        ),
-                        (
-                            "{",
-                            "move to the start of the current paragraph (<n>{ moves n times)",
-                        ),
-                        (
-                            "w",
-                            "move to the end of the current word (<n>w moves n times)",
-                        ),
-                        (
-                            "b",
-                            "move to the start of the current word (<n>b moves n times)",
-                        ),
-                        ("i", "switch to insert mode"),
-                        ("g", "go to beginining of document"),
-                        ("G", "go to end of document"),
-                        ("0", "go to first character in line"),
-                        ("^", "go to first non-whitespace character in line"),
-                        ("$", "go to end of line"),
-                        ("H", "go to first line in screen"),
-                        ("M", "go to line in the middle of the screen"),
-                        ("L", "go to last line in screen"),
-                        ("n%", "move to n% in the file"),
-                        ("/", "open search prompt"),
-                        ("n", "go to next search match"),
-                        ("N", "go to previous search match"),
-                        ("d", "delete current line"),
-                        ("x", "delete current character"),
-                        ("o", "insert newline after current line & enter insert mode"),
-                        (
-                            "O",
-                            "insert newline before current line & enter insert mode",
-                        ),
-                        ("A", "go to end of line & enter insert mode"),
-                        ("J", "join the current line with the next one"),
-                        (":", "open command prompt"),
-                        ("u", "undo last operation"),
-                    ]),
-                },
-                Section {
-                    title: String::from("Prompt commands"),
-                    entries: HashMap::from([
-                        ("help", "display this help screen"),
-                        ("ln", "toggle line numbers"),
-                        ("new <filename>", "open a new file"),
-                        ("open/o <filename>", "open a file"),
-                        ("q", "quit bo"),
-                        ("stats", "toggle line/word stats"),
-                        ("w <new_name>", "save"),
-                        ("wq", "save and quit"),
-                    ]),
-                },
-                Section {
-                    title: String::from("Insert commands"),
-                    entries: HashMap::from([("Esc", "go back to normal mode")]),
-                },
+                Section::normal_commands(),
+                Section::prompt_commands(),
+                Section::new(
+                    "Prompt editing",
+                    &[
+                        ("Left/Right", "move the cursor in the prompt"),
+                        ("Home/End", "move to the start/end of the prompt"),
+                        ("Ctrl-w", "delete the word before the cursor"),
+                        ("Ctrl-u", "delete everything before the cursor"),
+                        ("Ctrl-r <x>", "insert the content of register x"),
+                        ("Ctrl-r Ctrl-w", "insert the word under the cursor"),
+                    ],
+                ),
+                Section::new("Insert commands", &[("Esc", "go back to normal mode")]),
             ],
         }
     }
 
+    /// Return the help, along with a section listing the user mappings, if any
+    #[must_use]
+    pub fn with_mappings(keymap: &Keymap) -> Help {
+        let mut help = Help::new();
+        if !keymap.is_empty() {
+            let mut section = Section::new("User mappings", &[]);
+            for mode in [MapMode::Normal, MapMode::Insert, MapMode::Prompt] {
+                for (lhs, rhs) in keymap.describe(mode) {
+                    section.entries.insert(format!("{mode} {lhs}"), rhs);
+                }
+            }
+            help.sections.push(section);
+        }
+        help
+    }
+
     #[must_use]
     pub fn format(&self) -> String {
         let mut out: Vec<String> = Vec::new();
@@ -0,0 +1,349 @@
+use crate::frame::{Color, Style};
+use crate::grammar::{self, Grammar};
+use crate::{markdown, Row};
+use std::collections::{BTreeSet, HashMap};
+use std::mem;
+use std::ops::Range;
+
+/// Kind of highlighted text, which determines the style it is displayed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightGroup {
+    Heading,
+    Emphasis,
+    Strong,
+    Code,
+    Link,
+    Quote,
+    ListMarker,
+    Keyword,
+    String,
+    Comment,
+    Number,
+    Constant,
+    Lint,
+}
+
+impl HighlightGroup {
+    pub const ALL: [Self; 13] = [
+        Self::Heading,
+        Self::Emphasis,
+        Self::Strong,
+        Self::Code,
+        Self::Link,
+        Self::Quote,
+        Self::ListMarker,
+        Self::Keyword,
+        Self::String,
+        Self::Comment,
+        Self::Number,
+        Self::Constant,
+        Self::Lint,
+    ];
+
+    /// Return the name of the group, used by ``:highlight``
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Heading => "Heading",
+            Self::Emphasis => "Emphasis",
+            Self::Strong => "Strong",
+            Self::Code => "Code",
+            Self::Link => "Link",
+            Self::Quote => "Quote",
+            Self::ListMarker => "ListMarker",
+            Self::Keyword => "Keyword",
+            Self::String => "String",
+            Self::Comment => "Comment",
+            Self::Number => "Number",
+            Self::Constant => "Constant",
+            Self::Lint => "Lint",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|group| group.name() == name)
+    }
+
+    /// Return the style the group is displayed with by the default theme
+    #[must_use]
+    pub fn default_style(self) -> Style {
+        let (fg, bold) = match self {
+            Self::Heading => (Color::Ansi(4), true),
+            Self::Emphasis | Self::Keyword => (Color::Ansi(5), false),
+            Self::Strong => (Color::Default, true),
+            Self::Code | Self::String => (Color::Ansi(2), false),
+            Self::Link | Self::Constant => (Color::Ansi(6), false),
+            Self::Quote | Self::Comment => (Color::Ansi(244), false),
+            Self::ListMarker | Self::Number => (Color::Ansi(3), false),
+            Self::Lint => (Color::Ansi(0), false),
+        };
+        // the style findings are displayed black on yellow, like a highlighter pen
+        let bg = if self == Self::Lint {
+            Color::Ansi(3)
+        } else {
+            Color::Default
+        };
+        Style {
+            fg,
+            bg,
+            bold,
+            underline: false,
+        }
+    }
+}
+
+/// Styles the highlight groups are displayed with, which can be changed with
+/// ``:highlight`` (ex: ``:highlight Keyword blue bold``)
+#[derive(Debug)]
+pub struct Theme {
+    styles: HashMap<HighlightGroup, Style>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            styles: HighlightGroup::ALL
+                .iter()
+                .copied()
+                .map(|group| (group, group.default_style()))
+                .collect(),
+        }
+    }
+}
+
+impl Theme {
+    #[must_use]
+    pub fn style(&self, group: HighlightGroup) -> Style {
+        self.styles
+            .get(&group)
+            .copied()
+            .unwrap_or_else(|| group.default_style())
+    }
+
+    /// Change the style of a group from a definition made of a group name,
+    /// followed by a foreground color and an optional ``bold`` or ``nobold``
+    /// attribute (ex: ``Comment #808080 nobold``)
+    pub fn set(&mut self, definition: &str) -> Result<(), String> {
+        let mut words = definition.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let group =
+            HighlightGroup::from_name(name).ok_or(format!("Unknown highlight group '{name}'"))?;
+        let mut style = self.style(group);
+        for word in words {
+            match word {
+                "bold" => style.bold = true,
+                "nobold" => style.bold = false,
+                color => style.fg = color.parse()?,
+            }
+        }
+        self.styles.insert(group, style);
+        Ok(())
+    }
+
+    /// Return the description of the style of a group (ex: ``Keyword=5,bold``)
+    #[must_use]
+    pub fn describe(&self, group: HighlightGroup) -> String {
+        let style = self.style(group);
+        let bold = if style.bold { ",bold" } else { "" };
+        format!("{}={}{bold}", group.name(), style.fg)
+    }
+}
+
+/// Highlighted part of a row, located by its byte offsets in the row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub group: HighlightGroup,
+}
+
+impl Span {
+    #[must_use]
+    pub fn new(range: Range<usize>, group: HighlightGroup) -> Self {
+        Self { range, group }
+    }
+}
+
+/// State of the tokenizer at the end of a row, which the highlighting of the
+/// next row starts from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineState {
+    #[default]
+    Normal,
+    /// Inside a fenced code block, opened by ``len`` backticks or tildes
+    Fence { marker: char, len: usize },
+    /// Inside a multiline string, delimited by the string rule of the grammar at that index
+    String { rule: usize },
+    /// Inside a block comment, nested ``depth`` times
+    Comment { depth: usize },
+}
+
+/// Language the rows of a document are highlighted as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Markdown,
+    Rust,
+    Toml,
+    Yaml,
+    Shell,
+    Make,
+    Json,
+}
+
+impl Syntax {
+    /// Return the grammar of the syntax, or None if it has a dedicated highlighter
+    #[must_use]
+    pub fn grammar(self) -> Option<&'static Grammar> {
+        match self {
+            Self::Markdown => None,
+            Self::Rust => Some(&grammar::RUST),
+            Self::Toml => Some(&grammar::TOML),
+            Self::Yaml => Some(&grammar::YAML),
+            Self::Shell => Some(&grammar::SHELL),
+            Self::Make => Some(&grammar::MAKE),
+            Self::Json => Some(&grammar::JSON),
+        }
+    }
+
+    fn highlight_line(self, line: &str, state: LineState) -> (Vec<Span>, LineState) {
+        match self.grammar() {
+            Some(grammar) => grammar.highlight_line(line, state),
+            None => markdown::highlight_line(line, state),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HighlightedRow {
+    spans: Vec<Span>,
+    end_state: LineState,
+}
+
+/// Cache of the highlighted rows of a document, from its first row onwards.
+///
+/// As a row can only be highlighted once the state at the end of the previous
+/// one is known, the rows are highlighted in order, and only up to the last
+/// displayed one. Modifying a row only invalidates that row: the rows following
+/// it are highlighted again as long as the state they start in differs from
+/// the one they were last highlighted in (ex: after opening a code fence).
+#[derive(Debug, Default)]
+pub struct Highlighter {
+    syntax: Option<Syntax>,
+    rows: Vec<HighlightedRow>,
+    /// Indices of the cached rows modified since they were highlighted
+    invalid_rows: BTreeSet<usize>,
+}
+
+impl Highlighter {
+    /// Change the syntax the rows are highlighted as, discarding the cache if it changed
+    pub fn set_syntax(&mut self, syntax: Option<Syntax>) {
+        if syntax != self.syntax {
+            self.syntax = syntax;
+            self.invalidate_from(0);
+        }
+    }
+
+    /// Discard the highlighting of the rows located from the provided index onwards
+    pub fn invalidate_from(&mut self, index: usize) {
+        self.rows.truncate(index);
+        self.invalid_rows.split_off(&index);
+    }
+
+    /// Mark the row located at the provided index as modified
+    pub fn invalidate(&mut self, index: usize) {
+        if index < self.rows.len() {
+            self.invalid_rows.insert(index);
+        }
+    }
+
+    /// Make room for a row inserted at the provided index, shifting the following
+    /// ones. Until it's highlighted, the row ends in the state it starts in, which
+    /// is the state the following row was highlighted in.
+    pub fn insert(&mut self, index: usize) {
+        if index > self.rows.len() {
+            return;
+        }
+        let end_state = self.start_state(index);
+        self.rows.insert(
+            index,
+            HighlightedRow {
+                spans: vec![],
+                end_state,
+            },
+        );
+        self.shift_invalid_rows(index, |i| i + 1);
+        self.invalid_rows.insert(index);
+    }
+
+    /// Discard the highlighting of a removed row, shifting the following ones.
+    /// The row following it now starts in the state the removed row started in,
+    /// so it's invalidated too.
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.rows.len() {
+            return;
+        }
+        self.rows.remove(index);
+        self.invalid_rows.remove(&index);
+        self.shift_invalid_rows(index + 1, |i| i - 1);
+        self.invalidate(index);
+    }
+
+    fn shift_invalid_rows(&mut self, from: usize, shift: impl Fn(usize) -> usize) {
+        let shifted = self.invalid_rows.split_off(&from);
+        self.invalid_rows.extend(shifted.into_iter().map(shift));
+    }
+
+    /// Return the index of the first row to highlight, from the provided index
+    /// onwards: the first modified row, or the first row that wasn't highlighted
+    #[must_use]
+    pub fn next_row_to_highlight(&self, from: usize) -> usize {
+        self.invalid_rows
+            .range(from..)
+            .next()
+            .copied()
+            .unwrap_or(self.num_highlighted_rows())
+    }
+
+    /// Return the number of rows highlighted so far, including the modified ones
+    #[must_use]
+    pub fn num_highlighted_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Return the state the row located at the provided index starts in
+    fn start_state(&self, index: usize) -> LineState {
+        index
+            .checked_sub(1)
+            .and_then(|previous| self.rows.get(previous))
+            .map_or(LineState::default(), |row| row.end_state)
+    }
+
+    /// Highlight the row located at the provided index, the previous one having
+    /// been highlighted. Return whether the following row needs to be highlighted
+    /// again, as it now starts in a different state.
+    pub fn highlight(&mut self, index: usize, row: &Row) -> bool {
+        let Some(syntax) = self.syntax else {
+            return false;
+        };
+        let (spans, end_state) = syntax.highlight_line(&row.string, self.start_state(index));
+        let highlighted = HighlightedRow { spans, end_state };
+        if index < self.rows.len() {
+            self.invalid_rows.remove(&index);
+            let previous = mem::replace(&mut self.rows[index], highlighted);
+            previous.end_state != end_state
+        } else {
+            self.rows.push(highlighted);
+            true
+        }
+    }
+
+    /// Return the highlighted spans of the row, which are empty if it wasn't highlighted
+    #[must_use]
+    pub fn spans(&self, index: usize) -> &[Span] {
+        self.rows.get(index).map_or(&[], |row| row.spans.as_slice())
+    }
+}
+
+#[cfg(test)]
+#[path = "./highlighting_test.rs"]
+mod highlighting_test;
@@ -0,0 +1,76 @@
+use crate::highlighting::{HighlightGroup, Highlighter, Span, Syntax};
+use crate::Row;
+
+#[test]
+fn test_highlighter_carries_state_over_rows() {
+    let mut highlighter = Highlighter::default();
+    highlighter.set_syntax(Some(Syntax::Markdown));
+    for (index, line) in ["```", "*code*", "```", "*text*"].iter().enumerate() {
+        highlighter.highlight(index, &Row::from(*line));
+    }
+    assert_eq!(highlighter.num_highlighted_rows(), 4);
+    assert_eq!(
+        highlighter.spans(1),
+        &[Span::new(0..6, HighlightGroup::Code)]
+    );
+    assert_eq!(
+        highlighter.spans(3),
+        &[Span::new(0..6, HighlightGroup::Emphasis)]
+    );
+    assert_eq!(highlighter.spans(4), &[]);
+}
+
+#[test]
+fn test_highlighter_invalidation() {
+    let mut highlighter = Highlighter::default();
+    highlighter.set_syntax(Some(Syntax::Markdown));
+    for (index, line) in ["a", "b", "c"].iter().enumerate() {
+        highlighter.highlight(index, &Row::from(*line));
+    }
+    assert_eq!(highlighter.next_row_to_highlight(0), 3);
+    highlighter.invalidate(1);
+    assert_eq!(highlighter.next_row_to_highlight(0), 1);
+    assert_eq!(highlighter.next_row_to_highlight(2), 3);
+    highlighter.invalidate_from(1);
+    assert_eq!(highlighter.num_highlighted_rows(), 1);
+    // setting the same syntax again keeps the cache, but changing it discards it
+    highlighter.set_syntax(Some(Syntax::Markdown));
+    assert_eq!(highlighter.num_highlighted_rows(), 1);
+    highlighter.set_syntax(None);
+    assert_eq!(highlighter.num_highlighted_rows(), 0);
+    assert!(!highlighter.highlight(0, &Row::from("*a*")));
+    assert_eq!(highlighter.num_highlighted_rows(), 0);
+}
+
+#[test]
+fn test_highlighter_only_highlights_changed_rows_again() {
+    let mut highlighter = Highlighter::default();
+    highlighter.set_syntax(Some(Syntax::Markdown));
+    for (index, line) in ["a", "b", "c", "d"].iter().enumerate() {
+        highlighter.highlight(index, &Row::from(*line));
+    }
+    // the state at the end of an edited row is unchanged, so the following
+    // rows are kept
+    highlighter.invalidate(1);
+    assert!(!highlighter.highlight(1, &Row::from("*b*")));
+    assert_eq!(highlighter.next_row_to_highlight(2), 4);
+    // opening a fence changes the state the following rows start in
+    highlighter.invalidate(1);
+    assert!(highlighter.highlight(1, &Row::from("```")));
+    assert_eq!(highlighter.next_row_to_highlight(2), 4);
+    highlighter.invalidate(2);
+    assert!(highlighter.highlight(2, &Row::from("*c*")));
+    assert_eq!(
+        highlighter.spans(2),
+        &[Span::new(0..3, HighlightGroup::Code)]
+    );
+    // an inserted row keeps the following rows until it's highlighted
+    highlighter.insert(1);
+    assert_eq!(highlighter.next_row_to_highlight(0), 1);
+    assert!(!highlighter.highlight(1, &Row::from("x")));
+    assert_eq!(highlighter.num_highlighted_rows(), 5);
+    // the row following a removed row is highlighted again
+    highlighter.remove(1);
+    assert_eq!(highlighter.num_highlighted_rows(), 4);
+    assert_eq!(highlighter.next_row_to_highlight(0), 1);
+}
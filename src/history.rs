@@ -1,4 +1,4 @@
-use crate::Position;
+use crate::{GraphemeIndex, Position};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 use unicode_segmentation::UnicodeSegmentation;
@@ -9,27 +9,39 @@ const HISTORY_SIZE: usize = 8;
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum OperationType {
     Insert,
+    /// Deletion of the text preceding the start position, as done by Backspace
     Delete,
+    /// Deletion of the text following the start position, as done by a text object
+    DeleteForward,
+    /// Insertion of whole lines, before the row of the start position
+    InsertLines,
+    /// Deletion of whole lines, from the row of the start position
+    DeleteLines,
 }
 
 impl OperationType {
     fn reversed(self) -> Self {
         match self {
             OperationType::Insert => OperationType::Delete,
-            OperationType::Delete => OperationType::Insert,
+            OperationType::Delete | OperationType::DeleteForward => OperationType::Insert,
+            OperationType::InsertLines => OperationType::DeleteLines,
+            OperationType::DeleteLines => OperationType::InsertLines,
         }
     }
 }
 /// An Operation describe a text edition at a specific start position.
 ///
-/// An Operation can be of 2 types: either Insert or Delete, testifying of the fact
-/// that we either inserted or deleted the provided text content, starting at a given
-/// x/y position.
+/// An Operation either inserts or deletes the provided text content, starting at a
+/// given x/y position, or whole lines, starting at a given row. Line operations are
+/// registered by Ex commands such as ``:m`` or ``:>``, and can be grouped, so that a
+/// single command is undone at once.
 #[derive(Debug, PartialEq)]
 pub struct Operation {
     pub content: String,
     pub start_position: Position,
     pub op_type: OperationType,
+    /// Whether the operation is undone along with the one preceding it
+    pub grouped: bool,
 }
 
 impl Operation {
@@ -49,7 +61,7 @@ impl Operation {
     /// Note: this one took a long time to get right but seems to work. Check unit tests in doubt!
     #[must_use]
     pub fn end_position(&self, document_rows_length: &[usize]) -> Position {
-        let mut x: usize = self.start_position.x;
+        let mut x: usize = self.start_position.x.value;
         let mut y: usize = self.start_position.y;
         match self.op_type {
             OperationType::Insert => {
@@ -62,7 +74,7 @@ impl Operation {
                     }
                 }
                 Position {
-                    x: x.saturating_sub(1),
+                    x: GraphemeIndex::new(x.saturating_sub(1)),
                     y,
                 }
             }
@@ -75,17 +87,34 @@ impl Operation {
                         x = x.saturating_sub(1);
                     }
                 }
-                Position { x, y }
+                Position {
+                    x: GraphemeIndex::new(x),
+                    y,
+                }
             }
+            OperationType::DeleteForward => self.start_position,
+            OperationType::InsertLines | OperationType::DeleteLines => Position {
+                x: GraphemeIndex::new(0),
+                y,
+            },
         }
     }
 
     #[must_use]
     pub fn reversed(&self, document_rows_length: &[usize]) -> Self {
         Self {
-            content: self.content.graphemes(true).rev().collect(),
+            // lines and text deleted forward are stored in order, and text backwards otherwise
+            content: match self.op_type {
+                OperationType::DeleteForward
+                | OperationType::InsertLines
+                | OperationType::DeleteLines => self.content.clone(),
+                OperationType::Insert | OperationType::Delete => {
+                    self.content.graphemes(true).rev().collect()
+                }
+            },
             op_type: self.op_type.reversed(),
             start_position: self.end_position(document_rows_length),
+            grouped: self.grouped,
         }
     }
 }
@@ -124,6 +153,7 @@ impl History {
             content: text.to_string(),
             start_position: position,
             op_type: operation_type,
+            grouped: false,
         });
         self.set_last_edit_time_to_now();
     }
@@ -158,7 +188,12 @@ impl History {
                     op.mut_push(text);
                     self.set_last_edit_time_to_now();
                 }
-                OperationType::Delete => self.push_insert(text, position),
+                OperationType::Delete
+                | OperationType::DeleteForward
+                | OperationType::InsertLines
+                | OperationType::DeleteLines => {
+                    self.push_insert(text, position);
+                }
             }
         }
     }
@@ -178,7 +213,12 @@ impl History {
             self.push_delete(text, position);
         } else if let Some(op) = self.operations.back_mut() {
             match op.op_type {
-                OperationType::Insert => self.push_delete(text, position),
+                OperationType::Insert
+                | OperationType::DeleteForward
+                | OperationType::InsertLines
+                | OperationType::DeleteLines => {
+                    self.push_delete(text, position);
+                }
                 OperationType::Delete => {
                     op.mut_push(text);
                     self.set_last_edit_time_to_now();
@@ -187,6 +227,50 @@ impl History {
         }
     }
 
+    /// Register that the provided text, following the provided position, was
+    /// deleted at once. This is always registered as a whole new ``Operation``.
+    pub fn register_forward_deletion(&mut self, text: &str, position: Position) {
+        self.push(text, position, OperationType::DeleteForward);
+    }
+
+    /// Register that the provided lines, joined by newlines, were inserted
+    /// before the provided row. This is always registered as a whole new ``Operation``.
+    pub fn register_lines_insertion(&mut self, lines: &str, row_index: usize) {
+        self.push(
+            lines,
+            Position {
+                x: GraphemeIndex::new(0),
+                y: row_index,
+            },
+            OperationType::InsertLines,
+        );
+    }
+
+    /// Register that the provided lines, joined by newlines, were deleted
+    /// from the provided row. This is always registered as a whole new ``Operation``.
+    pub fn register_lines_deletion(&mut self, lines: &str, row_index: usize) {
+        self.push(
+            lines,
+            Position {
+                x: GraphemeIndex::new(0),
+                y: row_index,
+            },
+            OperationType::DeleteLines,
+        );
+    }
+
+    /// Group the last ``count`` operations, so that they are undone at once
+    pub fn group_last_operations(&mut self, count: usize) {
+        for op in self
+            .operations
+            .iter_mut()
+            .rev()
+            .take(count.saturating_sub(1))
+        {
+            op.grouped = true;
+        }
+    }
+
     /// If any Operation is in the history, pop it and returm its reversed Operation.
     #[must_use]
     pub fn last_operation_reversed(&mut self, document_rows_length: &[usize]) -> Option<Operation> {
@@ -4,8 +4,12 @@
 /// rows, starting at 0. We define simple types in order to delegate the
 /// +1/-1 conversions to them, as it has proven easy to forget these only
 /// when dealing with usize values.
+/// Likewise, a position in a row can be a byte offset in its string, the
+/// index of a grapheme, or a display column on screen. Each has its own type,
+/// and ``Row`` provides the conversions between them.
+use serde::Serialize;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct RowIndex {
     pub value: usize,
 }
@@ -70,3 +74,76 @@ impl LineNumber {
         self.sub(1)
     }
 }
+
+/// Offset of a byte in a row string, used to slice it. A grapheme can be
+/// made of several bytes, so byte offsets must not be used to move the cursor.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct ByteIndex {
+    pub value: usize,
+}
+
+impl ByteIndex {
+    #[must_use]
+    pub fn new(value: usize) -> Self {
+        Self { value }
+    }
+}
+
+/// Index of a grapheme cluster (ex: ``e`` followed by a combining accent) in a
+/// row, which is what the cursor moves over and the edits operate on.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize)]
+#[serde(transparent)]
+pub struct GraphemeIndex {
+    pub value: usize,
+}
+
+impl GraphemeIndex {
+    #[must_use]
+    pub fn new(value: usize) -> Self {
+        Self { value }
+    }
+
+    #[must_use]
+    pub fn add(&self, value: usize) -> Self {
+        Self::new(self.value.saturating_add(value))
+    }
+
+    #[must_use]
+    pub fn sub(&self, value: usize) -> Self {
+        Self::new(self.value.saturating_sub(value))
+    }
+
+    #[must_use]
+    pub fn next(&self) -> Self {
+        self.add(1)
+    }
+
+    #[must_use]
+    pub fn previous(&self) -> Self {
+        self.sub(1)
+    }
+}
+
+/// Display column of a row on screen. Wide graphemes and tabs span several
+/// columns, and zero-width graphemes none.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Column {
+    pub value: usize,
+}
+
+impl Column {
+    #[must_use]
+    pub fn new(value: usize) -> Self {
+        Self { value }
+    }
+
+    #[must_use]
+    pub fn add(&self, value: usize) -> Self {
+        Self::new(self.value.saturating_add(value))
+    }
+
+    #[must_use]
+    pub fn sub(&self, value: usize) -> Self {
+        Self::new(self.value.saturating_sub(value))
+    }
+}
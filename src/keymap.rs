@@ -0,0 +1,187 @@
+/// This module implements user-defined key mappings, created with prompt
+/// commands such as ``:imap jk <Esc>``, either interactively or from the
+/// config file.
+///
+/// Each editor mode has its own mapping table. A key sequence that is the
+/// start of a longer mapping is kept pending until the sequence is either
+/// complete, broken by an unmapped key, or times out.
+use std::collections::HashMap;
+use std::fmt;
+use termion::event::Key;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MapMode {
+    Normal,
+    Insert,
+    Prompt,
+}
+
+impl fmt::Display for MapMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MapMode::Normal => write!(f, "nmap"),
+            MapMode::Insert => write!(f, "imap"),
+            MapMode::Prompt => write!(f, "cmap"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mapping {
+    pub keys: Vec<Key>,
+    /// Whether the mapped keys can themselves be remapped (``:map`` vs ``:noremap``)
+    pub recursive: bool,
+}
+
+/// The outcome of looking up a sequence of pending keys in a mapping table
+#[derive(Debug, PartialEq)]
+pub enum Resolution<'a> {
+    /// No mapping starts with the keys
+    Unmapped,
+    /// The keys are the start of at least one longer mapping
+    Pending,
+    Mapped(&'a Mapping),
+}
+
+#[derive(Debug, Default)]
+pub struct Keymap {
+    tables: HashMap<MapMode, HashMap<Vec<Key>, Mapping>>,
+}
+
+impl Keymap {
+    pub fn insert(&mut self, mode: MapMode, keys: Vec<Key>, mapping: Mapping) {
+        self.tables.entry(mode).or_default().insert(keys, mapping);
+    }
+
+    /// Return the mapping exactly matching the provided keys, if any
+    #[must_use]
+    pub fn get(&self, mode: MapMode, keys: &[Key]) -> Option<&Mapping> {
+        self.tables.get(&mode).and_then(|table| table.get(keys))
+    }
+
+    /// Look the keys up in the mode mapping table. Keys matching a mapping that is
+    /// also the start of a longer one are considered as pending, until the longer
+    /// mapping is either typed or ruled out.
+    #[must_use]
+    pub fn resolve(&self, mode: MapMode, keys: &[Key]) -> Resolution<'_> {
+        let Some(table) = self.tables.get(&mode) else {
+            return Resolution::Unmapped;
+        };
+        if table
+            .keys()
+            .any(|lhs| lhs.len() > keys.len() && lhs.starts_with(keys))
+        {
+            return Resolution::Pending;
+        }
+        match table.get(keys) {
+            Some(mapping) => Resolution::Mapped(mapping),
+            None => Resolution::Unmapped,
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tables.values().all(HashMap::is_empty)
+    }
+
+    /// Return all the mappings defined for the mode, formatted with the key
+    /// notation and sorted by mapped keys
+    #[must_use]
+    pub fn describe(&self, mode: MapMode) -> Vec<(String, String)> {
+        let mut descriptions: Vec<(String, String)> = self
+            .tables
+            .get(&mode)
+            .map(|table| {
+                table
+                    .iter()
+                    .map(|(lhs, mapping)| (format_keys(lhs), format_keys(&mapping.keys)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        descriptions.sort();
+        descriptions
+    }
+}
+
+/// Return the key corresponding to a ``<name>`` special key notation
+fn special_key(name: &str) -> Option<Key> {
+    let lowercase_name = name.to_lowercase();
+    let key = match lowercase_name.as_str() {
+        "esc" => Key::Esc,
+        "cr" | "enter" | "return" => Key::Char('\n'),
+        "tab" => Key::Char('\t'),
+        "bs" => Key::Backspace,
+        "del" => Key::Delete,
+        "space" => Key::Char(' '),
+        "lt" => Key::Char('<'),
+        "bar" => Key::Char('|'),
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "home" => Key::Home,
+        "end" => Key::End,
+        _ => {
+            let c = lowercase_name.strip_prefix("c-")?;
+            let mut chars = c.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Key::Ctrl(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(key)
+}
+
+/// Parse a key sequence written in the Vim key notation (eg: ``jk``, ``<Esc>``,
+/// ``:w<CR>`` or ``<C-w>``). A ``<`` that does not start a known special key
+/// is taken literally.
+#[must_use]
+pub fn parse_keys(notation: &str) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut rest = notation;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some(end) = rest.find('>') {
+                if let Some(key) = special_key(&rest[1..end]) {
+                    keys.push(key);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        keys.push(Key::Char(c));
+        rest = &rest[c.len_utf8()..];
+    }
+    keys
+}
+
+/// Format a key sequence using the Vim key notation
+#[must_use]
+pub fn format_keys(keys: &[Key]) -> String {
+    keys.iter()
+        .map(|key| match key {
+            Key::Esc => String::from("<Esc>"),
+            Key::Char('\n') => String::from("<CR>"),
+            Key::Char('\t') => String::from("<Tab>"),
+            Key::Char(' ') => String::from("<Space>"),
+            Key::Char('<') => String::from("<lt>"),
+            Key::Char('|') => String::from("<Bar>"),
+            Key::Char(c) => c.to_string(),
+            Key::Ctrl(c) => format!("<C-{c}>"),
+            Key::Backspace => String::from("<BS>"),
+            Key::Delete => String::from("<Del>"),
+            Key::Left => String::from("<Left>"),
+            Key::Right => String::from("<Right>"),
+            Key::Up => String::from("<Up>"),
+            Key::Down => String::from("<Down>"),
+            Key::Home => String::from("<Home>"),
+            Key::End => String::from("<End>"),
+            other => format!("<{other:?}>"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "./keymap_test.rs"]
+mod keymap_test;
@@ -0,0 +1,88 @@
+use crate::keymap::{format_keys, parse_keys, Keymap, MapMode, Mapping, Resolution};
+use termion::event::Key;
+
+fn mapping(notation: &str) -> Mapping {
+    Mapping {
+        keys: parse_keys(notation),
+        recursive: false,
+    }
+}
+
+#[test]
+fn test_parse_keys() {
+    assert_eq!(parse_keys("jk"), vec![Key::Char('j'), Key::Char('k')]);
+    assert_eq!(parse_keys("<Esc>"), vec![Key::Esc]);
+    assert_eq!(
+        parse_keys(":w<cr>"),
+        vec![Key::Char(':'), Key::Char('w'), Key::Char('\n')]
+    );
+    assert_eq!(
+        parse_keys("<C-w><lt>"),
+        vec![Key::Ctrl('w'), Key::Char('<')]
+    );
+    // unknown special keys are taken literally
+    assert_eq!(
+        parse_keys("<x>"),
+        vec![Key::Char('<'), Key::Char('x'), Key::Char('>')]
+    );
+}
+
+#[test]
+fn test_format_keys() {
+    assert_eq!(format_keys(&parse_keys(":w<CR>")), ":w<CR>");
+    assert_eq!(
+        format_keys(&[Key::Ctrl('r'), Key::Char(' ')]),
+        "<C-r><Space>"
+    );
+}
+
+#[test]
+fn test_resolve() {
+    let mut keymap = Keymap::default();
+    keymap.insert(MapMode::Insert, parse_keys("jk"), mapping("<Esc>"));
+    keymap.insert(MapMode::Normal, parse_keys("Q"), mapping(":q<CR>"));
+    keymap.insert(MapMode::Normal, parse_keys("QQ"), mapping(":q!<CR>"));
+
+    assert_eq!(
+        keymap.resolve(MapMode::Insert, &[Key::Char('j')]),
+        Resolution::Pending
+    );
+    assert_eq!(
+        keymap.resolve(MapMode::Insert, &parse_keys("jk")),
+        Resolution::Mapped(&mapping("<Esc>"))
+    );
+    assert_eq!(
+        keymap.resolve(MapMode::Insert, &parse_keys("jj")),
+        Resolution::Unmapped
+    );
+    assert_eq!(
+        keymap.resolve(MapMode::Prompt, &parse_keys("j")),
+        Resolution::Unmapped
+    );
+    // ambiguous mappings stay pending until the longer one is ruled out
+    assert_eq!(
+        keymap.resolve(MapMode::Normal, &parse_keys("Q")),
+        Resolution::Pending
+    );
+    assert_eq!(
+        keymap.get(MapMode::Normal, &parse_keys("Q")),
+        Some(&mapping(":q<CR>"))
+    );
+}
+
+#[test]
+fn test_describe() {
+    let mut keymap = Keymap::default();
+    assert!(keymap.is_empty());
+    keymap.insert(MapMode::Normal, parse_keys("Q"), mapping(":q<CR>"));
+    keymap.insert(MapMode::Normal, parse_keys("<Space>w"), mapping(":w<CR>"));
+    assert!(!keymap.is_empty());
+    assert_eq!(
+        keymap.describe(MapMode::Normal),
+        vec![
+            (String::from("<Space>w"), String::from(":w<CR>")),
+            (String::from("Q"), String::from(":q<CR>")),
+        ]
+    );
+    assert!(keymap.describe(MapMode::Insert).is_empty());
+}
@@ -0,0 +1,293 @@
+/// This module implements the prose linter, a style checker in the spirit of
+/// write-good. It flags repeated words (ex: "the the"), sentences longer than
+/// a number of words, and the words and phrases matched by rule sets, such as
+/// weasel words, adverbs, clichés or passive constructions. Rule sets are data
+/// files: the ones bundled with bo can be replaced, and new ones added, by the
+/// ``.rules`` files of ``~/.config/bo/lint``.
+use crate::utils;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Directory of the user-defined rule sets
+const RULES_DIRECTORY: &str = "~/.config/bo/lint";
+/// Extension of the rule set files
+const RULES_EXTENSION: &str = "rules";
+/// Default maximum number of words of a sentence
+pub const DEFAULT_SENTENCE_LENGTH: usize = 30;
+
+/// Rule sets bundled with bo, along with their names
+const BUNDLED_RULE_SETS: [(&str, &str); 4] = [
+    ("adverbs", include_str!("../rules/adverbs.rules")),
+    ("cliches", include_str!("../rules/cliches.rules")),
+    (
+        "passive-voice",
+        include_str!("../rules/passive-voice.rules"),
+    ),
+    ("weasel-words", include_str!("../rules/weasel-words.rules")),
+];
+
+/// A style issue found in a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Byte range of the flagged text
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+/// What a word of a pattern matches, in lowercase
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Matcher {
+    Word(String),
+    /// Any word ending with the suffix (ex: ``*ly``)
+    Suffix(String),
+}
+
+impl Matcher {
+    fn parse(word: &str) -> Self {
+        match word.strip_prefix('*') {
+            Some(suffix) => Matcher::Suffix(normalize(suffix)),
+            None => Matcher::Word(normalize(word)),
+        }
+    }
+
+    fn matches(&self, word: &str) -> bool {
+        match self {
+            Matcher::Word(expected) => word == expected,
+            Matcher::Suffix(suffix) => word.len() > suffix.len() && word.ends_with(suffix.as_str()),
+        }
+    }
+}
+
+/// A word of a pattern, matching any of its alternatives
+type PatternWord = Vec<Matcher>;
+
+#[derive(Debug)]
+struct Rule {
+    message: String,
+    /// Sequences of words flagged by the rule
+    patterns: Vec<Vec<PatternWord>>,
+    /// Matched words and phrases that aren't flagged, in lowercase
+    exceptions: HashSet<String>,
+}
+
+impl Rule {
+    /// Return the number of words matched by the longest pattern of the rule
+    /// at the start of the provided words, if any
+    fn match_len(&self, words: &[String]) -> Option<usize> {
+        self.patterns
+            .iter()
+            .filter(|pattern| {
+                pattern.len() <= words.len()
+                    && pattern
+                        .iter()
+                        .zip(words)
+                        .all(|(alternatives, word)| alternatives.iter().any(|m| m.matches(word)))
+            })
+            .map(Vec::len)
+            .max()
+            .filter(|len| !self.exceptions.contains(&words[..*len].join(" ")))
+    }
+}
+
+/// Rules parsed from a rule set file. Each ``[name]`` section of the file is a
+/// rule, made of a ``message``, an optional ``except`` list of words, and of
+/// the patterns it flags, one per line. A pattern is a sequence of words, each
+/// being a literal word, a word ending (ex: ``*ed``) or a set of words defined
+/// beforehand on a line starting with ``@`` (ex: ``@be = am is are``).
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Parse the content of a rule set file
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut sets: HashMap<&str, PatternWord> = HashMap::new();
+        let mut rule_set = Self::default();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: String| format!("line {}: {message}", i + 1);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((name, words)) = line
+                .strip_prefix('@')
+                .and_then(|definition| definition.split_once('='))
+            {
+                let alternatives = words.split_whitespace().map(Matcher::parse).collect();
+                sets.insert(name.trim(), alternatives);
+            } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                rule_set.rules.push(Rule {
+                    message: name.trim().to_string(),
+                    patterns: vec![],
+                    exceptions: HashSet::new(),
+                });
+            } else {
+                let rule = rule_set
+                    .rules
+                    .last_mut()
+                    .ok_or_else(|| error(format!("Pattern '{line}' outside of a rule")))?;
+                match line.split_once('=').map(|(key, value)| (key.trim(), value)) {
+                    Some(("message", message)) => rule.message = message.trim().to_string(),
+                    Some(("except", words)) => {
+                        rule.exceptions
+                            .extend(words.split_whitespace().map(normalize));
+                    }
+                    Some((key, _)) => return Err(error(format!("Unknown key '{key}'"))),
+                    None => rule
+                        .patterns
+                        .push(parse_pattern(line, &sets).map_err(error)?),
+                }
+            }
+        }
+        Ok(rule_set)
+    }
+}
+
+/// Parse a pattern made of words, word endings and references to word sets
+fn parse_pattern(
+    line: &str,
+    sets: &HashMap<&str, PatternWord>,
+) -> Result<Vec<PatternWord>, String> {
+    let mut pattern = vec![];
+    for token in line.split_whitespace() {
+        if let Some(name) = token.strip_prefix('@') {
+            let set = sets
+                .get(name)
+                .ok_or_else(|| format!("Unknown word set '{token}'"))?;
+            pattern.push(set.clone());
+        } else if token.starts_with('*') {
+            pattern.push(vec![Matcher::parse(token)]);
+        } else {
+            // hyphenated words (ex: ``low-hanging``) are made of several words
+            pattern.extend(
+                words(token)
+                    .into_iter()
+                    .map(|(_, word)| vec![Matcher::Word(normalize(word))]),
+            );
+        }
+    }
+    Ok(pattern)
+}
+
+#[derive(Debug)]
+pub struct Linter {
+    rule_sets: Vec<RuleSet>,
+}
+
+impl Linter {
+    /// Return the directory the user-defined rule sets are looked up in
+    #[must_use]
+    pub fn directory() -> PathBuf {
+        PathBuf::from(utils::expand_tilde(RULES_DIRECTORY))
+    }
+
+    /// Load the bundled rule sets, along with the rule sets of the directory,
+    /// each replacing the bundled rule set of the same name, if any (ex:
+    /// ``adverbs.rules``). An empty file thus disables a bundled rule set.
+    pub fn load(directory: &Path) -> Result<Self, String> {
+        let mut rule_sets = BTreeMap::new();
+        for (name, content) in BUNDLED_RULE_SETS {
+            let rule_set = RuleSet::parse(content)
+                .map_err(|error| format!("Invalid rule set {name}: {error}"))?;
+            rule_sets.insert(name.to_string(), rule_set);
+        }
+        let mut paths: Vec<PathBuf> = fs::read_dir(directory)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        paths.sort();
+        for path in paths {
+            if path.extension() != Some(OsStr::new(RULES_EXTENSION)) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(OsStr::to_str) else {
+                continue;
+            };
+            let content = fs::read_to_string(&path)
+                .map_err(|error| format!("Error reading {}: {error}", path.display()))?;
+            let rule_set = RuleSet::parse(&content)
+                .map_err(|error| format!("Invalid rule set {}: {error}", path.display()))?;
+            rule_sets.insert(name.to_string(), rule_set);
+        }
+        Ok(Self {
+            rule_sets: rule_sets.into_values().collect(),
+        })
+    }
+
+    /// Return the style findings of the text, sorted by position, its sentences
+    /// being provided as byte ranges. Sentences longer than the maximum number
+    /// of words are flagged from their first extra word, and the rules only
+    /// match the words of a single sentence.
+    #[must_use]
+    pub fn lint(
+        &self,
+        text: &str,
+        sentences: &[Range<usize>],
+        max_sentence_length: usize,
+    ) -> Vec<Finding> {
+        let mut findings = vec![];
+        for sentence in sentences {
+            let (ranges, words): (Vec<Range<usize>>, Vec<String>) = words(&text[sentence.clone()])
+                .into_iter()
+                .map(|(range, word)| {
+                    let start = sentence.start + range.start;
+                    (start..start + word.len(), normalize(word))
+                })
+                .unzip();
+            if words.len() > max_sentence_length {
+                let end = sentence.start + text[sentence.clone()].trim_end().len();
+                findings.push(Finding {
+                    range: ranges[max_sentence_length].start..end,
+                    message: format!("Long sentence: {} words", words.len()),
+                });
+            }
+            for i in 1..words.len() {
+                let separator = &text[ranges[i - 1].end..ranges[i].start];
+                if words[i] == words[i - 1]
+                    && words[i].chars().all(char::is_alphabetic)
+                    && separator.chars().all(char::is_whitespace)
+                {
+                    findings.push(Finding {
+                        range: ranges[i - 1].start..ranges[i].end,
+                        message: format!("Repeated word: '{}'", &text[ranges[i].clone()]),
+                    });
+                }
+            }
+            for rule in self.rule_sets.iter().flat_map(|rule_set| &rule_set.rules) {
+                for i in 0..words.len() {
+                    if let Some(len) = rule.match_len(&words[i..]) {
+                        let range = ranges[i].start..ranges[i + len - 1].end;
+                        findings.push(Finding {
+                            message: format!("{}: '{}'", rule.message, &text[range.clone()]),
+                            range,
+                        });
+                    }
+                }
+            }
+        }
+        findings.sort_by_key(|finding| (finding.range.start, finding.range.end));
+        findings
+    }
+}
+
+/// Return the words of the text, along with their byte ranges, skipping the
+/// whitespace and punctuation separating them
+fn words(text: &str) -> Vec<(Range<usize>, &str)> {
+    text.split_word_bound_indices()
+        .filter(|(_, word)| word.chars().any(char::is_alphanumeric))
+        .map(|(start, word)| (start..start + word.len(), word))
+        .collect()
+}
+
+/// Return the word in lowercase, with its typographic apostrophes replaced by ASCII ones
+fn normalize(word: &str) -> String {
+    word.to_lowercase().replace('\u{2019}', "'")
+}
+
+#[cfg(test)]
+#[path = "./lint_test.rs"]
+mod lint_test;
@@ -0,0 +1,138 @@
+use crate::lint::{Linter, RuleSet};
+use std::fs;
+use std::ops::Range;
+use tempfile::tempdir;
+
+/// Return the flagged text and the message of the findings of a single sentence
+fn lint(linter: &Linter, text: &str, max_sentence_length: usize) -> Vec<(String, String)> {
+    linter
+        .lint(
+            text,
+            std::slice::from_ref(&(0..text.len())),
+            max_sentence_length,
+        )
+        .into_iter()
+        .map(|finding| (text[finding.range].to_string(), finding.message))
+        .collect()
+}
+
+fn bundled_linter() -> Linter {
+    let dir = tempdir().unwrap();
+    Linter::load(dir.path()).unwrap()
+}
+
+#[test]
+fn test_bundled_rule_sets() {
+    let linter = bundled_linter();
+    assert_eq!(
+        lint(
+            &linter,
+            "The report was written quickly, and it is very clear that the the plan failed.",
+            30
+        ),
+        vec![
+            (
+                "was written".to_string(),
+                "Passive voice: 'was written'".to_string()
+            ),
+            ("quickly".to_string(), "Adverb: 'quickly'".to_string()),
+            ("very".to_string(), "Weasel word: 'very'".to_string()),
+            ("the the".to_string(), "Repeated word: 'the'".to_string()),
+        ]
+    );
+    // phrases are matched regardless of case, hyphens and line breaks
+    assert_eq!(
+        lint(
+            &linter,
+            "At the end of the day, it's low-hanging\nfruit.",
+            30
+        ),
+        vec![
+            (
+                "At the end of the day".to_string(),
+                "Cliché: 'At the end of the day'".to_string()
+            ),
+            (
+                "low-hanging\nfruit".to_string(),
+                "Cliché: 'low-hanging\nfruit'".to_string()
+            ),
+        ]
+    );
+    // words listed as exceptions aren't flagged
+    assert!(lint(&linter, "Only the family was friendly.", 30).is_empty());
+}
+
+#[test]
+fn test_repeated_words() {
+    let linter = Linter::load(&tempdir().unwrap().path().join("missing")).unwrap();
+    assert_eq!(
+        lint(&linter, "The the cat sat", 30),
+        vec![("The the".to_string(), "Repeated word: 'the'".to_string())]
+    );
+    // separated by punctuation, or numbers
+    assert!(lint(&linter, "He said that, that was it: 1 1", 30).is_empty());
+}
+
+#[test]
+fn test_long_sentences() {
+    let linter = bundled_linter();
+    let text = "One two three four five. Six seven.";
+    let sentences: Vec<Range<usize>> = vec![0..25, 25..text.len()];
+    let findings = linter.lint(text, &sentences, 3);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(&text[findings[0].range.clone()], "four five.");
+    assert_eq!(findings[0].message, "Long sentence: 5 words");
+    // patterns don't span sentences
+    let text = "It was. Finished now.";
+    assert!(linter.lint(text, &[0..8, 8..text.len()], 30).is_empty());
+}
+
+#[test]
+fn test_user_rule_sets() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("house.rules"),
+        "# house style\n@verb = utilize leverage\n\n[jargon]\nmessage = Jargon\n@verb *ing\nsynergy\n",
+    )
+    .unwrap();
+    // an empty rule set replaces the bundled one of the same name
+    fs::write(dir.path().join("adverbs.rules"), "").unwrap();
+    fs::write(dir.path().join("notes.txt"), "[ignored]\nsynergy").unwrap();
+    let linter = Linter::load(dir.path()).unwrap();
+    assert_eq!(
+        lint(&linter, "Leverage existing synergy, happily.", 30),
+        vec![
+            (
+                "Leverage existing".to_string(),
+                "Jargon: 'Leverage existing'".to_string()
+            ),
+            ("synergy".to_string(), "Jargon: 'synergy'".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_rule_sets() {
+    assert_eq!(
+        RuleSet::parse("synergy\n").unwrap_err(),
+        "line 1: Pattern 'synergy' outside of a rule"
+    );
+    assert_eq!(
+        RuleSet::parse("[jargon]\n@verb *ing\n").unwrap_err(),
+        "line 2: Unknown word set '@verb'"
+    );
+    assert_eq!(
+        RuleSet::parse("[jargon]\nseverity = high\n").unwrap_err(),
+        "line 2: Unknown key 'severity'"
+    );
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("house.rules");
+    fs::write(&path, "synergy\n").unwrap();
+    assert_eq!(
+        Linter::load(dir.path()).unwrap_err(),
+        format!(
+            "Invalid rule set {}: line 1: Pattern 'synergy' outside of a rule",
+            path.display()
+        )
+    );
+}
@@ -1,18 +1,33 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+mod address;
 mod commands;
 mod config;
 mod console;
+mod display_width;
 mod document;
 mod editor;
+mod encoding;
+mod file_format;
+mod filetype;
+mod frame;
+mod grammar;
 mod help;
+mod highlighting;
 mod history;
 mod indexing;
+mod keymap;
+mod lint;
+mod mapped_file;
+mod markdown;
 mod mode;
 mod navigator;
+mod rope;
 mod row;
+mod spell;
 mod terminal;
 mod utils;
+mod virtual_console;
 
 use editor::Editor;
 use structopt::StructOpt;
@@ -20,15 +35,18 @@ use structopt::StructOpt;
 pub use config::Config;
 pub use console::{Console, ConsoleSize};
 pub use document::Document;
-pub use editor::{Position, ViewportOffset};
+pub use editor::{Position, ScreenPosition, ViewportOffset};
+pub use encoding::Encoding;
+pub use file_format::{FileFormat, LineEnding};
 pub use help::{Help, Section};
 pub use history::{History, Operation, OperationType};
-pub use indexing::{LineNumber, RowIndex};
+pub use indexing::{ByteIndex, Column, GraphemeIndex, LineNumber, RowIndex};
 pub use mode::Mode;
-pub use navigator::{Boundary, Navigator};
+pub use navigator::{Boundary, CharSearch, DocumentPosition, Navigator, WordKind};
 pub use row::Row;
 pub use terminal::{AnsiPosition, Terminal};
 pub use utils::{bo_version, log};
+pub use virtual_console::VirtualConsole;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "bo", about = "An opinionated text editor")]
@@ -48,6 +66,8 @@ fn main() {
         println!("{}", bo_version());
     } else {
         let term = Box::new(Terminal::default().unwrap());
-        Editor::new(opt.file_name, term).run();
+        let mut editor = Editor::new(opt.file_name, term);
+        editor.load_config_file(&Config::file_path());
+        editor.run();
     }
 }
@@ -0,0 +1,217 @@
+/// This module implements the read-mostly access to files too large to be
+/// read in memory up front. The file is memory-mapped, and split into blocks
+/// of lines by a background thread. The rows of a block are only decoded
+/// when they are accessed, and can be discarded again once out of view.
+use crate::rope::ChunkHash;
+use crate::{FileFormat, LineEnding, Row};
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::File;
+use std::io::{Error, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+/// Size above which a file is memory-mapped instead of being read in memory
+pub const MAPPED_FILE_MIN_SIZE: u64 = 64 * 1024 * 1024;
+/// Number of lines per block
+const BLOCK_LEN: usize = 1024;
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+/// Number of bytes at the start of a file its encoding and line ending are detected from
+const SAMPLE_LEN: usize = 1024 * 1024;
+
+/// Return whether the file looks UTF-8 encoded, which is required to map it,
+/// judging from its first bytes
+///
+/// # Errors
+/// Returns an error if the file can't be read.
+pub fn looks_like_utf8(filename: &Path) -> Result<bool, Error> {
+    let mut sample = Vec::with_capacity(SAMPLE_LEN);
+    File::open(filename)?
+        .take(SAMPLE_LEN as u64)
+        .read_to_end(&mut sample)?;
+    Ok(match std::str::from_utf8(&sample) {
+        Ok(_) => true,
+        // the sample can end in the middle of a character
+        Err(error) => error.error_len().is_none(),
+    })
+}
+
+/// A range of consecutive lines of a memory-mapped file
+#[derive(Clone)]
+pub struct MappedBlock {
+    mmap: Arc<Mmap>,
+    /// Byte range of the lines in the file, line terminators included
+    start: usize,
+    end: usize,
+    pub num_lines: usize,
+    /// Hash of the block rows, computed while indexing them
+    pub hash: ChunkHash,
+    pub line_ending: LineEnding,
+}
+
+impl fmt::Debug for MappedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedBlock")
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .field("num_lines", &self.num_lines)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MappedBlock {
+    /// Return the raw content of the block lines, as found in the file
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.mmap[self.start..self.end]
+    }
+
+    /// Decode the block lines into rows. Invalid UTF-8 sequences are replaced
+    /// by U+FFFD.
+    #[must_use]
+    pub fn rows(&self) -> Vec<Row> {
+        split_lines(self.bytes(), self.line_ending)
+            .map(|line| Row::from(String::from_utf8_lossy(line).into_owned()))
+            .collect()
+    }
+}
+
+/// Split the bytes into lines, stripping their terminator
+fn split_lines(bytes: &[u8], line_ending: LineEnding) -> impl Iterator<Item = &[u8]> {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes
+        .split(|&b| b == b'\n')
+        .map(move |line| match line_ending {
+            LineEnding::Dos => line.strip_suffix(b"\r").unwrap_or(line),
+            LineEnding::Unix => line,
+        })
+}
+
+/// Split the mapped file into blocks, sending them through the channel as
+/// soon as they are indexed
+fn index_blocks(
+    mmap: &Arc<Mmap>,
+    start: usize,
+    line_ending: LineEnding,
+    indexed_bytes: &AtomicUsize,
+    sender: &mpsc::Sender<MappedBlock>,
+) {
+    let mut start = start;
+    while start < mmap.len() {
+        let mut end = start;
+        let mut num_lines = 0;
+        while num_lines < BLOCK_LEN && end < mmap.len() {
+            end = match mmap[end..].iter().position(|&b| b == b'\n') {
+                Some(newline_index) => end + newline_index + 1,
+                None => mmap.len(),
+            };
+            num_lines += 1;
+        }
+        let hash =
+            ChunkHash::of(split_lines(&mmap[start..end], line_ending).map(String::from_utf8_lossy));
+        let block = MappedBlock {
+            mmap: Arc::clone(mmap),
+            start,
+            end,
+            num_lines,
+            hash,
+            line_ending,
+        };
+        if sender.send(block).is_err() {
+            // the document was closed
+            return;
+        }
+        indexed_bytes.store(end, Ordering::Relaxed);
+        start = end;
+    }
+}
+
+/// A memory-mapped file, being indexed in the background
+#[derive(Debug)]
+pub struct MappedFile {
+    pub format: FileFormat,
+    size: usize,
+    /// Number of bytes indexed so far
+    indexed_bytes: Arc<AtomicUsize>,
+    blocks: Receiver<MappedBlock>,
+}
+
+impl MappedFile {
+    /// Map the file in memory and start indexing its lines. The file is
+    /// expected to be UTF-8 encoded (see ``looks_like_utf8``), and its line
+    /// ending is detected from its first bytes.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be opened or mapped.
+    pub fn open(filename: &Path) -> Result<Self, Error> {
+        let file = File::open(filename)?;
+        // The file could be modified by another process while mapped, which would be
+        // reflected in the document. This is accepted for the read-mostly viewing of
+        // large files, as reading them in memory is not an option.
+        let mmap = Arc::new(unsafe { Mmap::map(&file)? });
+        let bom = mmap.starts_with(UTF8_BOM);
+        let content_start = if bom { UTF8_BOM.len() } else { 0 };
+        let sample_end = mmap.len().min(content_start.saturating_add(SAMPLE_LEN));
+        let line_ending = LineEnding::detect(&mmap[content_start..sample_end]);
+        let format = FileFormat {
+            line_ending,
+            final_newline: mmap.is_empty() || mmap.ends_with(b"\n"),
+            bom,
+            ..FileFormat::default()
+        };
+
+        let size = mmap.len();
+        let indexed_bytes = Arc::new(AtomicUsize::new(content_start));
+        let (sender, receiver) = mpsc::channel();
+        let thread_indexed_bytes = Arc::clone(&indexed_bytes);
+        thread::spawn(move || {
+            index_blocks(
+                &mmap,
+                content_start,
+                line_ending,
+                &thread_indexed_bytes,
+                &sender,
+            );
+        });
+        Ok(Self {
+            format,
+            size,
+            indexed_bytes,
+            blocks: receiver,
+        })
+    }
+
+    /// Wait for the next block to be indexed, and return it, or ``None`` if
+    /// the whole file was indexed.
+    #[must_use]
+    pub fn next_block(&self) -> Option<MappedBlock> {
+        self.blocks.recv().ok()
+    }
+
+    /// Return the blocks indexed since the last call, along with whether
+    /// the whole file was indexed
+    #[must_use]
+    pub fn indexed_blocks(&self) -> (Vec<MappedBlock>, bool) {
+        let mut blocks = Vec::new();
+        loop {
+            match self.blocks.try_recv() {
+                Ok(block) => blocks.push(block),
+                Err(TryRecvError::Empty) => return (blocks, false),
+                Err(TryRecvError::Disconnected) => return (blocks, true),
+            }
+        }
+    }
+
+    /// Return the percentage of the file indexed so far
+    #[must_use]
+    pub fn progress(&self) -> usize {
+        self.indexed_bytes.load(Ordering::Relaxed) * 100 / self.size.max(1)
+    }
+}
+
+#[cfg(test)]
+#[path = "./mapped_file_test.rs"]
+mod mapped_file_test;
@@ -0,0 +1,90 @@
+use crate::mapped_file::{looks_like_utf8, MappedFile};
+use crate::rope::ChunkHash;
+use crate::LineEnding;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn mapped_file(content: &[u8]) -> (NamedTempFile, MappedFile) {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(content).unwrap();
+    let mapped_file = MappedFile::open(file.path()).unwrap();
+    (file, mapped_file)
+}
+
+#[test]
+fn test_mapped_file_blocks() {
+    let content = (0..2500)
+        .map(|i| format!("line {i}\n"))
+        .collect::<Vec<_>>()
+        .concat();
+    let (_file, mapped_file) = mapped_file(content.as_bytes());
+    let mut blocks = Vec::new();
+    while let Some(block) = mapped_file.next_block() {
+        blocks.push(block);
+    }
+    assert_eq!(
+        blocks
+            .iter()
+            .map(|block| block.num_lines)
+            .collect::<Vec<_>>(),
+        vec![1024, 1024, 452]
+    );
+    assert_eq!(mapped_file.progress(), 100);
+    let (remaining_blocks, done) = mapped_file.indexed_blocks();
+    assert!(remaining_blocks.is_empty());
+    assert!(done);
+    let rows = blocks[1].rows();
+    assert_eq!(rows[0].string, "line 1024");
+    assert_eq!(rows[1023].string, "line 2047");
+    assert_eq!(
+        blocks[1].hash,
+        ChunkHash::of(rows.iter().map(|row| &row.string))
+    );
+    assert!(blocks[2].bytes().starts_with(b"line 2048\n"));
+}
+
+#[test]
+fn test_mapped_file_format() {
+    let (_file, mapped_file) = mapped_file(b"\xef\xbb\xbfa\r\nb\r\nc");
+    assert_eq!(mapped_file.format.to_string(), "dos, noeol, bom");
+    let block = mapped_file.next_block().unwrap();
+    assert_eq!(block.line_ending, LineEnding::Dos);
+    assert_eq!(
+        block
+            .rows()
+            .iter()
+            .map(|row| row.string.as_str())
+            .collect::<Vec<_>>(),
+        vec!["a", "b", "c"]
+    );
+    assert_eq!(block.bytes(), b"a\r\nb\r\nc");
+}
+
+#[test]
+fn test_mapped_file_line_ending_is_detected_from_every_line() {
+    // as for files read in memory, a single Unix line ending makes the file Unix
+    let (_file, mixed) = mapped_file(b"a\r\nb\nc\r\n");
+    assert_eq!(mixed.format.line_ending, LineEnding::Unix);
+    let (_file, dos) = mapped_file(b"a\r\nb\r\n");
+    assert_eq!(dos.format.line_ending, LineEnding::Dos);
+}
+
+#[test]
+fn test_looks_like_utf8() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all("café\n".as_bytes()).unwrap();
+    assert!(looks_like_utf8(file.path()).unwrap());
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"caf\xe9\n").unwrap();
+    assert!(!looks_like_utf8(file.path()).unwrap());
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"\xff\xfeh\x00i\x00").unwrap();
+    assert!(!looks_like_utf8(file.path()).unwrap());
+}
+
+#[test]
+fn test_mapped_file_invalid_utf8() {
+    let (_file, mapped_file) = mapped_file(b"caf\xe9\n");
+    let block = mapped_file.next_block().unwrap();
+    assert_eq!(block.rows()[0].string, "caf\u{fffd}");
+}
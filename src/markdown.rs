@@ -0,0 +1,202 @@
+use crate::highlighting::{HighlightGroup, LineState, Span};
+
+/// Highlight a line of a Markdown document, starting from the state at the end
+/// of the previous line, and return the highlighted spans along with the state
+/// at the end of the line
+#[must_use]
+pub fn highlight_line(line: &str, state: LineState) -> (Vec<Span>, LineState) {
+    let whole_line = |group| vec![Span::new(0..line.len(), group)];
+    let content = line.trim_start_matches(' ');
+    let indent = line.len() - content.len();
+    if let LineState::Fence { marker, len } = state {
+        let closing = indent <= 3
+            && run_len(content.as_bytes(), 0, marker as u8) >= len
+            && content.trim_start_matches(marker).trim().is_empty();
+        let state = if closing { LineState::Normal } else { state };
+        return (whole_line(HighlightGroup::Code), state);
+    }
+    if indent <= 3 {
+        for marker in ['`', '~'] {
+            let len = run_len(content.as_bytes(), 0, marker as u8);
+            // the info string of a backtick fence can't contain backticks
+            if len >= 3 && !(marker == '`' && content[len..].contains('`')) {
+                return (
+                    whole_line(HighlightGroup::Code),
+                    LineState::Fence { marker, len },
+                );
+            }
+        }
+        let level = run_len(content.as_bytes(), 0, b'#');
+        if (1..=6).contains(&level) && matches!(content.as_bytes().get(level), None | Some(b' ')) {
+            return (whole_line(HighlightGroup::Heading), LineState::Normal);
+        }
+        if content.starts_with('>') {
+            return (whole_line(HighlightGroup::Quote), LineState::Normal);
+        }
+    }
+    let mut spans = vec![];
+    let mut start = indent;
+    if let Some(len) = list_marker_len(content) {
+        spans.push(Span::new(indent..indent + len, HighlightGroup::ListMarker));
+        start += len;
+    }
+    highlight_inline(line.as_bytes(), start, &mut spans);
+    (spans, LineState::Normal)
+}
+
+/// Return the number of consecutive ``c`` bytes starting at the provided offset
+fn run_len(bytes: &[u8], start: usize, c: u8) -> usize {
+    bytes
+        .get(start..)
+        .map_or(0, |rest| rest.iter().take_while(|byte| **byte == c).count())
+}
+
+/// Return the length of the list item marker (ex: ``-``, ``*`` or ``1.``)
+/// starting the text, if it is followed by a space or ends the line
+fn list_marker_len(content: &str) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let len = match bytes.first()? {
+        b'-' | b'*' | b'+' => 1,
+        b'0'..=b'9' => {
+            let digits = bytes
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            match bytes.get(digits) {
+                Some(b'.' | b')') if digits <= 9 => digits + 1,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    match bytes.get(len) {
+        None | Some(b' ' | b'\t') => Some(len),
+        _ => None,
+    }
+}
+
+/// Highlight the code spans, emphasis, strong emphasis and links located in
+/// the line from the provided offset
+fn highlight_inline(bytes: &[u8], start: usize, spans: &mut Vec<Span>) {
+    let mut i = start;
+    while i < bytes.len() {
+        let span = match bytes[i] {
+            b'\\' => {
+                // escaped character
+                i += 2;
+                continue;
+            }
+            b'`' => code_span(bytes, i),
+            b'*' | b'_' => emphasis(bytes, i),
+            b'[' | b'!' => link(bytes, i),
+            b'<' => autolink(bytes, i),
+            _ => None,
+        };
+        match span {
+            Some(span) => {
+                i = span.range.end;
+                spans.push(span);
+            }
+            None => i += 1,
+        }
+    }
+}
+
+/// Return the code span (ex: ``code``) opened by the run of backticks at the
+/// provided offset, and closed by a run of the same length
+fn code_span(bytes: &[u8], start: usize) -> Option<Span> {
+    let len = run_len(bytes, start, b'`');
+    let mut i = start + len;
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let run = run_len(bytes, i, b'`');
+            if run == len {
+                return Some(Span::new(start..i + run, HighlightGroup::Code));
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Return the emphasis (``*text*``) or strong emphasis (``**text**``) opened
+/// at the provided offset
+fn emphasis(bytes: &[u8], start: usize) -> Option<Span> {
+    let c = bytes[start];
+    let is_word_byte = |index: Option<usize>| {
+        index
+            .and_then(|index| bytes.get(index))
+            .is_some_and(u8::is_ascii_alphanumeric)
+    };
+    // underscores within a word (ex: snake_case) don't emphasize anything
+    if c == b'_' && is_word_byte(start.checked_sub(1)) {
+        return None;
+    }
+    let (len, group) = if run_len(bytes, start, c) >= 2 {
+        (2, HighlightGroup::Strong)
+    } else {
+        (1, HighlightGroup::Emphasis)
+    };
+    // the opening delimiter must be followed by some text
+    if bytes
+        .get(start + len)
+        .is_none_or(|byte| byte.is_ascii_whitespace() || *byte == c)
+    {
+        return None;
+    }
+    let mut i = start + len + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            byte if byte == c => {
+                let run = run_len(bytes, i, c);
+                let end = i + run;
+                // a single delimiter can't close emphasis within a strong one
+                let matching = if len == 1 { run != 2 } else { run >= 2 };
+                let flanking =
+                    !bytes[i - 1].is_ascii_whitespace() && (c != b'_' || !is_word_byte(Some(end)));
+                if matching && flanking {
+                    return Some(Span::new(start..end, group));
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Return the link (``[text](url)``) or image (``![alt](url)``) starting at
+/// the provided offset
+fn link(bytes: &[u8], start: usize) -> Option<Span> {
+    let open = if bytes[start] == b'!' {
+        start + 1
+    } else {
+        start
+    };
+    if bytes.get(open) != Some(&b'[') {
+        return None;
+    }
+    let close = open + 1 + bytes[open + 1..].iter().position(|byte| *byte == b']')?;
+    if bytes.get(close + 1) != Some(&b'(') {
+        return None;
+    }
+    let end = close + 2 + bytes[close + 2..].iter().position(|byte| *byte == b')')?;
+    Some(Span::new(start..end + 1, HighlightGroup::Link))
+}
+
+/// Return the autolink (``<https://example.com>``) starting at the provided offset
+fn autolink(bytes: &[u8], start: usize) -> Option<Span> {
+    let rest = &bytes[start + 1..];
+    if !(rest.starts_with(b"http://") || rest.starts_with(b"https://")) {
+        return None;
+    }
+    let end = start + 1 + rest.iter().position(|byte| *byte == b'>')?;
+    Some(Span::new(start..end + 1, HighlightGroup::Link))
+}
+
+#[cfg(test)]
+#[path = "./markdown_test.rs"]
+mod markdown_test;
@@ -0,0 +1,117 @@
+use crate::highlighting::{HighlightGroup, LineState, Span};
+use crate::markdown::highlight_line;
+
+fn spans(line: &str) -> Vec<(&str, HighlightGroup)> {
+    let (spans, state) = highlight_line(line, LineState::Normal);
+    assert_eq!(state, LineState::Normal);
+    spans
+        .iter()
+        .map(|span| (&line[span.range.clone()], span.group))
+        .collect()
+}
+
+#[test]
+fn test_highlight_block_elements() {
+    assert_eq!(
+        spans("## Title"),
+        vec![("## Title", HighlightGroup::Heading)]
+    );
+    assert_eq!(spans("#hashtag"), vec![]);
+    assert_eq!(spans("####### seven"), vec![]);
+    assert_eq!(
+        spans("> quoted *text*"),
+        vec![("> quoted *text*", HighlightGroup::Quote)]
+    );
+    assert_eq!(spans("- item"), vec![("-", HighlightGroup::ListMarker)]);
+    assert_eq!(
+        spans("  12. item with `code`"),
+        vec![
+            ("12.", HighlightGroup::ListMarker),
+            ("`code`", HighlightGroup::Code)
+        ]
+    );
+    assert_eq!(spans("---"), vec![]);
+    assert_eq!(spans("-not a list"), vec![]);
+}
+
+#[test]
+fn test_highlight_inline_elements() {
+    assert_eq!(
+        spans("Some *emphasis*, **strong** and __strong__ text"),
+        vec![
+            ("*emphasis*", HighlightGroup::Emphasis),
+            ("**strong**", HighlightGroup::Strong),
+            ("__strong__", HighlightGroup::Strong),
+        ]
+    );
+    assert_eq!(
+        spans("a `code *span*` and ``a ` b``"),
+        vec![
+            ("`code *span*`", HighlightGroup::Code),
+            ("``a ` b``", HighlightGroup::Code),
+        ]
+    );
+    assert_eq!(
+        spans("see [the docs](https://docs.rs), ![logo](logo.png) or <https://bo.rs>"),
+        vec![
+            ("[the docs](https://docs.rs)", HighlightGroup::Link),
+            ("![logo](logo.png)", HighlightGroup::Link),
+            ("<https://bo.rs>", HighlightGroup::Link),
+        ]
+    );
+    assert_eq!(
+        spans("Ünïcode *émphasis* 🎉 **strông**"),
+        vec![
+            ("*émphasis*", HighlightGroup::Emphasis),
+            ("**strông**", HighlightGroup::Strong),
+        ]
+    );
+}
+
+#[test]
+fn test_unmatched_or_escaped_delimiters_are_not_highlighted() {
+    assert_eq!(spans("snake_case_name and 2 * 3 * 4"), vec![]);
+    assert_eq!(spans("\\*not emphasis\\* and `unclosed"), vec![]);
+    assert_eq!(spans("[not a link] (here)"), vec![]);
+    assert_eq!(spans("**unclosed strong"), vec![]);
+}
+
+#[test]
+fn test_fenced_code_block_spans_multiple_lines() {
+    let (spans, state) = highlight_line("```rust", LineState::Normal);
+    assert_eq!(spans, vec![Span::new(0..7, HighlightGroup::Code)]);
+    assert_eq!(
+        state,
+        LineState::Fence {
+            marker: '`',
+            len: 3
+        }
+    );
+
+    let (spans, state) = highlight_line("# not a heading", state);
+    assert_eq!(spans, vec![Span::new(0..15, HighlightGroup::Code)]);
+    assert_eq!(
+        state,
+        LineState::Fence {
+            marker: '`',
+            len: 3
+        }
+    );
+
+    // a fence is only closed by the same marker, at least as long as the opening one
+    let (_, state) = highlight_line("~~~", state);
+    assert_eq!(
+        state,
+        LineState::Fence {
+            marker: '`',
+            len: 3
+        }
+    );
+    let (spans, state) = highlight_line("````", state);
+    assert_eq!(spans, vec![Span::new(0..4, HighlightGroup::Code)]);
+    assert_eq!(state, LineState::Normal);
+
+    // backticks in the info string make it an inline code span
+    let (_, state) = highlight_line("```inline```", LineState::Normal);
+    assert_eq!(state, LineState::Normal);
+}
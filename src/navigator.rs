@@ -1,6 +1,10 @@
-use crate::{Document, LineNumber, Position, Row, RowIndex, ViewportOffset};
+use crate::{
+    ByteIndex, Document, GraphemeIndex, LineNumber, Position, Row, RowIndex, ViewportOffset,
+};
 use std::cmp;
 use std::collections::HashMap;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
 
 fn matching_closing_symbols() -> HashMap<&'static str, &'static str> {
     [("'", "'"), ("\"", "\""), ("{", "}"), ("(", ")"), ("[", "]")]
@@ -15,24 +19,103 @@ fn matching_opening_symbols() -> HashMap<&'static str, &'static str> {
         .copied()
         .collect()
 }
+/// Abbreviations which period doesn't end a sentence, even when followed by
+/// a capitalized word (ex: ``Dr. Watson``)
+const ABBREVIATIONS: [&str; 12] = [
+    "cf", "dr", "e.g", "fig", "i.e", "jr", "mr", "mrs", "ms", "prof", "sr", "vs",
+];
+/// Abbreviations that can also end a sentence (ex: "The answer was no."), and are only
+/// considered as such when followed by a number (``No. 5``) or by a name (``St. Louis``)
+const AMBIGUOUS_ABBREVIATIONS: [(&str, bool); 3] = [("mt", false), ("no", true), ("st", false)];
+
+/// Position in the document, made of a row index and the index of a grapheme in that row
+pub type DocumentPosition = (RowIndex, GraphemeIndex);
+
 #[derive(PartialEq)]
 pub enum Boundary {
     Start,
     End,
 }
 
+/// What word motions move over: a ``word`` is made of letters, digits and
+/// underscores, or of punctuation, while a ``WORD`` is any sequence of
+/// non-blank characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordKind {
+    Word,
+    BigWord,
+}
+
+/// In-line search for a grapheme (``f``, ``F``, ``t`` and ``T``), remembered
+/// so that it can be repeated with ``;`` and ``,``
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharSearch {
+    pub target: String,
+    pub forward: bool,
+    /// Whether the cursor stops right before the target (``t``/``T``) instead of on it
+    pub till: bool,
+}
+
+impl CharSearch {
+    #[must_use]
+    pub fn new(command: char, target: &str) -> Self {
+        Self {
+            target: String::from(target),
+            forward: matches!(command, 'f' | 't'),
+            till: matches!(command, 't' | 'T'),
+        }
+    }
+
+    /// Return the same search, going in the opposite direction (``,``)
+    #[must_use]
+    pub fn reversed(&self) -> Self {
+        Self {
+            forward: !self.forward,
+            ..self.clone()
+        }
+    }
+}
+
+/// Class of a Unicode word segment, adjacent segments of the same mergeable
+/// class being part of the same word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SegmentClass {
+    Blank,
+    Punctuation,
+    Word,
+    NonBlank,
+}
+
+impl SegmentClass {
+    fn of(segment: &str, kind: WordKind) -> Self {
+        if segment.chars().all(char::is_whitespace) {
+            Self::Blank
+        } else if kind == WordKind::BigWord {
+            Self::NonBlank
+        } else if segment
+            .chars()
+            .all(|c| !c.is_alphanumeric() && !c.is_whitespace())
+        {
+            Self::Punctuation
+        } else {
+            Self::Word
+        }
+    }
+
+    fn is_mergeable(self) -> bool {
+        self != Self::Word
+    }
+}
+
 #[derive(Debug)]
 pub struct Navigator {}
 
 impl Navigator {
     #[must_use]
-    pub fn find_index_of_first_non_whitespace(row: &Row) -> Option<usize> {
-        for (x, character) in row.string.chars().enumerate() {
-            if !character.is_whitespace() {
-                return Some(x);
-            }
-        }
-        None
+    pub fn find_index_of_first_non_whitespace(row: &Row) -> Option<GraphemeIndex> {
+        row.graphemes()
+            .position(|grapheme| !grapheme.chars().all(char::is_whitespace))
+            .map(GraphemeIndex::new)
     }
 
     /// Return the index of the matching closing symbol (eg } for {, etc)
@@ -44,31 +127,33 @@ impl Navigator {
         current_position: &Position,
         offset: &ViewportOffset,
     ) -> Option<Position> {
-        let initial_col_position = current_position.x.saturating_add(offset.columns);
+        let initial_col_position = current_position.x.add(offset.columns);
         let initial_row_position = current_position.y.saturating_add(offset.rows);
         let symbol = document
             .get_row(RowIndex::new(initial_row_position))
             .unwrap()
-            .nth_grapheme(current_position.x.saturating_add(offset.columns));
+            .nth_grapheme(initial_col_position);
         let mut stack = vec![symbol];
         let mut current_opening_symbol = symbol;
         matching_closing_symbols().get(&symbol)?;
         for y in initial_row_position..document.num_rows() {
             let current_row = document.get_row(RowIndex::new(y)).unwrap();
             let start_x = if y == initial_row_position {
-                initial_col_position.saturating_add(1)
+                initial_col_position.next().value
             } else {
                 0
             };
-            for index in start_x..current_row.len() {
-                let c = current_row.nth_grapheme(index);
+            for (index, c) in current_row.graphemes().enumerate().skip(start_x) {
                 if c == *matching_closing_symbols()
                     .get(&current_opening_symbol)
                     .unwrap()
                 {
                     stack.pop();
                     if stack.is_empty() {
-                        return Some(Position { x: index, y });
+                        return Some(Position {
+                            x: GraphemeIndex::new(index),
+                            y,
+                        });
                     }
                     current_opening_symbol = *stack.last().unwrap();
                 } else if matching_closing_symbols().contains_key(&c) {
@@ -89,31 +174,34 @@ impl Navigator {
         current_position: &Position,
         offset: &ViewportOffset,
     ) -> Option<Position> {
-        let initial_col_position = current_position.x.saturating_add(offset.columns);
+        let initial_col_position = current_position.x.add(offset.columns);
         let initial_row_position = current_position.y.saturating_add(offset.rows);
         let symbol = document
             .get_row(RowIndex::new(initial_row_position))
             .unwrap()
-            .nth_grapheme(current_position.x.saturating_add(offset.columns));
+            .nth_grapheme(initial_col_position);
         let mut stack = vec![symbol];
         let mut current_closing_symbol = symbol;
         matching_opening_symbols().get(&symbol)?;
         for y in (0..=initial_row_position).rev() {
             let current_row = document.get_row(RowIndex::new(y)).unwrap();
             let start_x = if y == initial_row_position {
-                initial_col_position
+                initial_col_position.value
             } else {
                 current_row.len()
             };
-            for index in (0..start_x).rev() {
-                let c = current_row.nth_grapheme(index);
+            let graphemes: Vec<&str> = current_row.graphemes().take(start_x).collect();
+            for (index, &c) in graphemes.iter().enumerate().rev() {
                 if c == *matching_opening_symbols()
                     .get(&current_closing_symbol)
                     .unwrap()
                 {
                     stack.pop();
                     if stack.is_empty() {
-                        return Some(Position { x: index, y });
+                        return Some(Position {
+                            x: GraphemeIndex::new(index),
+                            y,
+                        });
                     }
                     current_closing_symbol = *stack.last().unwrap();
                 } else if matching_opening_symbols().contains_key(&c) {
@@ -159,57 +247,463 @@ impl Navigator {
         }
     }
 
-    #[allow(clippy::suspicious_operation_groupings)]
+    /// Return the ranges of grapheme indices covered by the words of the row.
+    ///
+    /// Words are delimited following the Unicode word boundaries (UAX #29), so
+    /// that accented letters, scripts without ASCII letters and emoji are handled,
+    /// and each CJK ideograph is its own word. As in vim, a sequence of ASCII
+    /// punctuation characters (ex: ``::``) is a single word. ``WORD``s are the
+    /// sequences of non-blank characters.
     #[must_use]
-    // mirrorred over the look and feel of vim
-    // Note: this assumes working on char, and I _think_ is is shaky at best
-    // as we start supporting unicde, as an unicode is made of code points, each
-    // of which is internally represented by a char, so this has no change of _really_ working well.
-    // we should drop that function and try to rely on the string.split_word_bounds
-    // method implemented in the unicode-segmentation crate. However, that crate seems
-    // to drop all characters (eg: heart) that isn't alphabetic.
-    pub fn is_word_delimiter(char1: char, char2: char) -> bool {
-        if char2.is_whitespace() || char1 == '_' || char2 == '_' {
-            return false;
+    pub fn word_spans(row: &Row, kind: WordKind) -> Vec<Range<usize>> {
+        let mut spans: Vec<(Range<usize>, SegmentClass)> = vec![];
+        let mut start = 0;
+        for segment in row.string.split_word_bounds() {
+            let end = start + segment.graphemes(true).count();
+            let class = SegmentClass::of(segment, kind);
+            match spans.last_mut() {
+                _ if class == SegmentClass::Blank => (),
+                Some((span, last_class))
+                    if span.end == start && *last_class == class && class.is_mergeable() =>
+                {
+                    span.end = end;
+                }
+                _ => spans.push((start..end, class)),
+            }
+            start = end;
         }
-        (char1.is_alphabetic() && !char2.is_alphabetic())
-            || (!char1.is_alphabetic() && char2.is_alphabetic())
-            || (char1.is_alphanumeric() && char2.is_ascii_punctuation())
-            || (char1.is_whitespace() && char2.is_alphanumeric())
+        spans.into_iter().map(|(span, _)| span).collect()
     }
 
+    /// Return the position of the start of the next word (``w``/``W``). Empty
+    /// lines count as words, and the end of the document is returned when there
+    /// is no next word.
     #[must_use]
-    pub fn find_index_of_next_or_previous_word(
-        current_row: &Row,
-        current_x_position: usize,
-        boundary: &Boundary,
-    ) -> usize {
-        let current_x_index = current_x_position.saturating_add(1);
-        match boundary {
-            Boundary::End => {
-                let mut current_char = current_row.nth_char(current_x_position);
-                for (i, next_char) in current_row.chars().skip(current_x_index).enumerate() {
-                    if Self::is_word_delimiter(current_char, next_char) {
-                        return current_x_index.saturating_add(i);
-                    }
-                    current_char = next_char;
-                }
-                current_row.len().saturating_sub(1)
-            }
-            Boundary::Start => {
-                for i in (1..current_x_index.saturating_sub(1)).rev() {
-                    let current_char = current_row.nth_char(i);
-                    let prev_char = current_row.nth_char(i.saturating_sub(1));
-                    if Self::is_word_delimiter(prev_char, current_char) {
-                        return i;
-                    }
+    pub fn find_start_of_next_word(
+        document: &Document,
+        row_index: RowIndex,
+        x: GraphemeIndex,
+        kind: WordKind,
+    ) -> DocumentPosition {
+        let mut y = row_index.value;
+        while let Some(row) = document.get_row(RowIndex::new(y)) {
+            let spans = Self::word_spans(row, kind);
+            let next = if y == row_index.value {
+                spans.iter().find(|span| span.start > x.value)
+            } else if row.is_empty() {
+                return (RowIndex::new(y), GraphemeIndex::new(0));
+            } else {
+                spans.first()
+            };
+            if let Some(span) = next {
+                return (RowIndex::new(y), GraphemeIndex::new(span.start));
+            }
+            y += 1;
+        }
+        Self::end_of_document(document)
+    }
+
+    /// Return the position of the start of the previous word (``b``/``B``),
+    /// empty lines counting as words
+    #[must_use]
+    pub fn find_start_of_previous_word(
+        document: &Document,
+        row_index: RowIndex,
+        x: GraphemeIndex,
+        kind: WordKind,
+    ) -> DocumentPosition {
+        for y in (0..=row_index.value).rev() {
+            let Some(row) = document.get_row(RowIndex::new(y)) else {
+                continue;
+            };
+            let spans = Self::word_spans(row, kind);
+            let previous = if y == row_index.value {
+                spans.iter().rev().find(|span| span.start < x.value)
+            } else if row.is_empty() {
+                return (RowIndex::new(y), GraphemeIndex::new(0));
+            } else {
+                spans.last()
+            };
+            if let Some(span) = previous {
+                return (RowIndex::new(y), GraphemeIndex::new(span.start));
+            }
+        }
+        (RowIndex::new(0), GraphemeIndex::new(0))
+    }
+
+    /// Return the position of the end of the next word (``e``/``E``), that is
+    /// the last grapheme of the first word ending after the cursor. Empty
+    /// lines are skipped.
+    #[must_use]
+    pub fn find_end_of_next_word(
+        document: &Document,
+        row_index: RowIndex,
+        x: GraphemeIndex,
+        kind: WordKind,
+    ) -> DocumentPosition {
+        let mut y = row_index.value;
+        while let Some(row) = document.get_row(RowIndex::new(y)) {
+            let next = Self::word_spans(row, kind)
+                .into_iter()
+                .find(|span| y > row_index.value || span.end - 1 > x.value);
+            if let Some(span) = next {
+                return (RowIndex::new(y), GraphemeIndex::new(span.end - 1));
+            }
+            y += 1;
+        }
+        Self::end_of_document(document)
+    }
+
+    /// Return the position of the end of the previous word (``ge``/``gE``),
+    /// empty lines counting as words
+    #[must_use]
+    pub fn find_end_of_previous_word(
+        document: &Document,
+        row_index: RowIndex,
+        x: GraphemeIndex,
+        kind: WordKind,
+    ) -> DocumentPosition {
+        for y in (0..=row_index.value).rev() {
+            let Some(row) = document.get_row(RowIndex::new(y)) else {
+                continue;
+            };
+            let spans = Self::word_spans(row, kind);
+            let previous = if y == row_index.value {
+                spans.iter().rev().find(|span| span.end - 1 < x.value)
+            } else if row.is_empty() {
+                return (RowIndex::new(y), GraphemeIndex::new(0));
+            } else {
+                spans.last()
+            };
+            if let Some(span) = previous {
+                return (RowIndex::new(y), GraphemeIndex::new(span.end - 1));
+            }
+        }
+        (RowIndex::new(0), GraphemeIndex::new(0))
+    }
+
+    /// Return the index at which the cursor lands after searching the row for
+    /// the ``times``-th occurrence of the target grapheme, or None if there
+    /// aren't as many. When a ``t``/``T`` search is ``repeated``, an occurrence
+    /// right next to the cursor is skipped, so that the cursor doesn't stay
+    /// stuck before it.
+    #[must_use]
+    pub fn find_grapheme(
+        row: &Row,
+        x: GraphemeIndex,
+        search: &CharSearch,
+        times: usize,
+        repeated: bool,
+    ) -> Option<GraphemeIndex> {
+        let skip = usize::from(search.till && repeated);
+        let matches = row
+            .graphemes()
+            .enumerate()
+            .filter(|(_, grapheme)| *grapheme == search.target)
+            .map(|(index, _)| index);
+        if search.forward {
+            let index = matches
+                .filter(|index| *index > x.value + skip)
+                .nth(times.saturating_sub(1))?;
+            Some(GraphemeIndex::new(index - usize::from(search.till)))
+        } else {
+            let index = matches
+                .filter(|index| index + skip < x.value)
+                .collect::<Vec<usize>>()
+                .into_iter()
+                .rev()
+                .nth(times.saturating_sub(1))?;
+            Some(GraphemeIndex::new(index + usize::from(search.till)))
+        }
+    }
+
+    /// Return the position of the start of the next sentence (``)``). Empty
+    /// lines count as sentences, and the end of the document is returned when
+    /// there is no next sentence.
+    #[must_use]
+    pub fn find_start_of_next_sentence(
+        document: &Document,
+        row_index: RowIndex,
+        x: GraphemeIndex,
+    ) -> DocumentPosition {
+        let mut y = row_index.value;
+        while y < document.num_rows() {
+            let block = Self::block_at(document, y);
+            let next = Self::sentence_stops(document, &block)
+                .into_iter()
+                .find(|stop| *stop > (row_index, x));
+            if let Some(stop) = next {
+                return stop;
+            }
+            y = block.end;
+        }
+        Self::end_of_document(document)
+    }
+
+    /// Return the position of the start of the current sentence, or of the
+    /// previous one if the cursor is already at the start of a sentence (``(``).
+    /// Empty lines count as sentences.
+    #[must_use]
+    pub fn find_start_of_previous_sentence(
+        document: &Document,
+        row_index: RowIndex,
+        x: GraphemeIndex,
+    ) -> DocumentPosition {
+        let mut y = cmp::min(row_index.value, document.num_rows().saturating_sub(1));
+        loop {
+            let block = Self::block_at(document, y);
+            let previous = Self::sentence_stops(document, &block)
+                .into_iter()
+                .rev()
+                .find(|stop| *stop < (row_index, x));
+            if let Some(stop) = previous {
+                return stop;
+            }
+            if block.start == 0 {
+                return (RowIndex::new(0), GraphemeIndex::new(0));
+            }
+            y = block.start - 1;
+        }
+    }
+
+    /// Return the start and (excluded) end positions of the sentence under the
+    /// cursor, and of the ``count - 1`` following ones in the same paragraph.
+    /// The inner sentence (``is``) excludes the whitespace surrounding it, while
+    /// a sentence (``as``) includes its trailing whitespace, or its leading one
+    /// if it has none.
+    #[must_use]
+    pub fn find_sentence(
+        document: &Document,
+        row_index: RowIndex,
+        x: GraphemeIndex,
+        around: bool,
+        count: usize,
+    ) -> Option<(DocumentPosition, DocumentPosition)> {
+        if document.get_row(row_index)?.is_whitespace() {
+            return None;
+        }
+        let paragraph = Paragraph::new(document, Self::block_at(document, row_index.value));
+        let sentences = paragraph.sentences();
+        let offset = paragraph.byte_offset(document, (row_index, x));
+        let first = sentences
+            .iter()
+            .rposition(|sentence| sentence.start <= offset)
+            .unwrap_or(0);
+        let last = cmp::min(first + count.max(1) - 1, sentences.len() - 1);
+        let text = paragraph.text.as_str();
+        let start =
+            sentences[first].start + Self::leading_whitespace_len(&text[sentences[first].clone()]);
+        let inner_end = sentences[last].start + text[sentences[last].clone()].trim_end().len();
+        let (start, end) = if !around {
+            (start, inner_end)
+        } else if sentences[last].end > inner_end {
+            (start, sentences[last].end)
+        } else if first > 0 {
+            // no trailing whitespace: include the whitespace preceding the sentence
+            let previous = &sentences[first - 1];
+            (
+                previous.start + text[previous.clone()].trim_end().len(),
+                inner_end,
+            )
+        } else {
+            (start, inner_end)
+        };
+        Some((
+            paragraph.position(document, start),
+            paragraph.position(document, end),
+        ))
+    }
+
+    /// Return the number of sentences in the document, excluding the empty lines
+    #[must_use]
+    pub fn count_sentences(document: &Document) -> usize {
+        let mut count = 0;
+        let mut y = 0;
+        while y < document.num_rows() {
+            let block = Self::block_at(document, y);
+            if !Self::is_blank(document, y) {
+                count += Paragraph::new(document, block.clone()).sentences().len();
+            }
+            y = block.end;
+        }
+        count
+    }
+
+    /// Return the paragraphs containing the provided rows, the blank rows
+    /// separating them being skipped
+    #[must_use]
+    pub fn paragraphs(document: &Document, rows: Range<usize>) -> Vec<Paragraph> {
+        let mut paragraphs = vec![];
+        let mut y = rows.start;
+        while y < rows.end.min(document.num_rows()) {
+            let block = Self::block_at(document, y);
+            y = block.end;
+            if !Self::is_blank(document, block.start) {
+                paragraphs.push(Paragraph::new(document, block));
+            }
+        }
+        paragraphs
+    }
+
+    /// Return the positions at which the sentences of the block start, an
+    /// empty block being a single sentence starting at its first row
+    fn sentence_stops(document: &Document, block: &Range<usize>) -> Vec<DocumentPosition> {
+        if Self::is_blank(document, block.start) {
+            return vec![(RowIndex::new(block.start), GraphemeIndex::new(0))];
+        }
+        let paragraph = Paragraph::new(document, block.clone());
+        paragraph
+            .sentences()
+            .iter()
+            .map(|sentence| {
+                let start = sentence.start
+                    + Self::leading_whitespace_len(&paragraph.text[sentence.clone()]);
+                paragraph.position(document, start)
+            })
+            .collect()
+    }
+
+    /// Return the rows of the paragraph, or of the sequence of blank lines,
+    /// containing the provided row
+    fn block_at(document: &Document, y: usize) -> Range<usize> {
+        let blank = Self::is_blank(document, y);
+        let mut start = y;
+        while start > 0 && Self::is_blank(document, start - 1) == blank {
+            start -= 1;
+        }
+        let mut end = y + 1;
+        while end < document.num_rows() && Self::is_blank(document, end) == blank {
+            end += 1;
+        }
+        start..end
+    }
+
+    fn is_blank(document: &Document, y: usize) -> bool {
+        document
+            .get_row(RowIndex::new(y))
+            .is_none_or(Row::is_whitespace)
+    }
+
+    fn leading_whitespace_len(text: &str) -> usize {
+        text.len() - text.trim_start().len()
+    }
+
+    /// Return the position of the last grapheme of the document
+    fn end_of_document(document: &Document) -> DocumentPosition {
+        let row_index = RowIndex::from(document.last_line_number());
+        let len = document.get_row(row_index).map_or(0, Row::len);
+        (row_index, GraphemeIndex::new(len.saturating_sub(1)))
+    }
+}
+
+/// The text of the rows of a paragraph, joined with spaces so that the
+/// sentences spanning several rows are segmented as a whole
+pub struct Paragraph {
+    first_row: usize,
+    pub text: String,
+    /// Byte offset of the start of each row in the text
+    row_starts: Vec<usize>,
+}
+
+impl Paragraph {
+    fn new(document: &Document, rows: Range<usize>) -> Self {
+        let mut text = String::new();
+        let mut row_starts = vec![];
+        for y in rows.clone() {
+            if y > rows.start {
+                text.push(' ');
+            }
+            row_starts.push(text.len());
+            if let Some(row) = document.get_row(RowIndex::new(y)) {
+                text.push_str(&row.string);
+            }
+        }
+        Self {
+            first_row: rows.start,
+            text,
+            row_starts,
+        }
+    }
+
+    /// Return the byte ranges of the sentences, including their trailing
+    /// whitespace. The sentence boundaries follow the Unicode rules (UAX #29),
+    /// which handle ellipses and closing quotes or brackets following the final
+    /// punctuation, with the sentences ending with an abbreviation being merged
+    /// with the following one.
+    #[must_use]
+    pub fn sentences(&self) -> Vec<Range<usize>> {
+        let mut sentences: Vec<Range<usize>> = vec![];
+        for (start, sentence) in self.text.split_sentence_bound_indices() {
+            let range = start..start + sentence.len();
+            match sentences.last_mut() {
+                Some(previous)
+                    if ends_with_abbreviation(&self.text[previous.clone()], sentence) =>
+                {
+                    previous.end = range.end;
                 }
-                0
+                _ => sentences.push(range),
             }
         }
+        sentences
+    }
+
+    /// Return the byte offset of the document position in the text
+    fn byte_offset(&self, document: &Document, position: DocumentPosition) -> usize {
+        let (row_index, x) = position;
+        let row_start = self.row_starts[row_index.value - self.first_row];
+        let byte_index = document
+            .get_row(row_index)
+            .map_or(0, |row| row.byte_index(x).value);
+        row_start + byte_index
+    }
+
+    /// Return the document position of the byte offset in the text, the space
+    /// joining two rows being located at the end of the first one
+    #[must_use]
+    pub fn position(&self, document: &Document, offset: usize) -> DocumentPosition {
+        let index = self
+            .row_starts
+            .partition_point(|row_start| *row_start <= offset)
+            .saturating_sub(1);
+        let row_index = RowIndex::new(self.first_row + index);
+        let x = document
+            .get_row(row_index)
+            .map_or(GraphemeIndex::new(0), |row| {
+                row.grapheme_index(ByteIndex::new(offset - self.row_starts[index]))
+            });
+        (row_index, x)
     }
 }
 
+/// Return whether the sentence ends with an abbreviation, such as ``Dr.``
+/// or ``e.g.``, in which case it most likely doesn't end there, depending on
+/// the following text for the ambiguous abbreviations
+fn ends_with_abbreviation(sentence: &str, following: &str) -> bool {
+    let last_word = sentence
+        .split_whitespace()
+        .last()
+        .unwrap_or_default()
+        .trim_start_matches(|c: char| !c.is_alphanumeric());
+    let Some(word) = last_word.strip_suffix('.') else {
+        return false;
+    };
+    let word = word.to_lowercase();
+    let next_char = following.trim_start().chars().next();
+    ABBREVIATIONS.contains(&word.as_str())
+        || AMBIGUOUS_ABBREVIATIONS
+            .iter()
+            .any(|&(abbreviation, before_number)| {
+                abbreviation == word
+                    && next_char.is_some_and(|c| {
+                        if before_number {
+                            c.is_ascii_digit()
+                        } else {
+                            c.is_uppercase()
+                        }
+                    })
+            })
+}
+
 #[cfg(test)]
 #[path = "./navigator_test.rs"]
 mod navigator_test;
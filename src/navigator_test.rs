@@ -1,4 +1,7 @@
-use crate::{Boundary, Document, LineNumber, Navigator, Position, Row, ViewportOffset};
+use crate::{
+    Boundary, CharSearch, Document, DocumentPosition, GraphemeIndex, LineNumber, Navigator,
+    Position, Row, RowIndex, ViewportOffset, WordKind,
+};
 use std::path::PathBuf;
 
 fn test_document() -> Document {
@@ -12,19 +15,11 @@ fn test_document() -> Document {
     )
 }
 
-fn test_row_word_nav() -> Row {
-    Row::from("const STATUS_FG_COLOR: color::Rgb = color::Rgb(63, 63, 63);")
-}
-
-fn test_row_word_nav_unicode() -> Row {
-    Row::from("I \u{9ec} unicode!")
-}
-
 #[test]
 fn test_find_index_of_first_non_whitespace() {
     assert_eq!(
         Navigator::find_index_of_first_non_whitespace(&Row::from("  test")),
-        Some(2)
+        Some(GraphemeIndex::new(2))
     );
 }
 
@@ -34,13 +29,19 @@ fn test_find_matching_closing_symbol() {
     assert_eq!(
         Navigator::find_matching_closing_symbol(
             &doc,
-            &Position { x: 7, y: 0 },
+            &Position {
+                x: GraphemeIndex::new(7),
+                y: 0
+            },
             &ViewportOffset {
                 columns: 0,
                 rows: 0
             },
         ),
-        Some(Position { x: 8, y: 0 })
+        Some(Position {
+            x: GraphemeIndex::new(8),
+            y: 0
+        })
     );
 }
 #[test]
@@ -56,13 +57,19 @@ fn test_find_matching_closing_symbol_multiline() {
     assert_eq!(
         Navigator::find_matching_closing_symbol(
             &doc,
-            &Position { x: 10, y: 0 },
+            &Position {
+                x: GraphemeIndex::new(10),
+                y: 0
+            },
             &ViewportOffset {
                 columns: 0,
                 rows: 0
             },
         ),
-        Some(Position { x: 0, y: 2 })
+        Some(Position {
+            x: GraphemeIndex::new(0),
+            y: 2
+        })
     );
 }
 
@@ -72,7 +79,10 @@ fn test_find_matching_closing_symbol_no_match() {
     assert_eq!(
         Navigator::find_matching_closing_symbol(
             &doc,
-            &Position { x: 7, y: 0 },
+            &Position {
+                x: GraphemeIndex::new(7),
+                y: 0
+            },
             &ViewportOffset {
                 columns: 0,
                 rows: 0
@@ -88,13 +98,19 @@ fn test_find_matching_opening_symbol() {
     assert_eq!(
         Navigator::find_matching_opening_symbol(
             &doc,
-            &Position { x: 11, y: 0 },
+            &Position {
+                x: GraphemeIndex::new(11),
+                y: 0
+            },
             &ViewportOffset {
                 columns: 0,
                 rows: 0
             }
         ),
-        Some(Position { x: 10, y: 0 })
+        Some(Position {
+            x: GraphemeIndex::new(10),
+            y: 0
+        })
     );
 }
 
@@ -111,13 +127,19 @@ fn test_find_matching_opening_symbol_multiline() {
     assert_eq!(
         Navigator::find_matching_opening_symbol(
             &doc,
-            &Position { x: 0, y: 2 },
+            &Position {
+                x: GraphemeIndex::new(0),
+                y: 2
+            },
             &ViewportOffset {
                 columns: 0,
                 rows: 0
             }
         ),
-        Some(Position { x: 10, y: 0 })
+        Some(Position {
+            x: GraphemeIndex::new(10),
+            y: 0
+        })
     );
 }
 
@@ -127,7 +149,10 @@ fn test_find_matching_opening_symbol_no_match() {
     assert_eq!(
         Navigator::find_matching_opening_symbol(
             &doc,
-            &Position { x: 7, y: 0 },
+            &Position {
+                x: GraphemeIndex::new(7),
+                y: 0
+            },
             &ViewportOffset {
                 columns: 0,
                 rows: 0
@@ -193,128 +218,384 @@ fn test_find_line_number_of_start_of_paragraph_when_at_first_line() {
     );
 }
 
+/// Return the (row index, grapheme index) positions the motion goes through,
+/// starting from the start of the document, until it stops moving
+fn motion_stops(
+    document: &Document,
+    motion: fn(&Document, RowIndex, GraphemeIndex, WordKind) -> DocumentPosition,
+    start: (usize, usize),
+    kind: WordKind,
+) -> Vec<(usize, usize)> {
+    let mut stops = vec![];
+    let mut position = (RowIndex::new(start.0), GraphemeIndex::new(start.1));
+    loop {
+        let next = motion(document, position.0, position.1, kind);
+        if next == position {
+            return stops;
+        }
+        stops.push((next.0.value, next.1.value));
+        position = next;
+    }
+}
+
+fn document_from(lines: &[&str]) -> Document {
+    Document::new(
+        lines.iter().map(|line| Row::from(*line)).collect(),
+        PathBuf::from("test.txt"),
+    )
+}
+
 #[test]
-fn test_is_word_delimiter_false() {
-    assert!(!Navigator::is_word_delimiter('a', 'a'));
-    assert!(!Navigator::is_word_delimiter('a', ' '));
-    assert!(!Navigator::is_word_delimiter('a', '_'));
-    assert!(!Navigator::is_word_delimiter('_', 'a'));
-    assert!(!Navigator::is_word_delimiter(':', ':'));
+fn test_word_spans() {
+    let spans = |text: &str, kind: WordKind| Navigator::word_spans(&Row::from(text), kind);
+    assert_eq!(
+        spans("const STATUS_FG_COLOR: color::Rgb", WordKind::Word),
+        vec![0..5, 6..21, 21..22, 23..28, 28..30, 30..33]
+    );
+    assert_eq!(
+        spans("const STATUS_FG_COLOR: color::Rgb", WordKind::BigWord),
+        vec![0..5, 6..22, 23..33]
+    );
+    // the spans are grapheme indices, and combining accents are part of the word
+    assert_eq!(
+        spans("cre\u{300}me d\u{e9}j\u{e0}", WordKind::Word),
+        vec![0..5, 6..10]
+    );
+    // cyrillic
+    assert_eq!(
+        spans(
+            "\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442}, \u{43c}\u{438}\u{440}!",
+            WordKind::Word
+        ),
+        vec![0..6, 6..7, 8..11, 11..12]
+    );
+    // each CJK ideograph is a word, while emoji are symbols, grouped like punctuation
+    assert_eq!(
+        spans("\u{65e5}\u{672c} \u{1f600}\u{1f600}", WordKind::Word),
+        vec![0..1, 1..2, 3..5]
+    );
+    // non-ASCII punctuation, such as French quotes and dashes, isn't part of the words
+    assert_eq!(
+        spans(
+            "\u{ab}\u{a0}Oui\u{a0}\u{bb}\u{2014}non\u{2026} \u{bf}Qu\u{e9}?",
+            WordKind::Word
+        ),
+        vec![0..1, 2..5, 6..8, 8..11, 11..12, 13..14, 14..17, 17..18]
+    );
+    assert!(spans("   ", WordKind::Word).is_empty());
 }
 
 #[test]
-fn test_is_word_delimiter_true() {
-    assert!(Navigator::is_word_delimiter('a', ':'));
-    assert!(Navigator::is_word_delimiter(':', 'a'));
-    assert!(Navigator::is_word_delimiter(' ', 'a'));
-    assert!(Navigator::is_word_delimiter('"', 'a'));
-    assert!(Navigator::is_word_delimiter('a', '"'));
+fn test_find_start_of_next_word() {
+    let document = document_from(&[
+        "const STATUS_FG_COLOR: color::Rgb",
+        "",
+        "   ",
+        "  d\u{e9}j\u{e0} vu",
+    ]);
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_start_of_next_word,
+            (0, 0),
+            WordKind::Word
+        ),
+        // the empty line is a stop, but not the blank one
+        vec![
+            (0, 6),
+            (0, 21),
+            (0, 23),
+            (0, 28),
+            (0, 30),
+            (1, 0),
+            (3, 2),
+            (3, 7),
+            (3, 8)
+        ]
+    );
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_start_of_next_word,
+            (0, 0),
+            WordKind::BigWord
+        ),
+        vec![(0, 6), (0, 23), (1, 0), (3, 2), (3, 7), (3, 8)]
+    );
 }
 
 #[test]
-fn test_is_word_delimited_unicode() {
-    assert!(Navigator::is_word_delimiter(' ', '\u{9ec}'));
+fn test_find_start_of_next_word_over_non_ascii_punctuation() {
+    let document = document_from(&["\u{ab} Bonjour \u{bb}\u{2014}dit-il\u{2026} \u{bf}Qu\u{e9}?"]);
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_start_of_next_word,
+            (0, 0),
+            WordKind::Word
+        ),
+        vec![
+            (0, 2),
+            (0, 10),
+            (0, 12),
+            (0, 15),
+            (0, 16),
+            (0, 18),
+            (0, 20),
+            (0, 21),
+            (0, 24)
+        ]
+    );
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_end_of_next_word,
+            (0, 0),
+            WordKind::Word
+        ),
+        vec![
+            (0, 8),
+            (0, 11),
+            (0, 14),
+            (0, 15),
+            (0, 17),
+            (0, 18),
+            (0, 20),
+            (0, 23),
+            (0, 24)
+        ]
+    );
 }
 
 #[test]
-fn test_find_index_of_next_word() {
-    let test_cases: Vec<(usize, usize)> = vec![
-        // const STATUS_FG_COLOR
-        // 0.....6
-        (0, 6),
-        // const STATUS_FG_COLOR: color::Rgb
-        //       6..............^21
-        (6, 21),
-        // const STATUS_FG_COLOR: color::Rgb
-        //                    21^.^23
-        (21, 23),
-        // const STATUS_FG_COLOR: color::Rgb
-        //                      23^....^26
-        (23, 28),
-        (58, 58), // EOL
-    ];
-    for (start_index, expected_next_word_start_index) in test_cases {
-        assert_eq!(
-            Navigator::find_index_of_next_or_previous_word(
-                &test_row_word_nav(),
-                start_index,
-                &Boundary::End
-            ),
-            expected_next_word_start_index
-        );
-    }
+fn test_find_start_of_previous_word() {
+    let document = document_from(&["I \u{9ec} unicode!", "", "  foo::bar"]);
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_start_of_previous_word,
+            (2, 9),
+            WordKind::Word
+        ),
+        vec![
+            (2, 7),
+            (2, 5),
+            (2, 2),
+            (1, 0),
+            (0, 11),
+            (0, 4),
+            (0, 2),
+            (0, 0)
+        ]
+    );
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_start_of_previous_word,
+            (2, 9),
+            WordKind::BigWord
+        ),
+        vec![(2, 2), (1, 0), (0, 4), (0, 2), (0, 0)]
+    );
 }
 
 #[test]
-fn test_find_index_of_next_word_with_unicode_chars() {
-    let test_cases: Vec<(usize, usize)> = vec![
-        // I * unicode!
-        // 0.2.4......^11
-        (0, 2),
-        // I * unicode!
-        // 0.2.4......^11
-        (2, 4),
-        (4, 11),
-    ];
-    for (start_index, expected_next_word_start_index) in test_cases {
-        assert_eq!(
-            Navigator::find_index_of_next_or_previous_word(
-                &test_row_word_nav_unicode(),
-                start_index,
-                &Boundary::End
-            ),
-            expected_next_word_start_index
-        );
-    }
+fn test_find_end_of_next_word() {
+    let document = document_from(&["caf\u{e9} au", "", "lait, merci"]);
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_end_of_next_word,
+            (0, 0),
+            WordKind::Word
+        ),
+        // empty lines are skipped
+        vec![(0, 3), (0, 6), (2, 3), (2, 4), (2, 10)]
+    );
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_end_of_next_word,
+            (0, 0),
+            WordKind::BigWord
+        ),
+        vec![(0, 3), (0, 6), (2, 4), (2, 10)]
+    );
 }
 
 #[test]
-fn test_find_index_of_previous_word() {
-    let test_cases: Vec<(usize, usize)> = vec![
-        // const STATUS_FG_COLOR
-        // 0.....6
-        (6, 0),
-        // const STATUS_FG_COLOR: color::Rgb
-        //       6..............^21
-        (21, 6),
-        // const STATUS_FG_COLOR: color::Rgb
-        //                    21^.^23
-        (23, 21),
-        // const STATUS_FG_COLOR: color::Rgb
-        //                      23^....^26
-        (28, 23),
-        (1, 0),
-        (0, 0),
-    ];
-    for (start_index, expected_next_word_start_index) in test_cases {
-        assert_eq!(
-            Navigator::find_index_of_next_or_previous_word(
-                &test_row_word_nav(),
-                start_index,
-                &Boundary::Start
-            ),
-            expected_next_word_start_index
-        );
+fn test_find_end_of_previous_word() {
+    let document = document_from(&["caf\u{e9} au", "", "lait, merci"]);
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_end_of_previous_word,
+            (2, 10),
+            WordKind::Word
+        ),
+        vec![(2, 4), (2, 3), (1, 0), (0, 6), (0, 3), (0, 0)]
+    );
+    assert_eq!(
+        motion_stops(
+            &document,
+            Navigator::find_end_of_previous_word,
+            (2, 10),
+            WordKind::BigWord
+        ),
+        vec![(2, 4), (1, 0), (0, 6), (0, 3), (0, 0)]
+    );
+}
+
+fn sentence_stops(
+    document: &Document,
+    motion: fn(&Document, RowIndex, GraphemeIndex) -> DocumentPosition,
+    start: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut stops = vec![];
+    let mut position = (RowIndex::new(start.0), GraphemeIndex::new(start.1));
+    loop {
+        let next = motion(document, position.0, position.1);
+        if next == position {
+            return stops;
+        }
+        stops.push((next.0.value, next.1.value));
+        position = next;
     }
 }
 
+fn sentence_text(document: &Document, start: (usize, usize), around: bool, count: usize) -> String {
+    Navigator::find_sentence(
+        document,
+        RowIndex::new(start.0),
+        GraphemeIndex::new(start.1),
+        around,
+        count,
+    )
+    .map(|(start, end)| document.text_between(start, end))
+    .unwrap_or_default()
+}
+
+fn find_grapheme(
+    row: &Row,
+    x: usize,
+    command: char,
+    target: &str,
+    times: usize,
+    repeated: bool,
+) -> Option<usize> {
+    let search = CharSearch::new(command, target);
+    Navigator::find_grapheme(row, GraphemeIndex::new(x), &search, times, repeated)
+        .map(|index| index.value)
+}
+
 #[test]
-fn test_find_index_of_previous_word_with_unicode() {
-    let test_cases: Vec<(usize, usize)> = vec![
-        // I * unicode!
-        // 0.2.4......^11
-        (11, 4),
-        (4, 2),
-        (2, 0),
-    ];
-    for (start_index, expected_next_word_start_index) in test_cases {
-        assert_eq!(
-            Navigator::find_index_of_next_or_previous_word(
-                &test_row_word_nav_unicode(),
-                start_index,
-                &Boundary::Start
-            ),
-            expected_next_word_start_index
-        );
-    }
+fn test_find_grapheme() {
+    let row = Row::from("a(b) café 👍🏽 (c)");
+    assert_eq!(find_grapheme(&row, 0, 'f', ")", 1, false), Some(3));
+    assert_eq!(find_grapheme(&row, 0, 'f', ")", 2, false), Some(14));
+    assert_eq!(find_grapheme(&row, 0, 'f', ")", 3, false), None);
+    assert_eq!(find_grapheme(&row, 0, 't', ")", 1, false), Some(2));
+    assert_eq!(find_grapheme(&row, 0, 'f', "é", 1, false), Some(8));
+    assert_eq!(find_grapheme(&row, 0, 'f', "👍🏽", 1, false), Some(10));
+    assert_eq!(find_grapheme(&row, 14, 'F', "(", 1, false), Some(12));
+    assert_eq!(find_grapheme(&row, 14, 'F', "(", 2, false), Some(1));
+    assert_eq!(find_grapheme(&row, 14, 'T', "(", 1, false), Some(13));
+    // the grapheme under the cursor isn't a match
+    assert_eq!(find_grapheme(&row, 3, 'f', ")", 1, false), Some(14));
+}
+
+#[test]
+fn test_repeated_till_search_skips_adjacent_match() {
+    let row = Row::from("a(b) (c)");
+    assert_eq!(find_grapheme(&row, 2, 't', ")", 1, false), Some(2));
+    assert_eq!(find_grapheme(&row, 2, 't', ")", 1, true), Some(6));
+    assert_eq!(find_grapheme(&row, 6, 'T', "(", 1, false), Some(6));
+    assert_eq!(find_grapheme(&row, 6, 'T', "(", 1, true), Some(2));
+    assert_eq!(
+        CharSearch::new('t', ")").reversed(),
+        CharSearch::new('T', ")")
+    );
+}
+
+#[test]
+fn test_find_start_of_next_and_previous_sentence() {
+    let document = document_from(&[
+        "Hello there. This sentence",
+        "spans two lines! Bye.",
+        "",
+        "  New paragraph.",
+    ]);
+    let stops = vec![(0, 13), (1, 17), (2, 0), (3, 2), (3, 15)];
+    assert_eq!(
+        sentence_stops(&document, Navigator::find_start_of_next_sentence, (0, 0)),
+        stops
+    );
+    assert_eq!(
+        sentence_stops(
+            &document,
+            Navigator::find_start_of_previous_sentence,
+            (3, 15)
+        ),
+        vec![(3, 2), (2, 0), (1, 17), (0, 13), (0, 0)]
+    );
+    // from the middle of a sentence, ( goes to its start
+    assert_eq!(
+        Navigator::find_start_of_previous_sentence(
+            &document,
+            RowIndex::new(1),
+            GraphemeIndex::new(3)
+        ),
+        (RowIndex::new(0), GraphemeIndex::new(13))
+    );
+}
+
+#[test]
+fn test_sentence_boundaries() {
+    let document = document_from(&[
+        "Dr. Watson met Mr. Holmes, e.g. in London. Wait... What?",
+        "He said \u{201c}Stop.\u{201d} Then (he left.) \u{bf}Qu\u{e9}? Fin.",
+    ]);
+    assert_eq!(
+        sentence_stops(&document, Navigator::find_start_of_next_sentence, (0, 0)),
+        // the last stop is the end of the document
+        vec![(0, 43), (0, 51), (1, 0), (1, 16), (1, 32), (1, 38), (1, 41)]
+    );
+    assert_eq!(Navigator::count_sentences(&document), 7);
+}
+
+#[test]
+fn test_sentence_boundaries_after_ambiguous_abbreviations() {
+    let document =
+        document_from(&["The answer was no. She left for St. Louis, at No. 5 Mt. Street."]);
+    assert_eq!(
+        sentence_stops(&document, Navigator::find_start_of_next_sentence, (0, 0)),
+        vec![(0, 19), (0, 62)]
+    );
+}
+
+#[test]
+fn test_find_sentence() {
+    let document = document_from(&[
+        "Hello there.  This sentence",
+        "spans two lines!",
+        "",
+        "Last one.",
+    ]);
+    assert_eq!(sentence_text(&document, (0, 3), false, 1), "Hello there.");
+    assert_eq!(sentence_text(&document, (0, 3), true, 1), "Hello there.  ");
+    assert_eq!(
+        sentence_text(&document, (1, 3), false, 1),
+        "This sentence\nspans two lines!"
+    );
+    // without trailing whitespace, the leading one is included
+    assert_eq!(
+        sentence_text(&document, (1, 3), true, 1),
+        "  This sentence\nspans two lines!"
+    );
+    assert_eq!(
+        sentence_text(&document, (0, 0), false, 2),
+        "Hello there.  This sentence\nspans two lines!"
+    );
+    assert_eq!(sentence_text(&document, (3, 0), true, 1), "Last one.");
+    assert_eq!(sentence_text(&document, (2, 0), true, 1), "");
 }
@@ -0,0 +1,396 @@
+/// This module implements the storage of the document rows, as a rope of lines:
+/// rows are stored in small contiguous chunks, along with the index of the first
+/// row of each chunk.
+///
+/// Finding a row is a binary search over the chunk index, and inserting or
+/// removing a row only moves the rows of its own chunk, instead of every row
+/// following it. Edits in the middle of a 200k lines file thus cost about the
+/// same as edits in a 10 lines file.
+///
+/// The rope also maintains a polynomial hash of its rows, in which each chunk
+/// contributes a partial sum cached until the chunk is modified. Hashing the
+/// rope after an edit thus only rehashes the edited chunk, and the hash does
+/// not depend on how the rows happen to be split into chunks.
+///
+/// A chunk can also be backed by a block of a memory-mapped file, whose rows
+/// are only decoded when accessed. Such a chunk is loaded in memory for good
+/// as soon as it is modified.
+use crate::mapped_file::MappedBlock;
+use crate::{LineEnding, Row};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use std::cell::{Cell, OnceCell};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::iter::Flatten;
+use std::ops::Range;
+use std::{slice, vec};
+
+/// Number of rows above which a chunk is split in two
+const MAX_CHUNK_LEN: usize = 1024;
+/// Base of the polynomial hash of the rows (the 64 bits FNV prime)
+const HASH_BASE: u64 = 0x0100_0000_01b3;
+
+/// Contribution of a chunk to the rope hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkHash {
+    /// Sum of the chunk row hashes, each multiplied by ``HASH_BASE`` to the
+    /// power of the row offset in the chunk
+    sum: u64,
+    /// ``HASH_BASE`` to the power of the chunk length
+    base_power: u64,
+}
+
+impl ChunkHash {
+    /// Hash the content of consecutive rows
+    pub fn of<S: AsRef<str>>(rows: impl Iterator<Item = S>) -> Self {
+        let mut sum: u64 = 0;
+        let mut base_power: u64 = 1;
+        for row in rows {
+            let mut hasher = DefaultHasher::new();
+            // hashed like the string of a Row
+            row.as_ref().hash(&mut hasher);
+            sum = sum.wrapping_add(hasher.finish().wrapping_mul(base_power));
+            base_power = base_power.wrapping_mul(HASH_BASE);
+        }
+        Self { sum, base_power }
+    }
+}
+
+#[derive(Debug)]
+enum ChunkRows {
+    Loaded(Vec<Row>),
+    /// Rows of a mapped file block, decoded on first access
+    Mapped(MappedBlock, OnceCell<Vec<Row>>),
+}
+
+#[derive(Debug)]
+struct Chunk {
+    /// Non-empty rows
+    rows: ChunkRows,
+    /// Hash of the rows, reset when they are modified
+    hash: Cell<Option<ChunkHash>>,
+}
+
+impl Chunk {
+    fn loaded(rows: Vec<Row>) -> Self {
+        Self {
+            rows: ChunkRows::Loaded(rows),
+            hash: Cell::new(None),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.rows {
+            ChunkRows::Loaded(rows) => rows.len(),
+            ChunkRows::Mapped(block, _) => block.num_lines,
+        }
+    }
+
+    fn rows(&self) -> &[Row] {
+        match &self.rows {
+            ChunkRows::Loaded(rows) => rows,
+            ChunkRows::Mapped(block, rows) => rows.get_or_init(|| block.rows()),
+        }
+    }
+
+    /// Return the rows to be modified, loading them in memory for good
+    fn rows_mut(&mut self) -> &mut Vec<Row> {
+        self.hash.set(None);
+        if let ChunkRows::Mapped(block, rows) = &mut self.rows {
+            let rows = rows.take().unwrap_or_else(|| block.rows());
+            self.rows = ChunkRows::Loaded(rows);
+        }
+        match &mut self.rows {
+            ChunkRows::Loaded(rows) => rows,
+            ChunkRows::Mapped(..) => unreachable!("the chunk was just loaded"),
+        }
+    }
+
+    fn hashed(&self) -> ChunkHash {
+        self.hash.get().unwrap_or_else(|| {
+            let hash = match &self.rows {
+                ChunkRows::Loaded(rows) => ChunkHash::of(rows.iter().map(|row| &row.string)),
+                ChunkRows::Mapped(block, _) => block.hash,
+            };
+            self.hash.set(Some(hash));
+            hash
+        })
+    }
+}
+
+/// Part of the rope, as written to a file
+#[derive(Debug)]
+pub enum Piece<'a> {
+    Rows(&'a [Row]),
+    /// Raw content of an unmodified block of a mapped file, line terminators
+    /// included, along with its number of rows
+    Bytes(&'a [u8], usize),
+}
+
+pub type IterMut<'a> = Flatten<vec::IntoIter<&'a mut Vec<Row>>>;
+
+/// Iterator over the rows of the rope
+pub struct Iter<'a> {
+    chunks: slice::Iter<'a, Chunk>,
+    rows: slice::Iter<'a, Row>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Row;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(row);
+            }
+            self.rows = self.chunks.next()?.rows().iter();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Rope {
+    chunks: Vec<Chunk>,
+    /// Index of the first row of each chunk
+    chunk_starts: Vec<usize>,
+    len: usize,
+}
+
+impl From<Vec<Row>> for Rope {
+    fn from(rows: Vec<Row>) -> Self {
+        let mut rope = Self::default();
+        let mut rows = rows.into_iter().peekable();
+        while rows.peek().is_some() {
+            rope.chunks.push(Chunk::loaded(
+                rows.by_ref().take(MAX_CHUNK_LEN / 2).collect(),
+            ));
+        }
+        rope.reindex(0);
+        rope
+    }
+}
+
+impl Serialize for Rope {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        for row in self.iter() {
+            seq.serialize_element(row)?;
+        }
+        seq.end()
+    }
+}
+
+impl Rope {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&Row> {
+        let (chunk, offset) = self.locate(index)?;
+        self.chunks[chunk].rows().get(offset)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Row> {
+        let (chunk, offset) = self.locate(index)?;
+        self.chunks[chunk].rows_mut().get_mut(offset)
+    }
+
+    pub fn push(&mut self, row: Row) {
+        self.insert(self.len, row);
+    }
+
+    /// Append the rows of a mapped file block, without decoding them
+    pub fn push_mapped(&mut self, block: MappedBlock) {
+        let hash = Cell::new(Some(block.hash));
+        self.chunks.push(Chunk {
+            rows: ChunkRows::Mapped(block, OnceCell::new()),
+            hash,
+        });
+        self.reindex(self.chunks.len() - 1);
+    }
+
+    /// Insert the row at the provided index, shifting all rows after it down.
+    ///
+    /// # Panics
+    /// Panics if the index is greater than the number of rows.
+    pub fn insert(&mut self, index: usize, row: Row) {
+        assert!(index <= self.len, "row index {} out of bounds", index);
+        let (chunk, offset) = match self.locate(index) {
+            Some(location) => location,
+            None => match self.chunks.len() {
+                0 => {
+                    self.chunks.push(Chunk::loaded(Vec::new()));
+                    (0, 0)
+                }
+                num_chunks => (num_chunks - 1, self.chunks[num_chunks - 1].len()),
+            },
+        };
+        let rows = self.chunks[chunk].rows_mut();
+        rows.insert(offset, row);
+        if rows.len() > MAX_CHUNK_LEN {
+            let tail = rows.split_off(MAX_CHUNK_LEN / 2);
+            self.chunks.insert(chunk + 1, Chunk::loaded(tail));
+        }
+        self.reindex(chunk);
+    }
+
+    /// Remove and return the row at the provided index, shifting all rows after it up.
+    ///
+    /// # Panics
+    /// Panics if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Row {
+        let (chunk, offset) = self
+            .locate(index)
+            .unwrap_or_else(|| panic!("row index {} out of bounds", index));
+        let rows = self.chunks[chunk].rows_mut();
+        let row = rows.remove(offset);
+        if rows.is_empty() {
+            self.chunks.remove(chunk);
+        }
+        self.reindex(chunk);
+        row
+    }
+
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            chunks: self.chunks.iter(),
+            rows: [].iter(),
+        }
+    }
+
+    /// Iterate over the rows, loading them all in memory
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        self.chunks
+            .iter_mut()
+            .map(Chunk::rows_mut)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+    }
+
+    /// Iterate over the rows loaded in memory, which include all the modified
+    /// rows, along with their indices
+    pub fn iter_loaded_mut(&mut self) -> impl Iterator<Item = (usize, &mut Row)> {
+        self.chunks
+            .iter_mut()
+            .zip(&self.chunk_starts)
+            .filter(|(chunk, _)| matches!(chunk.rows, ChunkRows::Loaded(_)))
+            .flat_map(|(chunk, &start)| {
+                chunk
+                    .rows_mut()
+                    .iter_mut()
+                    .enumerate()
+                    .map(move |(offset, row)| (start + offset, row))
+            })
+    }
+
+    /// Return whether some rows are backed by a mapped file
+    #[must_use]
+    pub fn is_mapped(&self) -> bool {
+        self.chunks
+            .iter()
+            .any(|chunk| matches!(chunk.rows, ChunkRows::Mapped(..)))
+    }
+
+    /// Discard the decoded rows of the unmodified mapped chunks located outside
+    /// of the provided row range
+    pub fn unload_rows_outside(&mut self, range: &Range<usize>) {
+        for (chunk, &start) in self.chunks.iter_mut().zip(&self.chunk_starts) {
+            let end = start + chunk.len();
+            if let ChunkRows::Mapped(_, rows) = &mut chunk.rows {
+                if end <= range.start || start >= range.end {
+                    rows.take();
+                }
+            }
+        }
+    }
+
+    /// Return the content of the rows located in the provided index range, as
+    /// it should be written to a file. The raw content of the unmodified mapped
+    /// blocks is used when they are written with the same line ending as the
+    /// one they were read with, if any.
+    #[must_use]
+    pub fn pieces(
+        &self,
+        range: &Range<usize>,
+        raw_line_ending: Option<LineEnding>,
+    ) -> Vec<Piece<'_>> {
+        let mut pieces = Vec::new();
+        for (chunk, &start) in self.chunks.iter().zip(&self.chunk_starts) {
+            let end = start + chunk.len();
+            if end <= range.start || start >= range.end {
+                continue;
+            }
+            match &chunk.rows {
+                ChunkRows::Mapped(block, _)
+                    if range.start <= start
+                        && end <= range.end
+                        && raw_line_ending == Some(block.line_ending) =>
+                {
+                    pieces.push(Piece::Bytes(block.bytes(), block.num_lines));
+                }
+                _ => {
+                    let from = range.start.saturating_sub(start);
+                    let to = range.end.min(end) - start;
+                    pieces.push(Piece::Rows(&chunk.rows()[from..to]));
+                }
+            }
+        }
+        pieces
+    }
+
+    /// Return the hash of the rows, only rehashing the chunks modified since
+    /// the last call
+    #[must_use]
+    pub fn hashed(&self) -> u64 {
+        let mut hash: u64 = 0;
+        let mut base_power: u64 = 1;
+        for chunk in &self.chunks {
+            let chunk_hash = chunk.hashed();
+            hash = hash.wrapping_add(chunk_hash.sum.wrapping_mul(base_power));
+            base_power = base_power.wrapping_mul(chunk_hash.base_power);
+        }
+        hash
+    }
+
+    /// Return the index of the chunk containing the row, and the row offset in that chunk
+    fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        let chunk = self
+            .chunk_starts
+            .partition_point(|&start| start <= index)
+            .saturating_sub(1);
+        Some((chunk, index - self.chunk_starts[chunk]))
+    }
+
+    /// Recompute the chunk index, from the provided chunk onwards
+    fn reindex(&mut self, from_chunk: usize) {
+        self.chunk_starts.truncate(from_chunk);
+        let mut start = match from_chunk.checked_sub(1) {
+            Some(previous) => self.chunk_starts[previous] + self.chunks[previous].len(),
+            None => 0,
+        };
+        for chunk in &self.chunks[from_chunk..] {
+            self.chunk_starts.push(start);
+            start += chunk.len();
+        }
+        self.len = start;
+    }
+}
+
+#[cfg(test)]
+#[path = "./rope_test.rs"]
+mod rope_test;
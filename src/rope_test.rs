@@ -0,0 +1,107 @@
+use crate::rope::Rope;
+use crate::{GraphemeIndex, Row};
+
+fn numbered_rows(n: usize) -> Vec<Row> {
+    (0..n).map(|i| Row::from(i.to_string().as_str())).collect()
+}
+
+fn contents(rope: &Rope) -> Vec<String> {
+    rope.iter().map(|row| row.string.clone()).collect()
+}
+
+#[test]
+fn test_rope_get() {
+    let rope = Rope::from(numbered_rows(5000));
+    assert_eq!(rope.len(), 5000);
+    assert_eq!(rope.get(0).unwrap().string, "0");
+    assert_eq!(rope.get(2048).unwrap().string, "2048");
+    assert_eq!(rope.get(4999).unwrap().string, "4999");
+    assert!(rope.get(5000).is_none());
+    assert!(Rope::default().is_empty());
+}
+
+#[test]
+fn test_rope_insert_splits_chunks() {
+    let mut rope = Rope::default();
+    for i in 0..3000 {
+        // always inserting in the middle, to fill and split the same chunks
+        rope.insert(i / 2, Row::from(i.to_string().as_str()));
+    }
+    rope.push(Row::from("last"));
+    assert_eq!(rope.len(), 3001);
+    let mut expected: Vec<String> = Vec::new();
+    for i in 0..3000 {
+        expected.insert(i / 2, i.to_string());
+    }
+    expected.push(String::from("last"));
+    assert_eq!(contents(&rope), expected);
+    for (i, row) in expected.iter().enumerate() {
+        assert_eq!(&rope.get(i).unwrap().string, row);
+    }
+}
+
+#[test]
+fn test_rope_remove() {
+    let mut rope = Rope::from(numbered_rows(2000));
+    assert_eq!(rope.remove(0).string, "0");
+    assert_eq!(rope.remove(1998).string, "1999");
+    // emptying a whole chunk
+    for _ in 0..600 {
+        rope.remove(100);
+    }
+    assert_eq!(rope.len(), 1398);
+    assert_eq!(rope.get(99).unwrap().string, "100");
+    assert_eq!(rope.get(100).unwrap().string, "701");
+    assert_eq!(rope.get(1397).unwrap().string, "1998");
+    while !rope.is_empty() {
+        rope.remove(0);
+    }
+    rope.insert(0, Row::from("again"));
+    assert_eq!(contents(&rope), vec!["again"]);
+}
+
+#[test]
+fn test_rope_get_mut() {
+    let mut rope = Rope::from(numbered_rows(1500));
+    rope.get_mut(1200).unwrap().append_str("!");
+    assert_eq!(rope.get(1200).unwrap().string, "1200!");
+    for row in rope.iter_mut() {
+        row.append_str(".");
+    }
+    assert_eq!(rope.get(1499).unwrap().string, "1499.");
+}
+
+#[test]
+fn test_rope_hash_does_not_depend_on_chunks() {
+    let rope = Rope::from(numbered_rows(3000));
+    let mut other = Rope::default();
+    for i in (0..3000).rev() {
+        other.insert(0, Row::from(i.to_string().as_str()));
+    }
+    assert_eq!(rope.hashed(), other.hashed());
+}
+
+#[test]
+fn test_rope_hash_is_updated_on_edits() {
+    let mut rope = Rope::from(numbered_rows(3000));
+    let initial_hash = rope.hashed();
+
+    rope.get_mut(2500).unwrap().append_str("!");
+    let edited_hash = rope.hashed();
+    assert_ne!(edited_hash, initial_hash);
+    rope.get_mut(2500).unwrap().delete(GraphemeIndex::new(4));
+    assert_eq!(rope.hashed(), initial_hash);
+
+    let row = rope.remove(10);
+    assert_ne!(rope.hashed(), initial_hash);
+    rope.insert(11, row);
+    assert_ne!(rope.hashed(), initial_hash); // rows 10 and 11 were swapped
+    let row = rope.remove(11);
+    rope.insert(10, row);
+    assert_eq!(rope.hashed(), initial_hash);
+
+    for row in rope.iter_mut() {
+        row.trim_end_inplace();
+    }
+    assert_eq!(rope.hashed(), initial_hash);
+}
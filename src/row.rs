@@ -1,46 +1,179 @@
-use crate::utils;
+use crate::display_width::{grapheme_width_at, whitespace};
+use crate::{utils, ByteIndex, Column, GraphemeIndex};
 use serde::Serialize;
 use std::cmp;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 use std::str;
 use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Debug, Default, Hash, Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct Row {
+    /// The row content, which should only be modified through the `Row` methods,
+    /// to keep the cached grapheme count up to date
     pub string: String,
+    /// Number of graphemes in the string
+    #[serde(skip)]
+    len: usize,
 }
 
 impl From<&str> for Row {
     fn from(s: &str) -> Self {
-        Self {
-            string: String::from(s),
-        }
+        Self::from(String::from(s))
+    }
+}
+
+impl From<String> for Row {
+    fn from(string: String) -> Self {
+        let mut row = Self { string, len: 0 };
+        row.update_len();
+        row
+    }
+}
+
+impl Hash for Row {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.string.hash(state);
     }
 }
 
 impl Row {
+    /// Render the graphemes displayed between the ``start`` and ``end`` display
+    /// columns, prefixed by the line number if ``x_offset`` isn't 0. Tabs are
+    /// expanded into spaces, and a wide grapheme cut by either bound is replaced
+    /// by spaces.
     #[must_use]
-    pub fn render(&self, start: usize, end: usize, line_number: usize, x_offset: usize) -> String {
-        let end = cmp::min(end, self.string.len()); // either stop at terminal end or string end
-        let start = cmp::min(start, end);
+    pub fn render(
+        &self,
+        start: Column,
+        end: Column,
+        line_number: usize,
+        x_offset: usize,
+        tabstop: usize,
+    ) -> String {
+        let (start, end) = (start.value, end.value);
         let mut visible = String::new();
-        for grapheme in self.graphemes().skip(start).take(end - start) {
-            visible.push_str(grapheme);
+        let mut column = 0;
+        for grapheme in self.graphemes() {
+            if column >= end {
+                break;
+            }
+            let next_column = column + grapheme_width_at(grapheme, column, tabstop);
+            if column >= start && next_column <= end && grapheme != "\t" {
+                visible.push_str(grapheme);
+            } else if next_column > start {
+                visible.push_str(&" ".repeat(next_column.min(end) - column.max(start)));
+            }
+            column = next_column;
         }
         let prefix = if x_offset == 0 {
-            "".to_string()
+            String::new()
         } else {
             format!("{} ", utils::zfill(&line_number.to_string(), " ", x_offset))
         };
-        format!("{}{}", prefix, visible)
+        format!("{prefix}{visible}")
+    }
+
+    /// Return the number of columns taken by the row on screen
+    #[must_use]
+    pub fn width(&self, tabstop: usize) -> Column {
+        self.column_of(GraphemeIndex::new(self.len), tabstop)
+    }
+
+    /// Return the display column at which the grapheme located at the provided
+    /// index starts, or the row width if the index is past the end of the row
+    #[must_use]
+    pub fn column_of(&self, index: GraphemeIndex, tabstop: usize) -> Column {
+        if self.is_single_column() {
+            return Column::new(cmp::min(index.value, self.len));
+        }
+        Column::new(
+            self.graphemes()
+                .take(index.value)
+                .fold(0, |column, grapheme| {
+                    column + grapheme_width_at(grapheme, column, tabstop)
+                }),
+        )
     }
 
-    pub fn chars(&self) -> std::str::Chars {
-        self.string.chars()
+    /// Return the index of the grapheme displayed over the provided column, or
+    /// the row length if the column is past the end of the row
+    #[must_use]
+    pub fn index_at_column(&self, column: Column, tabstop: usize) -> GraphemeIndex {
+        if self.is_single_column() {
+            return GraphemeIndex::new(cmp::min(column.value, self.len));
+        }
+        let mut next_column = 0;
+        for (index, grapheme) in self.graphemes().enumerate() {
+            next_column += grapheme_width_at(grapheme, next_column, tabstop);
+            if column.value < next_column {
+                return GraphemeIndex::new(index);
+            }
+        }
+        GraphemeIndex::new(self.len)
+    }
+
+    /// Return the number of columns taken by the leading whitespace of the row
+    #[must_use]
+    pub fn indentation_width(&self, tabstop: usize) -> Column {
+        let indentation_len = self
+            .graphemes()
+            .take_while(|grapheme| *grapheme == " " || *grapheme == "\t")
+            .count();
+        self.column_of(GraphemeIndex::new(indentation_len), tabstop)
     }
 
+    /// Return the row content with its leading whitespace replaced by an
+    /// indentation of the provided width
     #[must_use]
-    pub fn graphemes(&self) -> unicode_segmentation::Graphemes {
+    pub fn reindented(&self, width: Column, tabstop: usize, expandtab: bool) -> String {
+        let content = self.string.trim_start_matches([' ', '\t']);
+        format!(
+            "{}{content}",
+            whitespace(0, width.value, tabstop, expandtab)
+        )
+    }
+
+    /// Return the row content with the whitespace sequences containing tabs
+    /// displayed with the new tab stop, as they were with the current one. If
+    /// ``convert_spaces`` is set, sequences made of spaces only are converted too.
+    #[must_use]
+    pub fn retabbed(
+        &self,
+        tabstop: usize,
+        new_tabstop: usize,
+        expandtab: bool,
+        convert_spaces: bool,
+    ) -> String {
+        let mut retabbed = String::with_capacity(self.string.len());
+        let mut column = 0;
+        // start column of the current whitespace sequence, and whether it contains a tab
+        let mut sequence: Option<(usize, bool)> = None;
+        let mut sequence_text = String::new();
+        for grapheme in self.graphemes().chain(std::iter::once("")) {
+            let is_whitespace = grapheme == " " || grapheme == "\t";
+            if is_whitespace {
+                let (start, has_tab) = sequence.unwrap_or((column, false));
+                sequence = Some((start, has_tab || grapheme == "\t"));
+                sequence_text.push_str(grapheme);
+            } else if let Some((start, has_tab)) = sequence.take() {
+                // a single space is left as is, as it can't be a tab
+                if (has_tab || convert_spaces) && sequence_text != " " {
+                    retabbed.push_str(&whitespace(start, column, new_tabstop, expandtab));
+                } else {
+                    retabbed.push_str(&sequence_text);
+                }
+                sequence_text.clear();
+            }
+            if !is_whitespace {
+                retabbed.push_str(grapheme);
+            }
+            column += grapheme_width_at(grapheme, column, tabstop);
+        }
+        retabbed
+    }
+
+    #[must_use]
+    pub fn graphemes(&self) -> unicode_segmentation::Graphemes<'_> {
         self.string[..].graphemes(true)
     }
 
@@ -54,9 +187,10 @@ impl Row {
         !self.string.chars().any(|c| !c.is_whitespace())
     }
 
+    /// Return the number of graphemes in the row
     #[must_use]
     pub fn len(&self) -> usize {
-        self.string[..].graphemes(true).count()
+        self.len
     }
 
     #[must_use]
@@ -65,13 +199,14 @@ impl Row {
     }
 
     #[must_use]
-    pub fn nth_grapheme(&self, index: usize) -> &str {
-        self.graphemes().nth(index).unwrap_or_default()
-    }
-
-    #[must_use]
-    pub fn nth_char(&self, index: usize) -> char {
-        self.chars().nth(index).unwrap_or_default()
+    pub fn nth_grapheme(&self, index: GraphemeIndex) -> &str {
+        if self.len == self.string.len() {
+            return self
+                .string
+                .get(index.value..=index.value)
+                .unwrap_or_default();
+        }
+        self.graphemes().nth(index.value).unwrap_or_default()
     }
 
     #[must_use]
@@ -84,11 +219,35 @@ impl Row {
         self.string.contains(pattern)
     }
 
+    /// Return the index of the first grapheme of every occurrence of the pattern.
+    /// Occurrences that don't start on a grapheme boundary (ex: ``e`` matching the
+    /// start of ``e`` followed by a combining accent) are not reported.
     #[must_use]
-    pub fn find_all(&self, pattern: &str) -> Vec<usize> {
-        self.string
-            .match_indices(pattern)
-            .map(|item| item.0)
+    pub fn find_all(&self, pattern: &str) -> Vec<GraphemeIndex> {
+        if pattern.is_empty() {
+            return vec![];
+        }
+        let matches = self.string.match_indices(pattern);
+        if self.len == self.string.len() {
+            // every grapheme is a single byte
+            return matches
+                .map(|(byte_index, _)| GraphemeIndex::new(byte_index))
+                .collect();
+        }
+        let boundaries: Vec<usize> = self
+            .string
+            .grapheme_indices(true)
+            .map(|(byte_index, _)| byte_index)
+            .chain(std::iter::once(self.string.len()))
+            .collect();
+        matches
+            .filter_map(|(byte_index, matched)| {
+                let index = boundaries.binary_search(&byte_index).ok()?;
+                boundaries
+                    .binary_search(&(byte_index + matched.len()))
+                    .ok()?;
+                Some(GraphemeIndex::new(index))
+            })
             .collect()
     }
 
@@ -98,32 +257,34 @@ impl Row {
     }
 
     pub fn trim_end_inplace(&mut self) {
-        self.string = String::from(self.string.trim_end());
+        let trimmed_len = self.string.trim_end().len();
+        self.string.truncate(trimmed_len);
+        self.update_len();
     }
 
-    /// Insert a character in the provided x index
-    pub fn insert(&mut self, index: usize, c: char) {
-        if index >= self.len() {
-            self.string.push(c); // Append at the end of the row
-        } else {
-            // mid row edition
-            let mut before: String = self.graphemes().take(index).collect();
-            let after: String = self.graphemes().skip(index).collect();
-            before.push(c);
-            before.push_str(&after);
-            self.string = before;
-        }
+    /// Insert a character before the grapheme located at the provided index
+    pub fn insert(&mut self, index: GraphemeIndex, c: char) {
+        let byte_index = self.byte_index(index);
+        self.string.insert(byte_index.value, c);
+        self.update_len();
+    }
+
+    /// Insert a string before the grapheme located at the provided index
+    pub fn insert_str(&mut self, index: GraphemeIndex, string: &str) {
+        let byte_index = self.byte_index(index);
+        self.string.insert_str(byte_index.value, string);
+        self.update_len();
     }
 
-    /// Delete the character located at provided index
-    pub fn delete(&mut self, index: usize) {
-        if index >= self.len() {
+    /// Delete the grapheme located at provided index
+    pub fn delete(&mut self, index: GraphemeIndex) {
+        if index.value >= self.len() {
             return;
         }
-        let mut before: String = self.graphemes().take(index).collect();
-        let after: String = self.graphemes().skip(index.saturating_add(1)).collect();
-        before.push_str(&after);
-        self.string = before;
+        let start = self.byte_index(index);
+        let end = self.byte_index(index.next());
+        self.string.replace_range(start.value..end.value, "");
+        self.update_len();
     }
 
     /// Append a string at the end of the current one
@@ -132,15 +293,65 @@ impl Row {
     }
 
     pub fn append_str(&mut self, s: &str) {
-        self.string = format!("{}{}", self.string, s);
+        self.string.push_str(s);
+        self.update_len();
     }
 
+    /// Split the row before the grapheme located at the provided index, and
+    /// return the part following it
     #[must_use]
-    pub fn split(&mut self, at: usize) -> Self {
-        let before: String = self.graphemes().take(at).collect();
-        let after: String = self.graphemes().skip(at).collect();
-        self.string = before;
-        Self::from(&after[..])
+    pub fn split(&mut self, at: GraphemeIndex) -> Self {
+        let after = self.string.split_off(self.byte_index(at).value);
+        self.update_len();
+        Self::from(after)
+    }
+
+    /// Return whether every grapheme of the row is displayed over a single column
+    fn is_single_column(&self) -> bool {
+        self.len == self.string.len() && !self.string.contains('\t')
+    }
+
+    /// Return the byte index at which the grapheme located at the provided
+    /// index starts, or the string length if the index is past the end of the row
+    #[must_use]
+    pub fn byte_index(&self, index: GraphemeIndex) -> ByteIndex {
+        if self.len == self.string.len() {
+            // every grapheme is a single byte
+            return ByteIndex::new(cmp::min(index.value, self.len));
+        }
+        ByteIndex::new(
+            self.string
+                .grapheme_indices(true)
+                .nth(index.value)
+                .map_or(self.string.len(), |(byte_index, _)| byte_index),
+        )
+    }
+
+    /// Return the index of the grapheme containing the byte located at the
+    /// provided index, or the row length if the index is past the end of the row
+    #[must_use]
+    pub fn grapheme_index(&self, index: ByteIndex) -> GraphemeIndex {
+        if self.len == self.string.len() {
+            return GraphemeIndex::new(cmp::min(index.value, self.len));
+        }
+        GraphemeIndex::new(
+            self.string
+                .grapheme_indices(true)
+                .take_while(|(byte_index, grapheme)| byte_index + grapheme.len() <= index.value)
+                .count(),
+        )
+    }
+
+    /// Recount the graphemes after the string was modified. Edits can merge or
+    /// split the graphemes around them (ex: a combining accent), so the count
+    /// can't simply be adjusted by the number of inserted or deleted characters.
+    fn update_len(&mut self) {
+        // ASCII characters other than \r (joined with a following \n) are graphemes of their own
+        self.len = if self.string.is_ascii() && !self.string.contains('\r') {
+            self.string.len()
+        } else {
+            self.string.graphemes(true).count()
+        };
     }
 }
 
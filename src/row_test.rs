@@ -1,13 +1,38 @@
-use crate::Row;
+use crate::{ByteIndex, Column, GraphemeIndex, Row};
 
 #[test]
 fn test_row_render() {
-    // fn render(&self, start: usize, end: usize, line_number: usize, x_offset: usize)
-    assert_eq!(Row::from("Test").render(0, 50, 1, 0), "Test");
-    assert_eq!(Row::from("Test").render(0, 50, 1, 4), "   1 Test");
-    assert_eq!(Row::from("Test").render(0, 50, 11, 4), "  11 Test");
-    assert_eq!(Row::from("Test").render(10, 60, 11, 4), "  11 ");
-    assert_eq!(Row::from("\u{2764}").render(0, 50, 11, 4), "  11 \u{2764}");
+    // fn render(&self, start: Column, end: Column, line_number: usize, x_offset: usize, tabstop: usize)
+    assert_eq!(
+        Row::from("Test").render(Column::new(0), Column::new(50), 1, 0, 4),
+        "Test"
+    );
+    assert_eq!(
+        Row::from("Test").render(Column::new(0), Column::new(50), 1, 4, 4),
+        "   1 Test"
+    );
+    assert_eq!(
+        Row::from("Test").render(Column::new(0), Column::new(50), 11, 4, 4),
+        "  11 Test"
+    );
+    assert_eq!(
+        Row::from("Test").render(Column::new(10), Column::new(60), 11, 4, 4),
+        "  11 "
+    );
+    assert_eq!(
+        Row::from("\u{2764}").render(Column::new(0), Column::new(50), 11, 4, 4),
+        "  11 \u{2764}"
+    );
+    // the display columns of wide graphemes are taken into account
+    assert_eq!(
+        Row::from("日本語").render(Column::new(2), Column::new(6), 1, 0, 4),
+        "本語"
+    );
+    // a wide grapheme cut by the bounds is replaced by spaces
+    assert_eq!(
+        Row::from("日本語").render(Column::new(1), Column::new(5), 1, 0, 4),
+        " 本 "
+    );
 }
 
 #[test]
@@ -35,7 +60,10 @@ fn test_row_is_empty() {
 
 #[test]
 fn test_row_index() {
-    assert_eq!(Row::from("I \u{2764} unicode!").nth_grapheme(2), "\u{2764}");
+    assert_eq!(
+        Row::from("I \u{2764} unicode!").nth_grapheme(GraphemeIndex::new(2)),
+        "\u{2764}"
+    );
 }
 
 #[test]
@@ -62,14 +90,46 @@ fn test_row_contains() {
 
 #[test]
 fn test_row_find_all() {
-    assert_eq!(Row::from("Hello hello world!").find_all("ello"), vec![1, 7]);
+    assert_eq!(
+        Row::from("Hello hello world!").find_all("ello"),
+        vec![GraphemeIndex::new(1), GraphemeIndex::new(7)]
+    );
     assert!(Row::from("Hello world!").find_all("\u{2764}").is_empty());
     assert_eq!(
         Row::from("Hello \u{2764} world!").find_all("\u{2764}"),
-        vec![6]
+        vec![GraphemeIndex::new(6)]
+    );
+}
+
+#[test]
+fn test_row_find_all_non_ascii() {
+    // the matches are returned as grapheme indices, not byte offsets
+    assert_eq!(
+        Row::from("h\u{e9}llo h\u{e9}llo").find_all("llo"),
+        vec![GraphemeIndex::new(2), GraphemeIndex::new(8)]
+    );
+    // a match splitting a grapheme isn't a match
+    assert!(Row::from("cafe\u{301}").find_all("e").is_empty());
+    assert_eq!(
+        Row::from("cafe\u{301} cafe").find_all("e"),
+        vec![GraphemeIndex::new(8)]
     );
 }
 
+#[test]
+fn test_row_byte_and_grapheme_indices() {
+    let row = Row::from("a\u{e9}e\u{301}\u{2764}b");
+    let byte_indices = [0, 1, 3, 6, 9, 10];
+    for (grapheme_index, byte_index) in byte_indices.iter().enumerate() {
+        let grapheme_index = GraphemeIndex::new(grapheme_index);
+        let byte_index = ByteIndex::new(*byte_index);
+        assert_eq!(row.byte_index(grapheme_index), byte_index);
+        assert_eq!(row.grapheme_index(byte_index), grapheme_index);
+    }
+    // indices past the end of the row are clamped to it
+    assert_eq!(row.byte_index(GraphemeIndex::new(12)), ByteIndex::new(10));
+}
+
 #[test]
 fn test_row_is_whitespace() {
     // whitespaces
@@ -92,22 +152,22 @@ fn test_row_string_chars() {
 #[test]
 fn test_row_insert() {
     let mut row = Row::from("Hell");
-    row.insert(4, 'o');
+    row.insert(GraphemeIndex::new(4), 'o');
     assert_eq!(row.string, "Hello");
-    row.insert(8, 'o');
+    row.insert(GraphemeIndex::new(8), 'o');
     assert_eq!(row.string, "Helloo");
-    row.insert(0, '.');
+    row.insert(GraphemeIndex::new(0), '.');
     assert_eq!(row.string, ".Helloo");
 }
 
 #[test]
 fn test_row_delete() {
     let mut row = Row::from("Hello!");
-    row.delete(8); // outside the string's boundaries
+    row.delete(GraphemeIndex::new(8)); // outside the string's boundaries
     assert_eq!(row.string, "Hello!");
-    row.delete(5);
+    row.delete(GraphemeIndex::new(5));
     assert_eq!(row.string, "Hello");
-    row.delete(2);
+    row.delete(GraphemeIndex::new(2));
     assert_eq!(row.string, "Helo");
 }
 
@@ -122,7 +182,111 @@ fn test_row_append() {
 #[test]
 fn test_row_split() {
     let mut row1 = Row::from("Hello world!");
-    let row2 = row1.split(5);
+    let row2 = row1.split(GraphemeIndex::new(5));
     assert_eq!(row1.string, "Hello");
     assert_eq!(row2.string, " world!");
 }
+
+#[test]
+fn test_row_len_is_kept_up_to_date() {
+    let mut row = Row::from("cafe");
+    row.insert(GraphemeIndex::new(4), '\u{301}'); // combining acute accent, merged with the 'e'
+    assert_eq!(row.string, "cafe\u{301}");
+    assert_eq!(row.len(), 4);
+    row.insert(GraphemeIndex::new(4), '!');
+    assert_eq!(row.string, "cafe\u{301}!");
+    assert_eq!(row.len(), 5);
+    row.delete(GraphemeIndex::new(3));
+    assert_eq!(row.string, "caf!");
+    assert_eq!(row.len(), 4);
+    row.append_str("\r");
+    assert_eq!(row.len(), 5);
+    row.append_str("\n"); // \r\n is a single grapheme
+    assert_eq!(row.len(), 5);
+    let after = row.split(GraphemeIndex::new(2));
+    assert_eq!((row.string.as_str(), row.len()), ("ca", 2));
+    assert_eq!((after.string.as_str(), after.len()), ("f!\r\n", 3));
+    let mut row = Row::from("trailing   ");
+    row.trim_end_inplace();
+    assert_eq!(row.len(), 8);
+}
+
+#[test]
+fn test_row_display_columns() {
+    let row = Row::from("a日e\u{301}😀b");
+    assert_eq!(row.len(), 5);
+    assert_eq!(row.width(4), Column::new(7));
+    assert_eq!(
+        (0..=5)
+            .map(|i| row.column_of(GraphemeIndex::new(i), 4).value)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 3, 4, 6, 7]
+    );
+    assert_eq!(
+        (0..=8)
+            .map(|c| row.index_at_column(Column::new(c), 4).value)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 1, 2, 3, 3, 4, 5, 5]
+    );
+    assert_eq!(
+        Row::from("abc").column_of(GraphemeIndex::new(5), 4),
+        Column::new(3)
+    );
+    assert_eq!(
+        Row::from("abc").index_at_column(Column::new(5), 4),
+        GraphemeIndex::new(3)
+    );
+}
+
+#[test]
+fn test_row_tabs() {
+    let row = Row::from("a\tbc\td");
+    assert_eq!(row.width(4), Column::new(9));
+    assert_eq!(
+        (0..=6)
+            .map(|i| row.column_of(GraphemeIndex::new(i), 4).value)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 4, 5, 6, 8, 9]
+    );
+    assert_eq!(
+        row.index_at_column(Column::new(2), 4),
+        GraphemeIndex::new(1)
+    );
+    assert_eq!(
+        row.index_at_column(Column::new(7), 4),
+        GraphemeIndex::new(4)
+    );
+    assert_eq!(
+        row.render(Column::new(0), Column::new(50), 1, 0, 4),
+        "a   bc  d"
+    );
+    assert_eq!(
+        row.render(Column::new(0), Column::new(50), 1, 0, 8),
+        "a       bc      d"
+    );
+    // a tab cut by the start of the visible columns is partially displayed
+    assert_eq!(
+        row.render(Column::new(2), Column::new(50), 1, 0, 4),
+        "  bc  d"
+    );
+}
+
+#[test]
+fn test_row_indentation() {
+    let row = Row::from(" \t  foo bar");
+    assert_eq!(row.indentation_width(4), Column::new(6));
+    assert_eq!(row.reindented(Column::new(8), 4, true), "        foo bar");
+    assert_eq!(row.reindented(Column::new(6), 4, false), "\t  foo bar");
+    assert_eq!(Row::from("foo").reindented(Column::new(0), 4, false), "foo");
+}
+
+#[test]
+fn test_row_retabbed() {
+    let row = Row::from("\tfoo\tbar    baz x");
+    // expand the tabs into spaces
+    assert_eq!(row.retabbed(4, 4, true, false), "    foo bar    baz x");
+    // change the tab stop, keeping the tabs
+    assert_eq!(row.retabbed(4, 2, false, false), "\t\tfoo\tbar    baz x");
+    // also convert the sequences of spaces to tabs
+    assert_eq!(row.retabbed(4, 4, false, true), "\tfoo\tbar\t   baz x");
+}
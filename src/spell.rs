@@ -0,0 +1,493 @@
+/// This module implements the offline spell checker. Dictionaries are read
+/// from disk, either as plain word lists (``<lang>.txt``, one word per line),
+/// or as Hunspell dictionaries (``<lang>.dic``), whose words are expanded with
+/// the prefix and suffix rules of the accompanying affix file (``<lang>.aff``).
+use crate::{utils, Encoding};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Directories dictionaries are looked up in, in order
+pub const DICTIONARY_DIRECTORIES: [&str; 4] = [
+    "~/.config/bo/spell",
+    "/usr/share/hunspell",
+    "/usr/share/myspell",
+    "/usr/share/myspell/dicts",
+];
+/// Maximum number of spelling suggestions
+pub const MAX_SUGGESTIONS: usize = 10;
+/// Maximum edit distance between a misspelled word and its suggestions
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// How the flags attached to the words of a Hunspell dictionary are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagFormat {
+    /// A flag per character
+    Char,
+    /// A flag per pair of characters
+    Long,
+    /// Comma-separated numbers
+    Numeric,
+}
+
+impl FlagFormat {
+    fn parse(self, flags: &str) -> Vec<String> {
+        match self {
+            FlagFormat::Char => flags.chars().map(String::from).collect(),
+            FlagFormat::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|pair| pair.iter().collect()).collect()
+            }
+            FlagFormat::Numeric => flags.split(',').map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// A character of the condition of an affix rule
+#[derive(Debug, PartialEq, Eq)]
+enum ConditionChar {
+    Any,
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl ConditionChar {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionChar::Any => true,
+            ConditionChar::OneOf(chars) => chars.contains(&c),
+            ConditionChar::NoneOf(chars) => !chars.contains(&c),
+        }
+    }
+
+    /// Parse a condition such as ``[^aeiou]y``
+    fn parse_condition(condition: &str) -> Vec<Self> {
+        let mut parsed = vec![];
+        let mut chars = condition.chars();
+        while let Some(c) = chars.next() {
+            parsed.push(match c {
+                '.' => ConditionChar::Any,
+                '[' => {
+                    let class: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                    match class.strip_prefix('^') {
+                        Some(class) => ConditionChar::NoneOf(class.chars().collect()),
+                        None => ConditionChar::OneOf(class.chars().collect()),
+                    }
+                }
+                c => ConditionChar::OneOf(vec![c]),
+            });
+        }
+        parsed
+    }
+}
+
+/// A prefix or suffix rule of a Hunspell affix file
+#[derive(Debug)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<ConditionChar>,
+}
+
+impl AffixRule {
+    /// Apply the rule to the end of the word (or to its start for a
+    /// prefix), if the word matches the rule condition
+    fn apply(&self, word: &str, suffix: bool) -> Option<String> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < self.condition.len() {
+            return None;
+        }
+        let affixed = if suffix {
+            &chars[chars.len() - self.condition.len()..]
+        } else {
+            &chars[..self.condition.len()]
+        };
+        if !affixed
+            .iter()
+            .zip(&self.condition)
+            .all(|(c, condition)| condition.matches(*c))
+        {
+            return None;
+        }
+        if suffix {
+            let stem = word.strip_suffix(self.strip.as_str())?;
+            Some(format!("{stem}{}", self.add))
+        } else {
+            let stem = word.strip_prefix(self.strip.as_str())?;
+            Some(format!("{}{stem}", self.add))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Affix {
+    suffix: bool,
+    /// Whether the affix can be combined with an affix of the other kind
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+/// Prefix and suffix rules of a Hunspell dictionary, by flag
+#[derive(Debug)]
+struct AffixFile {
+    flag_format: FlagFormat,
+    affixes: HashMap<String, Affix>,
+}
+
+impl AffixFile {
+    fn parse(content: &str) -> Self {
+        let mut flag_format = FlagFormat::Char;
+        let mut affixes: HashMap<String, Affix> = HashMap::new();
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long"] => flag_format = FlagFormat::Long,
+                ["FLAG", "num"] => flag_format = FlagFormat::Numeric,
+                // header of an affix (ex: SFX A Y 2)
+                [kind @ ("PFX" | "SFX"), flag, cross_product, count]
+                    if count.parse::<usize>().is_ok() =>
+                {
+                    affixes.insert(
+                        (*flag).to_string(),
+                        Affix {
+                            suffix: *kind == "SFX",
+                            cross_product: *cross_product == "Y",
+                            rules: vec![],
+                        },
+                    );
+                }
+                // rule of an affix (ex: SFX A y ies [^aeiou]y)
+                ["PFX" | "SFX", flag, strip, add, rest @ ..] => {
+                    if let Some(affix) = affixes.get_mut(*flag) {
+                        // the flags of the added affix itself aren't supported
+                        let add = add.split('/').next().unwrap_or_default();
+                        affix.rules.push(AffixRule {
+                            strip: affix_text(strip),
+                            add: affix_text(add),
+                            condition: ConditionChar::parse_condition(
+                                rest.first().copied().unwrap_or("."),
+                            ),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+        Self {
+            flag_format,
+            affixes,
+        }
+    }
+
+    /// Return the word along with all the words derived from it by the affixes
+    /// designated by the flags
+    fn expand(&self, word: &str, flags: &str) -> Vec<String> {
+        let affixes: Vec<&Affix> = self
+            .flag_format
+            .parse(flags)
+            .iter()
+            .filter_map(|flag| self.affixes.get(flag))
+            .collect();
+        let mut words = vec![word.to_string()];
+        let mut suffixed = vec![];
+        for affix in affixes.iter().filter(|affix| affix.suffix) {
+            for rule in &affix.rules {
+                if let Some(derived) = rule.apply(word, true) {
+                    if affix.cross_product {
+                        suffixed.push(derived.clone());
+                    }
+                    words.push(derived);
+                }
+            }
+        }
+        for affix in affixes.iter().filter(|affix| !affix.suffix) {
+            for rule in &affix.rules {
+                words.extend(rule.apply(word, false));
+                if affix.cross_product {
+                    words.extend(suffixed.iter().filter_map(|word| rule.apply(word, false)));
+                }
+            }
+        }
+        words
+    }
+}
+
+/// Return the text of the affix, "0" standing for an empty one
+fn affix_text(affix: &str) -> String {
+    if affix == "0" {
+        String::new()
+    } else {
+        affix.to_string()
+    }
+}
+
+/// Set of the correctly spelled words of a language
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    /// Words, by number of characters, so that suggestions are only looked
+    /// for among the words of a similar length
+    words: HashMap<usize, HashSet<String>>,
+}
+
+impl Dictionary {
+    /// Load the dictionary of the language (ex: ``en_US``) from the first
+    /// directory containing either a Hunspell dictionary or a word list
+    ///
+    /// # Errors
+    /// Returns an error if no dictionary of the language can be found or read
+    pub fn load(directories: &[PathBuf], language: &str) -> Result<Self, String> {
+        for directory in directories {
+            let dic = directory.join(format!("{language}.dic"));
+            let words = directory.join(format!("{language}.txt"));
+            if dic.is_file() {
+                let aff = fs::read(directory.join(format!("{language}.aff"))).unwrap_or_default();
+                let encoding = affix_file_encoding(&aff)?;
+                let aff = decode(&aff, encoding)?;
+                return Ok(Self::from_hunspell(&read(&dic, encoding)?, &aff));
+            }
+            if words.is_file() {
+                return Ok(Self::from_word_list(&read(&words, Encoding::Utf8)?));
+            }
+        }
+        Err(format!("No dictionary found for language '{language}'"))
+    }
+
+    /// Return the directories dictionaries are looked up in
+    #[must_use]
+    pub fn directories() -> Vec<PathBuf> {
+        DICTIONARY_DIRECTORIES
+            .iter()
+            .map(|directory| PathBuf::from(utils::expand_tilde(directory)))
+            .collect()
+    }
+
+    /// Build a dictionary from a list of words, one per line
+    #[must_use]
+    pub fn from_word_list(content: &str) -> Self {
+        let mut dictionary = Self::default();
+        dictionary.add_words(content);
+        dictionary
+    }
+
+    /// Build a dictionary from the content of Hunspell ``.dic`` and ``.aff`` files
+    #[must_use]
+    pub fn from_hunspell(dic: &str, aff: &str) -> Self {
+        let affix_file = AffixFile::parse(aff);
+        let mut dictionary = Self::default();
+        let mut lines = dic.lines().peekable();
+        // the first line holds the approximate number of words
+        if lines
+            .peek()
+            .is_some_and(|line| line.trim().parse::<usize>().is_ok())
+        {
+            lines.next();
+        }
+        for line in lines {
+            // morphological fields can follow the word and its flags
+            let Some(entry) = line.split_whitespace().next() else {
+                continue;
+            };
+            match entry.split_once('/') {
+                Some((word, flags)) => {
+                    for word in affix_file.expand(word, flags) {
+                        dictionary.insert(word);
+                    }
+                }
+                None => dictionary.insert(entry.to_string()),
+            }
+        }
+        dictionary
+    }
+
+    /// Add the words of a list, one per line
+    pub fn add_words(&mut self, content: &str) {
+        for word in content
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+        {
+            self.insert(word.to_string());
+        }
+    }
+
+    pub fn add(&mut self, word: &str) {
+        self.insert(normalize(word));
+    }
+
+    fn insert(&mut self, word: String) {
+        self.words
+            .entry(word.chars().count())
+            .or_default()
+            .insert(word);
+    }
+
+    fn has(&self, word: &str) -> bool {
+        self.words
+            .get(&word.chars().count())
+            .is_some_and(|words| words.contains(word))
+    }
+
+    /// Return whether the word is correctly spelled. Capitalized words (ex:
+    /// ``The``) and words in all caps also match their lowercase form, and
+    /// words in all caps match their capitalized form (ex: ``PARIS``).
+    #[must_use]
+    pub fn contains(&self, word: &str) -> bool {
+        let word = normalize(word);
+        if self.has(&word) {
+            return true;
+        }
+        let mut chars = word.chars();
+        let rest = chars.next().map_or("", |_| chars.as_str());
+        let all_upper = !word.chars().any(char::is_lowercase);
+        if rest.chars().any(char::is_uppercase) && !all_upper {
+            // mixed case, such as "iPhone" or "hELLO"
+            return false;
+        }
+        let lowercase = word.to_lowercase();
+        self.has(&lowercase) || all_upper && self.has(&capitalize(&lowercase))
+    }
+
+    /// Return the words of the dictionary closest to the misspelled word,
+    /// ranked by edit distance, and capitalized like the word
+    #[must_use]
+    pub fn suggestions(&self, word: &str) -> Vec<String> {
+        let word = normalize(word);
+        let lowercase = word.to_lowercase();
+        let len = lowercase.chars().count();
+        let mut letters: Vec<char> = lowercase.chars().collect();
+        letters.sort_unstable();
+        // words made of the same letters (ex: "the" for "teh") rank first among
+        // the words at the same distance
+        let is_anagram = |candidate: &str| {
+            let mut candidate_letters: Vec<char> = candidate.chars().collect();
+            candidate_letters.sort_unstable();
+            candidate_letters == letters
+        };
+        let mut suggestions: Vec<(usize, bool, &String)> =
+            (len.saturating_sub(MAX_SUGGESTION_DISTANCE)..=len + MAX_SUGGESTION_DISTANCE)
+                .filter_map(|candidate_len| self.words.get(&candidate_len))
+                .flatten()
+                .filter_map(|candidate| {
+                    let candidate_lowercase = candidate.to_lowercase();
+                    let distance = edit_distance(&lowercase, &candidate_lowercase);
+                    (distance <= MAX_SUGGESTION_DISTANCE)
+                        .then(|| (distance, !is_anagram(&candidate_lowercase), candidate))
+                })
+                .collect();
+        suggestions.sort();
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        let mut ranked: Vec<String> = vec![];
+        for (_, _, suggestion) in suggestions {
+            let suggestion = if capitalized {
+                capitalize(suggestion)
+            } else {
+                suggestion.clone()
+            };
+            if !ranked.contains(&suggestion) && suggestion != word {
+                ranked.push(suggestion);
+            }
+            if ranked.len() == MAX_SUGGESTIONS {
+                break;
+            }
+        }
+        ranked
+    }
+
+    /// Return the byte ranges of the misspelled words of the line, skipping
+    /// the words located in the provided byte ranges, as well as the words
+    /// containing digits
+    #[must_use]
+    pub fn misspelled_words(&self, line: &str, skipped: &[Range<usize>]) -> Vec<Range<usize>> {
+        line.split_word_bound_indices()
+            .filter(|(_, word)| {
+                word.chars().any(char::is_alphabetic) && !word.chars().any(|c| c.is_ascii_digit())
+            })
+            .map(|(start, word)| start..start + word.len())
+            .filter(|range| {
+                !skipped
+                    .iter()
+                    .any(|skipped| skipped.start < range.end && range.start < skipped.end)
+            })
+            .filter(|range| !self.contains(&line[range.clone()]))
+            .collect()
+    }
+}
+
+fn read(path: &Path, encoding: Encoding) -> Result<String, String> {
+    let bytes =
+        fs::read(path).map_err(|error| format!("Error reading {}: {error}", path.display()))?;
+    decode(&bytes, encoding).map_err(|error| format!("Error reading {}: {error}", path.display()))
+}
+
+/// Decode the content of a dictionary file, replacing the invalid UTF-8
+/// sequences rather than rejecting the whole dictionary
+fn decode(bytes: &[u8], encoding: Encoding) -> Result<String, String> {
+    match encoding {
+        Encoding::Utf8 => Ok(String::from_utf8_lossy(bytes).into_owned()),
+        _ => encoding.decode(bytes),
+    }
+}
+
+/// Return the encoding of the Hunspell files, as set by the ``SET`` directive
+/// of the affix file (ex: ``SET ISO8859-1``), UTF-8 being the default
+fn affix_file_encoding(aff: &[u8]) -> Result<Encoding, String> {
+    // the directive is ASCII, whatever the encoding of the rest of the file
+    String::from_utf8_lossy(aff)
+        .lines()
+        .find_map(
+            |line| match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["SET", name] => Some(
+                    name.parse::<Encoding>()
+                        .map_err(|_| format!("Unsupported dictionary encoding '{name}'")),
+                ),
+                _ => None,
+            },
+        )
+        .unwrap_or(Ok(Encoding::Utf8))
+}
+
+/// Replace the typographic apostrophes of a word by ASCII ones
+fn normalize(word: &str) -> String {
+    word.replace('\u{2019}', "'")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Return the number of character insertions, deletions, substitutions and
+/// transpositions of adjacent characters required to turn a word into another
+#[must_use]
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+#[path = "./spell_test.rs"]
+mod spell_test;
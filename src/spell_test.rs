@@ -0,0 +1,138 @@
+use crate::spell::{edit_distance, Dictionary};
+use std::fs;
+use tempfile::tempdir;
+
+const AFF: &str = "SET UTF-8
+SFX S Y 2
+SFX S y ies [^aeiou]y
+SFX S 0 s [aeiou]y
+
+SFX D Y 1
+SFX D 0 ed .
+
+PFX U Y 1
+PFX U 0 un .
+";
+
+const DIC: &str = "4
+cherry/S
+play/SDU
+test/D po:verb
+naïve
+";
+
+#[test]
+fn test_hunspell_dictionary() {
+    let dictionary = Dictionary::from_hunspell(DIC, AFF);
+    for word in [
+        "cherry", "cherries", "play", "plays", "played", "unplay", "unplayed", "unplays", "tested",
+        "naïve",
+    ] {
+        assert!(
+            dictionary.contains(word),
+            "{} should be spelled correctly",
+            word
+        );
+    }
+    for word in ["cherrys", "playies", "untest", "po:verb", "4"] {
+        assert!(!dictionary.contains(word), "{} should be misspelled", word);
+    }
+}
+
+#[test]
+fn test_hunspell_long_flags() {
+    let aff = "FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n";
+    let dictionary = Dictionary::from_hunspell("cat/Aa\n", aff);
+    assert!(dictionary.contains("cats"));
+}
+
+#[test]
+fn test_dictionary_case() {
+    let dictionary = Dictionary::from_word_list("hello\nParis\ndon't\n");
+    assert!(dictionary.contains("hello"));
+    assert!(dictionary.contains("Hello"));
+    assert!(dictionary.contains("HELLO"));
+    assert!(!dictionary.contains("hELLO"));
+    assert!(dictionary.contains("Paris"));
+    assert!(dictionary.contains("PARIS"));
+    assert!(!dictionary.contains("paris"));
+    assert!(dictionary.contains("don\u{2019}t"));
+}
+
+#[test]
+fn test_misspelled_words() {
+    let dictionary = Dictionary::from_word_list("the\ncat\nsat\non\nmat\n");
+    let line = "The cta sat on teh `mat` in 2024, on mat42.";
+    let misspelled: Vec<&str> = dictionary
+        .misspelled_words(line, std::slice::from_ref(&(19..24)))
+        .into_iter()
+        .map(|range| &line[range])
+        .collect();
+    assert_eq!(misspelled, vec!["cta", "teh", "in"]);
+}
+
+#[test]
+fn test_suggestions() {
+    let dictionary = Dictionary::from_word_list("the\nthen\ntea\nten\nhello\n");
+    assert_eq!(
+        dictionary.suggestions("teh"),
+        vec!["the", "tea", "ten", "then"]
+    );
+    assert_eq!(dictionary.suggestions("Teh")[0], "The");
+    assert!(dictionary.suggestions("xyzzy").is_empty());
+    // only the words of a similar length are looked at
+    let dictionary = Dictionary::from_word_list("a\nab\nabc\nabcd\nabcde\nabcdef\n");
+    assert_eq!(
+        dictionary.suggestions("abcxy"),
+        vec!["abc", "abcd", "abcde"]
+    );
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("teh", "the"), 1);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("café", "cafe"), 1);
+}
+
+#[test]
+fn test_load_dictionary() {
+    let dir = tempdir().unwrap();
+    let directories = vec![dir.path().join("missing"), dir.path().to_path_buf()];
+    assert_eq!(
+        Dictionary::load(&directories, "fr_FR").unwrap_err(),
+        "No dictionary found for language 'fr_FR'"
+    );
+    fs::write(dir.path().join("fr_FR.txt"), "bonjour\n").unwrap();
+    assert!(Dictionary::load(&directories, "fr_FR")
+        .unwrap()
+        .contains("bonjour"));
+    // Hunspell dictionaries take precedence over word lists
+    fs::write(dir.path().join("fr_FR.dic"), "1\nsalut/S\n").unwrap();
+    fs::write(dir.path().join("fr_FR.aff"), "SFX S Y 1\nSFX S 0 s .\n").unwrap();
+    let dictionary = Dictionary::load(&directories, "fr_FR").unwrap();
+    assert!(dictionary.contains("saluts"));
+    assert!(!dictionary.contains("bonjour"));
+}
+
+#[test]
+fn test_load_latin1_dictionary() {
+    let dir = tempdir().unwrap();
+    let directories = vec![dir.path().to_path_buf()];
+    fs::write(dir.path().join("fr_FR.dic"), b"2\nd\xe9j\xe0/S\ncaf\xe9\n").unwrap();
+    fs::write(
+        dir.path().join("fr_FR.aff"),
+        b"SET ISO8859-1\nSFX S Y 1\nSFX S 0 s .\n",
+    )
+    .unwrap();
+    let dictionary = Dictionary::load(&directories, "fr_FR").unwrap();
+    assert!(dictionary.contains("déjà"));
+    assert!(dictionary.contains("déjàs"));
+    assert!(dictionary.contains("café"));
+    fs::write(dir.path().join("fr_FR.aff"), "SET KOI8-R\n").unwrap();
+    assert_eq!(
+        Dictionary::load(&directories, "fr_FR").unwrap_err(),
+        "Unsupported dictionary encoding 'KOI8-R'"
+    );
+}
@@ -1,13 +1,18 @@
-use crate::{Console, ConsoleSize, LineNumber, Position};
-use std::cmp;
+use crate::frame::{Color, Style};
+use crate::{Console, ConsoleSize, ScreenPosition};
+use std::convert::TryFrom;
 use std::fmt;
 use std::io::{self, stdout, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
 use termion::color;
 use termion::cursor::{SteadyBar, SteadyBlock};
-use termion::event::{Event, MouseEvent};
+use termion::event::Event;
 use termion::input::{MouseTerminal, TermRead};
 use termion::raw::{IntoRawMode, RawTerminal};
-use termion::screen::{AlternateScreen, ToAlternateScreen, ToMainScreen};
+use termion::screen::AlternateScreen;
+use termion::style;
 
 #[derive(Debug, PartialEq)]
 pub struct AnsiPosition {
@@ -15,11 +20,11 @@ pub struct AnsiPosition {
     pub y: u16,
 }
 
-impl From<Position> for AnsiPosition {
+impl From<ScreenPosition> for AnsiPosition {
     #[allow(clippy::cast_possible_truncation)]
-    fn from(p: Position) -> Self {
+    fn from(p: ScreenPosition) -> Self {
         Self {
-            x: (p.x as u16).saturating_add(1),
+            x: (p.x.value as u16).saturating_add(1),
             y: (p.y as u16).saturating_add(1),
         }
     }
@@ -27,7 +32,14 @@ impl From<Position> for AnsiPosition {
 
 pub struct Terminal {
     _stdout: AlternateScreen<MouseTerminal<RawTerminal<std::io::Stdout>>>,
-    stdin_event_stream: termion::input::Events<io::Stdin>,
+    /// Events read from stdin by a dedicated thread, so that they can be waited
+    /// for with a timeout
+    stdin_events: Receiver<Result<Event, io::Error>>,
+}
+
+/// Error returned when the stdin reading thread has stopped
+fn stdin_closed() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "stdin was closed")
 }
 
 impl fmt::Debug for Terminal {
@@ -41,10 +53,6 @@ impl Console for Terminal {
         print!("{}", termion::clear::All);
     }
 
-    fn clear_current_line(&self) {
-        print!("{}", termion::clear::CurrentLine);
-    }
-
     /// # Errors
     ///
     /// Returns an error if stdout can't be flushed
@@ -56,12 +64,17 @@ impl Console for Terminal {
     ///
     /// Returns an error if a event can't be read
     fn read_event(&mut self) -> Result<Event, std::io::Error> {
-        loop {
-            let opt_key = self.stdin_event_stream.next();
-            // at that point, event is a Result<Event, Error>, as the Option was unwrapped
-            if let Some(event) = opt_key {
-                return event;
-            }
+        self.stdin_events.recv().map_err(|_| stdin_closed())?
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if a event can't be read
+    fn read_event_timeout(&mut self, timeout: Duration) -> Result<Option<Event>, std::io::Error> {
+        match self.stdin_events.recv_timeout(timeout) {
+            Ok(event) => event.map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(stdin_closed()),
         }
     }
 
@@ -73,110 +86,67 @@ impl Console for Terminal {
         print!("{}", termion::cursor::Show);
     }
 
-    fn set_bg_color(&self, color: color::Rgb) {
-        print!("{}", color::Bg(color));
-    }
-
-    fn reset_bg_color(&self) {
-        print!("{}", color::Bg(color::Reset));
-    }
-
-    fn set_fg_color(&self, color: color::Rgb) {
-        print!("{}", color::Fg(color));
-    }
-
-    fn reset_fg_color(&self) {
-        print!("{}", color::Fg(color::Reset));
-    }
-
-    fn to_alternate_screen(&self) {
-        print!("{}", ToAlternateScreen);
-    }
-
-    fn to_main_screen(&self) {
-        print!("{}", ToMainScreen);
-    }
-
     fn clear_all(&self) {
         print!("{}", termion::clear::All);
     }
 
-    fn size(&self) -> ConsoleSize {
-        ConsoleSize::from(termion::terminal_size().unwrap_or_default())
-    }
-
-    fn text_area_size(&self) -> ConsoleSize {
-        self.size().restrict_to_text_area()
-    }
-
-    fn middle_of_screen_line_number(&self) -> LineNumber {
-        LineNumber::new(self.text_area_size().height as usize / 2)
+    fn move_cursor_to(&self, x: u16, y: u16) {
+        print!(
+            "{}",
+            termion::cursor::Goto(x.saturating_add(1), y.saturating_add(1))
+        );
     }
 
-    fn bottom_of_screen_line_number(&self) -> LineNumber {
-        LineNumber::new(self.text_area_size().height as usize)
+    fn write(&self, text: &str) {
+        print!("{text}");
     }
 
-    fn set_cursor_position_in_text_area(&self, position: &Position, mut row_prefix_length: u8) {
-        let ansi_position = AnsiPosition::from(*position);
-        // hiding the fact that the terminal position is 1-based, while preventing an overflow
-        row_prefix_length += if row_prefix_length > 0 { 1 } else { 0 };
-        let text_area_size = self.size().restrict_to_text_area();
-        print!(
-            "{}",
-            termion::cursor::Goto(
-                cmp::min(
-                    ansi_position.x.saturating_add(row_prefix_length.into()),
-                    text_area_size.width
-                ),
-                cmp::min(ansi_position.y, text_area_size.height)
-            )
-        );
+    fn set_style(&self, text_style: Style) {
+        print!("{}", style::Reset);
+        if text_style.bold {
+            print!("{}", style::Bold);
+        }
+        if text_style.underline {
+            print!("{}", style::Underline);
+        }
+        match text_style.fg {
+            Color::Default => (),
+            Color::Ansi(value) => print!("{}", color::Fg(color::AnsiValue(value))),
+            Color::Rgb(r, g, b) => print!("{}", color::Fg(color::Rgb(r, g, b))),
+        }
+        match text_style.bg {
+            Color::Default => (),
+            Color::Ansi(value) => print!("{}", color::Bg(color::AnsiValue(value))),
+            Color::Rgb(r, g, b) => print!("{}", color::Bg(color::Rgb(r, g, b))),
+        }
     }
 
-    fn set_cursor_position_anywhere(&self, position: &Position) {
-        let ansi_position = AnsiPosition::from(*position);
-        let console_size = self.size();
+    fn scroll_region(&self, top: u16, bottom: u16, lines: i32) {
+        // restrict the scrolling to the region, then reset the scrolling region
         print!(
-            "{}",
-            termion::cursor::Goto(
-                cmp::min(ansi_position.x, console_size.width),
-                cmp::min(
-                    ansi_position.y.saturating_add(2), // delta_y = 2 to account for the last 2 lines (status + message bars)
-                    console_size.height.saturating_add(2)
-                )
-            )
+            "\x1b[{};{}r",
+            top.saturating_add(1),
+            bottom.saturating_add(1)
         );
-    }
-
-    #[must_use]
-    fn get_cursor_index_from_mouse_event(
-        &self,
-        mouse_event: MouseEvent,
-        row_prefix_length: u8,
-    ) -> Position {
-        if let MouseEvent::Press(_, x, y) = mouse_event {
-            let offset_adjustment: u8 = if row_prefix_length > 0 {
-                row_prefix_length.saturating_add(1)
-            } else {
-                0
-            };
-            let ansi_position = AnsiPosition {
-                x: x.saturating_sub(u16::from(offset_adjustment)),
-                y,
-            };
-            Position::from(ansi_position)
+        let count = u16::try_from(lines.unsigned_abs()).unwrap_or(u16::MAX);
+        if lines > 0 {
+            print!("{}", termion::scroll::Up(count));
         } else {
-            Position::top_left()
+            print!("{}", termion::scroll::Down(count));
         }
+        print!("\x1b[r");
+    }
+
+    fn size(&self) -> ConsoleSize {
+        ConsoleSize::from(termion::terminal_size().unwrap_or_default())
     }
 
     fn set_cursor_as_steady_bar(&self) {
-        print!("{}", SteadyBar);
+        print!("{SteadyBar}");
     }
 
     fn set_cursor_as_steady_block(&self) {
-        print!("{}", SteadyBlock);
+        print!("{SteadyBlock}");
     }
 }
 
@@ -185,13 +155,22 @@ impl Terminal {
     ///
     /// will return an error if the terminal size can't be acquired
     /// or if the stdout cannot be put into raw mode.
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Result<Self, std::io::Error> {
         let mut term_stdout = stdout();
         write!(term_stdout, "{}", termion::cursor::Goto(1, 1))?;
         term_stdout.flush()?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for event in io::stdin().events() {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
         Ok(Self {
             _stdout: AlternateScreen::from(MouseTerminal::from(term_stdout.into_raw_mode()?)),
-            stdin_event_stream: io::stdin().events(),
+            stdin_events: receiver,
         })
     }
 }
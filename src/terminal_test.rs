@@ -1,8 +1,12 @@
-use crate::editor::Position;
+use crate::editor::ScreenPosition;
 use crate::terminal::AnsiPosition;
+use crate::Column;
 
 #[test]
-fn test_position_to_ansi_position_conversion() {
-    let pos = Position { x: 10, y: 5 };
+fn test_screen_position_to_ansi_position_conversion() {
+    let pos = ScreenPosition {
+        x: Column::new(10),
+        y: 5,
+    };
     assert_eq!(AnsiPosition::from(pos), AnsiPosition { x: 11, y: 6 });
 }
@@ -9,19 +9,18 @@ use termion::{color, style};
 /// Can panic if the file can't be written to
 pub fn log(s: &str) {
     let mut file = fs::OpenOptions::new()
-        .write(true)
         .create(true)
         .append(true)
         .open("bo.log")
         .unwrap();
-    if let Err(e) = writeln!(file, "{}", s) {
-        eprintln!("Couldn't write to file: {}", e);
+    if let Err(e) = writeln!(file, "{s}") {
+        eprintln!("Couldn't write to file: {e}");
     }
 }
 
 pub fn zfill(s: &str, fill_by: &str, size: usize) -> String {
     if size == 0 {
-        return "".to_string();
+        return String::new();
     }
     format!("{}{}", fill_by.repeat(size - s.len()), s)
 }
@@ -37,6 +36,36 @@ pub fn expand_tilde(s: &str) -> String {
     s.replace('~', env!("HOME"))
 }
 
+/// List the entries of the directory the provided (possibly partial) path points to,
+/// sorted by name. Directories are suffixed with a ``/``, and hidden entries are
+/// only listed if the partial file name starts with a dot.
+#[must_use]
+pub fn path_completions(partial_path: &str) -> Vec<String> {
+    let expanded = expand_tilde(partial_path);
+    let (directory, file_prefix) = match expanded.rfind('/') {
+        Some(i) => (&expanded[..=i], &expanded[i + 1..]),
+        None => ("./", expanded.as_str()),
+    };
+    let Ok(entries) = fs::read_dir(directory) else {
+        return vec![];
+    };
+    let mut completions: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let mut name = entry.file_name().into_string().ok()?;
+            if name.starts_with('.') && !file_prefix.starts_with('.') {
+                return None;
+            }
+            if entry.path().is_dir() {
+                name.push('/');
+            }
+            Some(name)
+        })
+        .collect();
+    completions.sort();
+    completions
+}
+
 #[must_use]
 pub fn git_head_short_ref() -> String {
     let git_commit = Command::new("git")
@@ -1,5 +1,7 @@
-use crate::utils::{expand_tilde, zfill};
+use crate::utils::{expand_tilde, path_completions, zfill};
 use std::env;
+use std::fs;
+use tempfile::tempdir;
 
 #[test]
 fn test_zfill() {
@@ -13,3 +15,21 @@ fn test_expand_tilde() {
     assert_eq!(expand_tilde("~/code"), format!("{}/code", env!("HOME")));
     assert_eq!(expand_tilde("/~code"), "/~code");
 }
+
+#[test]
+fn test_path_completions() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("notes")).unwrap();
+    fs::write(dir.path().join("novel.md"), "").unwrap();
+    fs::write(dir.path().join(".hidden"), "").unwrap();
+    let dir_path = dir.path().to_str().unwrap();
+    assert_eq!(
+        path_completions(&format!("{dir_path}/no")),
+        vec!["notes/", "novel.md"]
+    );
+    assert_eq!(
+        path_completions(&format!("{dir_path}/.h")),
+        vec![".hidden", "notes/", "novel.md"]
+    );
+    assert!(path_completions(&format!("{dir_path}/nope/")).is_empty());
+}
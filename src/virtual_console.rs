@@ -0,0 +1,191 @@
+/// This module implements a headless console, rendering into an in-memory
+/// grid of cells instead of a terminal. Events are read from a queue filled
+/// beforehand, which allows driving the editor and asserting on the exact
+/// content of the screen, such as in tests.
+use crate::frame::{Cell, Frame, Style};
+use crate::{Console, ConsoleSize};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::rc::Rc;
+use std::time::Duration;
+use termion::event::Event;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    Block,
+    Bar,
+}
+
+#[derive(Debug)]
+struct Screen {
+    frame: Frame,
+    cursor: (u16, u16),
+    cursor_visible: bool,
+    cursor_shape: CursorShape,
+    style: Style,
+}
+
+/// A console keeping its screen in memory. Clones share the same screen and
+/// event queue, so that a clone can be inspected after the original one was
+/// handed over to the editor.
+#[derive(Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct VirtualConsole {
+    screen: Rc<RefCell<Screen>>,
+    events: Rc<RefCell<VecDeque<Event>>>,
+}
+
+impl Default for VirtualConsole {
+    fn default() -> Self {
+        let size = ConsoleSize::default();
+        Self::new(size.width, size.height)
+    }
+}
+
+impl fmt::Debug for VirtualConsole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VirtualConsole").finish_non_exhaustive()
+    }
+}
+
+impl VirtualConsole {
+    #[must_use]
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            screen: Rc::new(RefCell::new(Screen {
+                frame: Frame::new(width, height),
+                cursor: (0, 0),
+                cursor_visible: true,
+                cursor_shape: CursorShape::Block,
+                style: Style::default(),
+            })),
+            events: Rc::new(RefCell::new(VecDeque::new())),
+        }
+    }
+
+    /// Queue an event, to be returned by a following read
+    pub fn push_event(&self, event: Event) {
+        self.events.borrow_mut().push_back(event);
+    }
+
+    /// Return the text of a screen line, trailing spaces excluded
+    #[must_use]
+    pub fn line(&self, y: u16) -> String {
+        self.screen.borrow().frame.line(y)
+    }
+
+    /// Return the text of the whole screen, one line per screen line
+    #[must_use]
+    pub fn contents(&self) -> String {
+        let screen = self.screen.borrow();
+        (0..screen.frame.height)
+            .map(|y| screen.frame.line(y))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[must_use]
+    pub fn cell(&self, x: u16, y: u16) -> Option<Cell> {
+        self.screen.borrow().frame.cell(x, y).cloned()
+    }
+
+    /// Return the 0-based position of the cursor on the screen
+    #[must_use]
+    pub fn cursor_position(&self) -> (u16, u16) {
+        self.screen.borrow().cursor
+    }
+
+    #[must_use]
+    pub fn is_cursor_visible(&self) -> bool {
+        self.screen.borrow().cursor_visible
+    }
+
+    #[must_use]
+    pub fn cursor_shape(&self) -> CursorShape {
+        self.screen.borrow().cursor_shape
+    }
+}
+
+impl Console for VirtualConsole {
+    /// # Errors
+    ///
+    /// Returns an error if no event is queued
+    fn read_event(&mut self) -> Result<Event, Error> {
+        self.events
+            .borrow_mut()
+            .pop_front()
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "no more events"))
+    }
+
+    fn read_event_timeout(&mut self, _timeout: Duration) -> Result<Option<Event>, Error> {
+        Ok(self.events.borrow_mut().pop_front())
+    }
+
+    fn clear_screen(&self) {
+        self.clear_all();
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn hide_cursor(&self) {
+        self.screen.borrow_mut().cursor_visible = false;
+    }
+
+    fn show_cursor(&self) {
+        self.screen.borrow_mut().cursor_visible = true;
+    }
+
+    fn clear_all(&self) {
+        let mut screen = self.screen.borrow_mut();
+        screen.frame = Frame::new(screen.frame.width, screen.frame.height);
+    }
+
+    fn move_cursor_to(&self, x: u16, y: u16) {
+        let mut screen = self.screen.borrow_mut();
+        // like a terminal, keep the cursor within the screen
+        screen.cursor = (
+            x.min(screen.frame.width.saturating_sub(1)),
+            y.min(screen.frame.height.saturating_sub(1)),
+        );
+    }
+
+    fn write(&self, text: &str) {
+        let mut screen = self.screen.borrow_mut();
+        let (x, y) = screen.cursor;
+        let style = screen.style;
+        let x = screen.frame.put_str(x, y, text, style);
+        screen.cursor = (x.min(screen.frame.width.saturating_sub(1)), y);
+    }
+
+    fn set_style(&self, style: Style) {
+        self.screen.borrow_mut().style = style;
+    }
+
+    fn scroll_region(&self, top: u16, bottom: u16, lines: i32) {
+        self.screen.borrow_mut().frame.scroll(top, bottom, lines);
+    }
+
+    fn size(&self) -> ConsoleSize {
+        let screen = self.screen.borrow();
+        ConsoleSize {
+            height: screen.frame.height,
+            width: screen.frame.width,
+        }
+    }
+
+    fn set_cursor_as_steady_bar(&self) {
+        self.screen.borrow_mut().cursor_shape = CursorShape::Bar;
+    }
+
+    fn set_cursor_as_steady_block(&self) {
+        self.screen.borrow_mut().cursor_shape = CursorShape::Block;
+    }
+}
+
+#[cfg(test)]
+#[path = "./virtual_console_test.rs"]
+mod virtual_console_test;
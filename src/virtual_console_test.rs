@@ -0,0 +1,91 @@
+use crate::frame::{Color, Style};
+use crate::virtual_console::CursorShape;
+use crate::{Column, Console, ScreenPosition, VirtualConsole};
+use termion::event::{Event, Key};
+
+#[test]
+fn test_virtual_console_write() {
+    let console = VirtualConsole::new(10, 3);
+    console.move_cursor_to(2, 1);
+    console.write("Hello");
+    console.write("!");
+    assert_eq!(console.contents(), "\n  Hello!\n");
+    assert_eq!(console.cursor_position(), (8, 1));
+
+    let style = Style {
+        fg: Color::Ansi(1),
+        ..Style::default()
+    };
+    console.set_style(style);
+    console.move_cursor_to(0, 0);
+    console.write("ab");
+    assert_eq!(console.cell(1, 0).unwrap().style, style);
+    assert_eq!(console.cell(2, 0).unwrap().style, Style::default());
+
+    console.clear_all();
+    assert_eq!(console.contents(), "\n\n");
+}
+
+#[test]
+fn test_virtual_console_clones_share_the_screen() {
+    let console = VirtualConsole::new(10, 3);
+    let mut boxed: Box<dyn Console> = Box::new(console.clone());
+    boxed.write("Hello");
+    boxed.hide_cursor();
+    boxed.set_cursor_as_steady_bar();
+    assert_eq!(console.line(0), "Hello");
+    assert!(!console.is_cursor_visible());
+    assert_eq!(console.cursor_shape(), CursorShape::Bar);
+
+    console.push_event(Event::Key(Key::Char('a')));
+    assert_eq!(boxed.read_event().unwrap(), Event::Key(Key::Char('a')));
+    assert!(boxed.read_event().is_err());
+}
+
+#[test]
+fn test_virtual_console_scroll_region() {
+    let console = VirtualConsole::new(3, 4);
+    for y in 0..4 {
+        console.move_cursor_to(0, y);
+        console.write(&y.to_string());
+    }
+    console.scroll_region(0, 2, 1);
+    assert_eq!(console.contents(), "1\n2\n\n3");
+}
+
+#[test]
+fn test_virtual_console_cursor_positioning() {
+    let console = VirtualConsole::new(20, 10);
+    console.set_cursor_position_in_text_area(
+        &ScreenPosition {
+            x: Column::new(2),
+            y: 3,
+        },
+        0,
+    );
+    assert_eq!(console.cursor_position(), (2, 3));
+    // the line numbers are followed by a space
+    console.set_cursor_position_in_text_area(
+        &ScreenPosition {
+            x: Column::new(2),
+            y: 3,
+        },
+        4,
+    );
+    assert_eq!(console.cursor_position(), (7, 3));
+    // the cursor is kept within the text area
+    console.set_cursor_position_in_text_area(
+        &ScreenPosition {
+            x: Column::new(2),
+            y: 30,
+        },
+        0,
+    );
+    assert_eq!(console.cursor_position(), (2, 7));
+    // positioning the cursor in the message bar
+    console.set_cursor_position_anywhere(&ScreenPosition {
+        x: Column::new(4),
+        y: 10,
+    });
+    assert_eq!(console.cursor_position(), (4, 9));
+}